@@ -1,23 +1,422 @@
-use chrono::Utc;
-use rusqlite::{params, params_from_iter, Connection, OptionalExtension};
-use serde::{Deserialize, Serialize};
-use serde_json::Value;
-use std::collections::{BTreeMap, HashSet};
+use chrono::{DateTime, Datelike, Utc};
+use pulldown_cmark::{html, Options, Parser};
+use rusqlite::types::Value as SqlValue;
+use rusqlite::{params, params_from_iter, Connection, OptionalExtension, TransactionBehavior};
+use serde::ser::SerializeStruct;
+use serde::{Deserialize, Serialize, Serializer};
+use serde_json::{Map, Value};
+use std::collections::hash_map::RandomState;
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::fmt;
 use std::fs;
+use std::hash::{BuildHasher, Hasher};
+use std::io::{BufRead, BufReader, BufWriter, Read, Write};
 use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
 use tauri::{AppHandle, Emitter, Manager};
+use tauri_plugin_clipboard_manager::ClipboardExt;
 use tauri_plugin_global_shortcut::{GlobalShortcutExt, ShortcutState};
 
 const DEFAULT_GLOBAL_SHORTCUT: &str = "CommandOrControl+Shift+K";
 const GLOBAL_SHORTCUT_SETTING_KEY: &str = "global_shortcut";
 const GLOBAL_SHORTCUT_EVENT: &str = "global-shortcut-triggered";
+const IMPORT_PROGRESS_EVENT: &str = "import-progress";
+const IMPORT_FINISHED_EVENT: &str = "import-finished";
+const IMPORT_PROGRESS_INTERVAL: usize = 25;
+const PROMPT_BATCH_EVENT: &str = "prompt-batch";
+const PROMPT_DONE_EVENT: &str = "prompt-done";
+const DATABASE_BUSY_TIMEOUT: Duration = Duration::from_millis(5000);
+const DELETE_TOKEN_TTL: Duration = Duration::from_secs(60);
+const MAX_BUSY_ATTEMPTS: u64 = 3;
+const BUSY_RETRY_DELAY_MS: u64 = 100;
+const USAGE_LOG_LIMIT_SETTING_KEY: &str = "usage_log_max_per_prompt";
+const DEFAULT_USAGE_LOG_LIMIT: i64 = 1000;
+const OUTPUT_TEXT_LIMIT_SETTING_KEY: &str = "usage_output_max_chars";
+const DEFAULT_OUTPUT_TEXT_LIMIT: i64 = 20000;
+const OUTPUT_TRUNCATION_MARKER: &str = "\n…[内容已截断]";
+const CONTENT_COMPRESSION_SETTING_KEY: &str = "content_compression_threshold";
+const CONTENT_COMPRESSION_LEVEL: i32 = 3;
+const CONTENT_NORMALIZATION_SETTING_KEY: &str = "normalize_prompt_content";
+const PINNED_TAGS_SETTING_KEY: &str = "pinned_tags";
+const MAX_TAGS_SETTING_KEY: &str = "max_tags_per_prompt";
+const AUTO_BACKUP_ENABLED_SETTING_KEY: &str = "auto_backup_enabled";
+const AUTO_BACKUP_INTERVAL_SETTING_KEY: &str = "auto_backup_interval_days";
+const AUTO_BACKUP_KEEP_SETTING_KEY: &str = "auto_backup_keep";
+const LAST_BACKUP_AT_SETTING_KEY: &str = "last_backup_at";
+const DEFAULT_AUTO_BACKUP_INTERVAL_DAYS: i64 = 7;
+const DEFAULT_AUTO_BACKUP_KEEP: i64 = 5;
+const BACKUP_DIR_NAME: &str = "backups";
+const BACKUP_FILE_PREFIX: &str = "prompt-library-";
+const DEFAULT_MAX_TAGS: i64 = 30;
+const MAX_DESCRIPTION_CHARS: usize = 500;
+const RECOMMENDED_TITLE_CHARS: usize = 120;
+const MAX_ICON_CHARS: usize = 8;
+const MAX_AUTHOR_CHARS: usize = 100;
+const MAX_CATEGORY_NAME_CHARS: usize = 50;
+const UNCATEGORIZED_CATEGORY_NAME: &str = "未分类";
+const AWESOME_IMPORT_TAG: &str = "imported";
+const EXPORT_SCHEMA_VERSION: u64 = 3;
+const LEGACY_EXPORT_SCHEMA_VERSION: u64 = 1;
+const MAX_COLLECTION_NAME_CHARS: usize = 100;
+
+const MAX_RECENT_PROMPTS: i64 = 100;
+const MAX_META_KEYS_PER_PROMPT: usize = 32;
+const MAX_META_KEY_CHARS: usize = 64;
+const MAX_PRESET_NAME_CHARS: usize = 50;
+const MAX_SNIPPET_NAME_CHARS: usize = 50;
+const MAX_TEST_CASE_NAME_CHARS: usize = 50;
+const MAX_SNIPPET_DEPTH: usize = 8;
+const LINT_TODO_MARKERS: &[&str] = &["TODO", "FIXME"];
+const LINT_EXAMPLE_HINTS: &[&str] = &["在此", "此处", "填写", "请输入", "your ", "insert "];
+const LINT_EXAMPLE_MAX_CHARS: usize = 40;
+const TEMPLATE_ESCAPE_HINT: &str = "如需输出字面量 {{，请写作 \\{{";
+const REQUIRED_TABLES: &[&str] = &[
+  "prompts",
+  "prompt_versions",
+  "usage_logs",
+  "app_settings",
+  "categories",
+  "collections",
+  "collection_prompts",
+];
+const FUZZY_SEARCH_THRESHOLD: f64 = 0.3;
+const MAX_FILE_STEM_CHARS: usize = 60;
+const MAX_IMPORT_CONTENT_BYTES: usize = 1024 * 1024;
+const DEFAULT_PAGE_SIZE: i64 = 50;
+const MAX_PAGE_SIZE: i64 = 500;
+
+const COLLECTION_COLUMNS: &str = "
+  collections.id, collections.name, collections.parent_id,
+  (SELECT COUNT(*) FROM collection_prompts WHERE collection_prompts.collection_id = collections.id),
+  collections.created_at, collections.updated_at
+";
+
+const PROMPT_COLUMNS: &str = "
+  id, title, content, tags, is_favorite, score_avg, score_count, created_at, updated_at,
+  description, source_url, model, language, params, icon, category_id,
+  (SELECT json_group_object(key, value) FROM prompt_meta WHERE prompt_meta.prompt_id = prompts.id),
+  content_compressed, variables_schema, last_used_at, author, uuid
+";
+
+const SCHEMA_MIGRATIONS: &[&str] = &[
+  "ALTER TABLE prompts ADD COLUMN description TEXT NOT NULL DEFAULT '';",
+  "ALTER TABLE prompts ADD COLUMN source_url TEXT NOT NULL DEFAULT '';",
+  "
+  ALTER TABLE prompts ADD COLUMN model TEXT NOT NULL DEFAULT '';
+  CREATE INDEX IF NOT EXISTS idx_prompts_model ON prompts(model);
+  ",
+  "
+  ALTER TABLE prompts ADD COLUMN language TEXT NOT NULL DEFAULT '';
+  CREATE INDEX IF NOT EXISTS idx_prompts_language ON prompts(language);
+  ",
+  "ALTER TABLE prompts ADD COLUMN params TEXT NOT NULL DEFAULT '{}';",
+  "ALTER TABLE prompts ADD COLUMN icon TEXT NOT NULL DEFAULT '';",
+  "
+  CREATE TABLE IF NOT EXISTS prompt_comments (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    prompt_id INTEGER NOT NULL,
+    body TEXT NOT NULL,
+    created_at TEXT NOT NULL,
+    FOREIGN KEY(prompt_id) REFERENCES prompts(id) ON DELETE CASCADE
+  );
+  CREATE INDEX IF NOT EXISTS idx_prompt_comments_prompt_id ON prompt_comments(prompt_id);
+  ",
+  "
+  CREATE TABLE IF NOT EXISTS categories (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    name TEXT NOT NULL UNIQUE COLLATE NOCASE,
+    created_at TEXT NOT NULL,
+    updated_at TEXT NOT NULL
+  );
+  ALTER TABLE prompts ADD COLUMN category_id INTEGER REFERENCES categories(id) ON DELETE SET NULL;
+  CREATE INDEX IF NOT EXISTS idx_prompts_category_id ON prompts(category_id);
+  ",
+  "
+  CREATE TABLE IF NOT EXISTS prompt_meta (
+    prompt_id INTEGER NOT NULL,
+    key TEXT NOT NULL,
+    value TEXT NOT NULL,
+    PRIMARY KEY (prompt_id, key),
+    FOREIGN KEY(prompt_id) REFERENCES prompts(id) ON DELETE CASCADE
+  );
+  CREATE INDEX IF NOT EXISTS idx_prompt_meta_key_value ON prompt_meta(key, value);
+  ",
+  "ALTER TABLE prompts ADD COLUMN example_output TEXT NOT NULL DEFAULT '';",
+  "
+  CREATE TABLE IF NOT EXISTS collections (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    name TEXT NOT NULL,
+    created_at TEXT NOT NULL,
+    updated_at TEXT NOT NULL
+  );
+  CREATE TABLE IF NOT EXISTS collection_prompts (
+    collection_id INTEGER NOT NULL,
+    prompt_id INTEGER NOT NULL,
+    added_at TEXT NOT NULL,
+    PRIMARY KEY (collection_id, prompt_id),
+    FOREIGN KEY(collection_id) REFERENCES collections(id) ON DELETE CASCADE,
+    FOREIGN KEY(prompt_id) REFERENCES prompts(id) ON DELETE CASCADE
+  );
+  CREATE INDEX IF NOT EXISTS idx_collection_prompts_prompt_id ON collection_prompts(prompt_id);
+  ",
+  "
+  ALTER TABLE collections ADD COLUMN parent_id INTEGER REFERENCES collections(id) ON DELETE CASCADE;
+  CREATE INDEX IF NOT EXISTS idx_collections_parent_id ON collections(parent_id);
+  ",
+  "
+  CREATE VIRTUAL TABLE IF NOT EXISTS prompts_fts USING fts5(title, description, content, tags);
+  INSERT INTO prompts_fts(rowid, title, description, content, tags)
+    SELECT id, title, description, content, tags FROM prompts;
+  CREATE TRIGGER IF NOT EXISTS prompts_fts_insert AFTER INSERT ON prompts BEGIN
+    INSERT INTO prompts_fts(rowid, title, description, content, tags)
+      VALUES (new.id, new.title, new.description, new.content, new.tags);
+  END;
+  CREATE TRIGGER IF NOT EXISTS prompts_fts_update AFTER UPDATE ON prompts BEGIN
+    DELETE FROM prompts_fts WHERE rowid = old.id;
+    INSERT INTO prompts_fts(rowid, title, description, content, tags)
+      VALUES (new.id, new.title, new.description, new.content, new.tags);
+  END;
+  CREATE TRIGGER IF NOT EXISTS prompts_fts_delete AFTER DELETE ON prompts BEGIN
+    DELETE FROM prompts_fts WHERE rowid = old.id;
+  END;
+  ",
+  "
+  CREATE TABLE IF NOT EXISTS smart_collections (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    name TEXT NOT NULL,
+    filter TEXT NOT NULL DEFAULT '{}',
+    created_at TEXT NOT NULL,
+    updated_at TEXT NOT NULL
+  );
+  ",
+  "
+  ALTER TABLE prompts ADD COLUMN content_compressed INTEGER NOT NULL DEFAULT 0;
+  DROP TRIGGER IF EXISTS prompts_fts_insert;
+  DROP TRIGGER IF EXISTS prompts_fts_update;
+  CREATE TRIGGER prompts_fts_insert AFTER INSERT ON prompts BEGIN
+    INSERT INTO prompts_fts(rowid, title, description, content, tags)
+      VALUES (new.id, new.title, new.description, CASE WHEN new.content_compressed = 1 THEN '' ELSE new.content END, new.tags);
+  END;
+  CREATE TRIGGER prompts_fts_update AFTER UPDATE ON prompts BEGIN
+    DELETE FROM prompts_fts WHERE rowid = old.id;
+    INSERT INTO prompts_fts(rowid, title, description, content, tags)
+      VALUES (new.id, new.title, new.description, CASE WHEN new.content_compressed = 1 THEN '' ELSE new.content END, new.tags);
+  END;
+  ",
+  "
+  ALTER TABLE collections ADD COLUMN position INTEGER NOT NULL DEFAULT 0;
+  ALTER TABLE collection_prompts ADD COLUMN position INTEGER NOT NULL DEFAULT 0;
+  CREATE INDEX IF NOT EXISTS idx_collection_prompts_position ON collection_prompts(collection_id, position);
+  ",
+  "ALTER TABLE prompts ADD COLUMN variables_schema TEXT NOT NULL DEFAULT '[]';",
+  "
+  CREATE TABLE IF NOT EXISTS variable_presets (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    prompt_id INTEGER NOT NULL REFERENCES prompts(id) ON DELETE CASCADE,
+    name TEXT NOT NULL COLLATE NOCASE,
+    values_json TEXT NOT NULL DEFAULT '{}',
+    created_at TEXT NOT NULL,
+    updated_at TEXT NOT NULL,
+    UNIQUE (prompt_id, name)
+  );
+  ",
+  "
+  CREATE TABLE IF NOT EXISTS snippets (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    name TEXT NOT NULL UNIQUE COLLATE NOCASE,
+    content TEXT NOT NULL,
+    created_at TEXT NOT NULL,
+    updated_at TEXT NOT NULL
+  );
+  ",
+  "
+  ALTER TABLE prompts ADD COLUMN last_used_at TEXT;
+  UPDATE prompts SET last_used_at = (
+    SELECT MAX(used_at) FROM usage_logs WHERE usage_logs.prompt_id = prompts.id
+  );
+  ",
+  "
+  CREATE TABLE IF NOT EXISTS prompt_test_cases (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    prompt_id INTEGER NOT NULL REFERENCES prompts(id) ON DELETE CASCADE,
+    name TEXT NOT NULL COLLATE NOCASE,
+    variables_json TEXT NOT NULL DEFAULT '{}',
+    created_at TEXT NOT NULL,
+    updated_at TEXT NOT NULL,
+    UNIQUE (prompt_id, name)
+  );
+  ",
+  "ALTER TABLE usage_logs ADD COLUMN output_original_length INTEGER;",
+  "ALTER TABLE prompts ADD COLUMN author TEXT NOT NULL DEFAULT '';",
+  "ALTER TABLE prompt_versions ADD COLUMN content_hash TEXT NOT NULL DEFAULT '';",
+  "
+  ALTER TABLE prompts ADD COLUMN uuid TEXT NOT NULL DEFAULT '';
+  UPDATE prompts SET uuid = lower(hex(randomblob(4))) || '-' || lower(hex(randomblob(2))) || '-4' || substr(lower(hex(randomblob(2))), 2) || '-' || substr('89ab', 1 + (abs(random()) % 4), 1) || substr(lower(hex(randomblob(2))), 2) || '-' || lower(hex(randomblob(6)));
+  CREATE UNIQUE INDEX IF NOT EXISTS idx_prompts_uuid ON prompts(uuid) WHERE uuid <> '';
+  CREATE TRIGGER IF NOT EXISTS prompts_assign_uuid AFTER INSERT ON prompts WHEN new.uuid = '' BEGIN
+    UPDATE prompts SET uuid = lower(hex(randomblob(4))) || '-' || lower(hex(randomblob(2))) || '-4' || substr(lower(hex(randomblob(2))), 2) || '-' || substr('89ab', 1 + (abs(random()) % 4), 1) || substr(lower(hex(randomblob(2))), 2) || '-' || lower(hex(randomblob(6))) WHERE id = new.id;
+  END;
+  CREATE TABLE IF NOT EXISTS deleted_prompts (
+    uuid TEXT PRIMARY KEY,
+    prompt_id INTEGER NOT NULL,
+    title TEXT NOT NULL,
+    deleted_at TEXT NOT NULL
+  );
+  CREATE INDEX IF NOT EXISTS idx_deleted_prompts_deleted_at ON deleted_prompts(deleted_at);
+  CREATE TRIGGER IF NOT EXISTS prompts_log_deletion AFTER DELETE ON prompts WHEN old.uuid <> '' BEGIN
+    INSERT OR REPLACE INTO deleted_prompts (uuid, prompt_id, title, deleted_at)
+      VALUES (old.uuid, old.id, old.title, strftime('%Y-%m-%dT%H:%M:%f+00:00', 'now'));
+  END;
+  ",
+  "
+  UPDATE smart_collections
+  SET filter = json_set(
+    json_remove(filter, '$.metaKey', '$.metaValue'),
+    '$.metadataFilters',
+    json_insert(
+      COALESCE(json_extract(filter, '$.metadataFilters'), json('[]')),
+      '$[#]',
+      json_array(json_extract(filter, '$.metaKey'), COALESCE(json_extract(filter, '$.metaValue'), ''))
+    )
+  )
+  WHERE json_valid(filter) AND COALESCE(json_extract(filter, '$.metaKey'), '') <> '';
+  ",
+  "
+  DROP TRIGGER IF EXISTS prompts_fts_update;
+  CREATE TRIGGER prompts_fts_update AFTER UPDATE OF title, description, content, tags, content_compressed ON prompts BEGIN
+    DELETE FROM prompts_fts WHERE rowid = old.id;
+    INSERT INTO prompts_fts(rowid, title, description, content, tags)
+      VALUES (new.id, new.title, new.description, CASE WHEN new.content_compressed = 1 THEN '' ELSE new.content END, new.tags);
+  END;
+  ",
+  "
+  DROP TRIGGER IF EXISTS prompts_fts_update;
+  CREATE TRIGGER prompts_fts_update AFTER UPDATE OF title, description, content, tags, content_compressed ON prompts BEGIN
+    UPDATE prompts_fts
+    SET title = new.title, description = new.description, tags = new.tags,
+        content = CASE WHEN new.content_compressed = 1 THEN content ELSE new.content END
+    WHERE rowid = old.id;
+  END;
+  ",
+  "
+  ALTER TABLE prompts ADD COLUMN direct_rating_sum REAL NOT NULL DEFAULT 0;
+  ALTER TABLE prompts ADD COLUMN direct_rating_count INTEGER NOT NULL DEFAULT 0;
+  UPDATE prompts
+  SET direct_rating_count = MAX(score_count - (SELECT COUNT(rating) FROM usage_logs WHERE usage_logs.prompt_id = prompts.id), 0),
+      direct_rating_sum = CASE
+        WHEN score_count > (SELECT COUNT(rating) FROM usage_logs WHERE usage_logs.prompt_id = prompts.id)
+        THEN MAX(score_avg * score_count - (SELECT COALESCE(SUM(rating), 0) FROM usage_logs WHERE usage_logs.prompt_id = prompts.id), 0)
+        ELSE 0
+      END;
+  ",
+];
+
+#[derive(Debug, Clone)]
+enum AppError {
+  NotFound(String),
+  Validation(String),
+  Database(String),
+  Busy(String),
+  Serialization(String),
+  Io(String),
+}
+
+impl AppError {
+  fn kind(&self) -> &'static str {
+    match self {
+      AppError::NotFound(_) => "notFound",
+      AppError::Validation(_) => "validation",
+      AppError::Database(_) => "database",
+      AppError::Busy(_) => "busy",
+      AppError::Serialization(_) => "serialization",
+      AppError::Io(_) => "io",
+    }
+  }
+
+  fn message(&self) -> &str {
+    match self {
+      AppError::NotFound(message)
+      | AppError::Validation(message)
+      | AppError::Database(message)
+      | AppError::Busy(message)
+      | AppError::Serialization(message)
+      | AppError::Io(message) => message,
+    }
+  }
+}
+
+impl fmt::Display for AppError {
+  fn fmt(&self, formatter: &mut fmt::Formatter<'_>) -> fmt::Result {
+    formatter.write_str(self.message())
+  }
+}
+
+impl std::error::Error for AppError {}
+
+impl Serialize for AppError {
+  fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+    let mut state = serializer.serialize_struct("AppError", 2)?;
+    state.serialize_field("kind", self.kind())?;
+    state.serialize_field("message", self.message())?;
+    state.end()
+  }
+}
+
+impl From<rusqlite::Error> for AppError {
+  fn from(error: rusqlite::Error) -> Self {
+    match &error {
+      rusqlite::Error::SqliteFailure(failure, _)
+        if matches!(
+          failure.code,
+          rusqlite::ErrorCode::DatabaseBusy | rusqlite::ErrorCode::DatabaseLocked
+        ) =>
+      {
+        AppError::Busy(error.to_string())
+      }
+      _ => AppError::Database(error.to_string()),
+    }
+  }
+}
+
+impl From<serde_json::Error> for AppError {
+  fn from(error: serde_json::Error) -> Self {
+    AppError::Serialization(error.to_string())
+  }
+}
+
+impl From<serde_yaml::Error> for AppError {
+  fn from(error: serde_yaml::Error) -> Self {
+    AppError::Serialization(error.to_string())
+  }
+}
 
 #[derive(Clone)]
 struct AppState {
   db_path: PathBuf,
+  import_jobs: Arc<Mutex<HashMap<u64, Arc<AtomicBool>>>>,
+  next_import_job_id: Arc<AtomicU64>,
+  delete_tokens: Arc<Mutex<HashMap<i64, (String, Instant)>>>,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct AutoBackupConfig {
+  enabled: bool,
+  interval_days: i64,
+  keep: i64,
+  last_backup_at: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct DeleteConfirmation {
+  token: String,
+  expires_in_seconds: u64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 struct PromptRecord {
   id: i64,
@@ -29,6 +428,93 @@ struct PromptRecord {
   score_count: i64,
   created_at: String,
   updated_at: String,
+  description: String,
+  source_url: String,
+  author: String,
+  model: String,
+  language: String,
+  params: Value,
+  icon: String,
+  category_id: Option<i64>,
+  meta: BTreeMap<String, String>,
+  char_count: i64,
+  word_count: i64,
+  estimated_tokens: i64,
+  variables_schema: Vec<VariableSpec>,
+  last_used_at: Option<String>,
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  collections: Option<Vec<CollectionRef>>,
+  #[serde(default)]
+  uuid: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct PromptBatchEvent {
+  request_id: Option<u64>,
+  batch_index: usize,
+  prompts: Vec<PromptRecord>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct PromptDoneEvent {
+  request_id: Option<u64>,
+  total: usize,
+  batches: usize,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+enum VariableKind {
+  #[default]
+  Text,
+  Multiline,
+  Number,
+  Select,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+enum LintSeverity {
+  Error,
+  Warning,
+  Info,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct LintFinding {
+  severity: LintSeverity,
+  code: &'static str,
+  message: String,
+  start: Option<usize>,
+  end: Option<usize>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct VariableSpec {
+  name: String,
+  #[serde(default)]
+  label: String,
+  #[serde(default, skip_serializing_if = "String::is_empty")]
+  description: String,
+  #[serde(rename = "type", default)]
+  kind: VariableKind,
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  default: Option<String>,
+  #[serde(default)]
+  required: bool,
+  #[serde(default, skip_serializing_if = "Vec::is_empty")]
+  options: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct CollectionRef {
+  id: i64,
+  name: String,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -39,6 +525,90 @@ struct PromptVersionRecord {
   content: String,
   change_note: String,
   created_at: String,
+  char_count: i64,
+  word_count: i64,
+  estimated_tokens: i64,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct VersionWithPrompt {
+  #[serde(flatten)]
+  version: PromptVersionRecord,
+  prompt_title: String,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct VersionTimeline {
+  versions: Vec<VersionWithPrompt>,
+  total: i64,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct PromptDetail {
+  #[serde(flatten)]
+  prompt: PromptRecord,
+  example_output: String,
+  last_input_vars: Value,
+  versions: Vec<PromptVersionRecord>,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct PromptCommentRecord {
+  id: i64,
+  prompt_id: i64,
+  body: String,
+  created_at: String,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct VariablePresetRecord {
+  id: i64,
+  prompt_id: i64,
+  name: String,
+  values: Value,
+  created_at: String,
+  updated_at: String,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct PromptTestCaseRecord {
+  id: i64,
+  prompt_id: i64,
+  name: String,
+  variables: Value,
+  created_at: String,
+  updated_at: String,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct TestCaseRun {
+  name: String,
+  text: String,
+  missing_variables: Vec<String>,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct SnippetRecord {
+  id: i64,
+  name: String,
+  content: String,
+  created_at: String,
+  updated_at: String,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct TextStats {
+  char_count: i64,
+  word_count: i64,
+  estimated_tokens: i64,
 }
 
 #[derive(Debug, Serialize)]
@@ -46,9 +616,103 @@ struct PromptVersionRecord {
 struct TagInfo {
   name: String,
   count: i64,
+  favorite_count: i64,
+  pinned: bool,
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct TagDetails {
+  name: String,
+  prompt_count: i64,
+  favorite_count: i64,
+  average_score: Option<f64>,
+  last_updated_at: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct CategoryRecord {
+  id: i64,
+  name: String,
+  count: i64,
+  created_at: String,
+  updated_at: String,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct CollectionRecord {
+  id: i64,
+  name: String,
+  parent_id: Option<i64>,
+  depth: i64,
+  prompt_count: i64,
+  created_at: String,
+  updated_at: String,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+struct PromptFilter {
+  search: Option<String>,
+  tag: Option<String>,
+  model: Option<String>,
+  author: Option<String>,
+  language: Option<String>,
+  category_id: Option<i64>,
+  metadata_filters: Vec<(String, String)>,
+  collection_id: Option<i64>,
+  include_descendants: bool,
+  unfiled_only: bool,
+  favorites_only: bool,
+  min_score: Option<f64>,
+  sort_by: Option<String>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+struct ListPromptsInput {
+  #[serde(flatten)]
+  filter: PromptFilter,
+  fuzzy: bool,
+  include_collections: bool,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct CollectionListing {
+  collections: Vec<CollectionRecord>,
+  unfiled_count: i64,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct SmartCollectionRecord {
+  id: i64,
+  name: String,
+  filter: PromptFilter,
+  prompt_count: i64,
+  created_at: String,
+  updated_at: String,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct FacetCount {
+  name: String,
+  count: i64,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct RatingBucket {
+  bucket: String,
+  average_rating: f64,
+  count: i64,
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
 #[serde(rename_all = "camelCase")]
 struct SavePromptInput {
   id: Option<i64>,
@@ -57,778 +721,8513 @@ struct SavePromptInput {
   tags: Vec<String>,
   is_favorite: bool,
   change_note: Option<String>,
+  description: Option<String>,
+  source_url: Option<String>,
+  author: Option<String>,
+  model: Option<String>,
+  language: Option<String>,
+  params: Option<Value>,
+  icon: Option<String>,
+  category_id: Option<i64>,
+  example_output: Option<String>,
+  variables_schema: Option<Vec<VariableSpec>>,
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Clone, Deserialize)]
 #[serde(rename_all = "camelCase")]
 struct LogUsageInput {
   prompt_id: i64,
   input_vars: Value,
   output_text: String,
   rating: Option<i64>,
+  check_variables: Option<bool>,
+  validate: Option<bool>,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Serialize)]
 #[serde(rename_all = "camelCase")]
-struct ExportVersionItem {
-  content: String,
-  change_note: String,
-  created_at: String,
+struct LogUsageResult {
+  logged: bool,
+  log_id: Option<i64>,
+  truncated: bool,
+  evicted: i64,
+  missing_variables: Vec<String>,
+  extra_variables: Vec<String>,
+  validation_errors: Vec<VariableValidationError>,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Serialize)]
 #[serde(rename_all = "camelCase")]
-struct ExportPromptItem {
-  title: String,
-  content: String,
-  tags: Vec<String>,
-  is_favorite: bool,
-  score_avg: f64,
-  score_count: i64,
-  versions: Vec<ExportVersionItem>,
+struct CopiedPrompt {
+  text: String,
+  log_id: i64,
 }
 
 #[derive(Debug, Serialize)]
 #[serde(rename_all = "camelCase")]
-struct ExportPayload {
-  exported_at: String,
-  prompts: Vec<ExportPromptItem>,
+struct VariableValidationError {
+  variable: String,
+  message: String,
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Serialize)]
 #[serde(rename_all = "camelCase")]
-struct ImportVersionItem {
-  content: String,
-  change_note: Option<String>,
-  created_at: Option<String>,
+struct TemplateVariable {
+  name: String,
+  offsets: Vec<usize>,
+  #[serde(skip_serializing_if = "Vec::is_empty")]
+  fields: Vec<String>,
 }
 
-#[derive(Debug, Deserialize)]
-#[serde(rename_all = "camelCase")]
-struct ImportPromptItem {
-  title: String,
-  content: String,
-  tags: Option<Vec<String>>,
-  is_favorite: Option<bool>,
-  score_avg: Option<f64>,
-  score_count: Option<i64>,
-  versions: Option<Vec<ImportVersionItem>>,
+#[derive(Debug)]
+enum TemplateNode {
+  Text(String),
+  Variable {
+    name: String,
+    raw: String,
+  },
+  If {
+    name: String,
+    then_nodes: Vec<TemplateNode>,
+    else_nodes: Vec<TemplateNode>,
+  },
+  Each {
+    name: String,
+    body: Vec<TemplateNode>,
+  },
 }
 
-#[derive(Debug, Deserialize)]
-#[serde(untagged)]
-enum ImportPayload {
-  Wrapped { prompts: Vec<ImportPromptItem> },
-  Flat(Vec<ImportPromptItem>),
+struct OpenTemplateBlock {
+  helper: &'static str,
+  name: String,
+  nodes: Vec<TemplateNode>,
+  else_nodes: Option<Vec<TemplateNode>>,
+}
+
+struct TemplateRenderState<'a> {
+  values: &'a Map<String, Value>,
+  missing_as_empty: bool,
+  missing_variables: Vec<String>,
+  matched_names: HashSet<String>,
 }
 
 #[derive(Debug, Serialize)]
 #[serde(rename_all = "camelCase")]
-struct ImportResult {
-  imported: i64,
+struct TemplateVariables {
+  variables: Vec<TemplateVariable>,
+  warnings: Vec<String>,
 }
 
-fn now_iso() -> String {
-  Utc::now().to_rfc3339()
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct RenderedTemplate {
+  text: String,
+  missing_variables: Vec<String>,
+  extra_variables: Vec<String>,
 }
 
-fn normalize_tags(tags: Vec<String>) -> Vec<String> {
-  let mut seen = HashSet::new();
-  let mut normalized = Vec::new();
-
-  for tag in tags {
-    let trimmed = tag.trim();
-    if trimmed.is_empty() {
-      continue;
-    }
-    let lower_key = trimmed.to_lowercase();
-    if seen.insert(lower_key) {
-      normalized.push(trimmed.to_string());
-    }
-  }
-
-  normalized
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct UpsertPromptResult {
+  prompt: PromptRecord,
+  warnings: Vec<String>,
 }
 
-fn encode_tags(tags: &[String]) -> String {
-  serde_json::to_string(tags).unwrap_or_else(|_| "[]".to_string())
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct DatabaseInfo {
+  sqlite_version: String,
+  schema_version: i64,
+  expected_schema_version: i64,
+  path: String,
+  size_bytes: u64,
 }
 
-fn decode_tags(value: &str) -> Vec<String> {
-  serde_json::from_str::<Vec<String>>(value).unwrap_or_default()
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+enum MoveOutcome {
+  Moved,
+  AlreadyInTarget,
+  Unfiled,
+  NotFound,
 }
 
-fn open_connection(db_path: &Path) -> Result<Connection, String> {
-  let connection = Connection::open(db_path).map_err(|error| error.to_string())?;
-  connection
-    .execute("PRAGMA foreign_keys = ON", [])
-    .map_err(|error| error.to_string())?;
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct MovePromptResult {
+  prompt_id: i64,
+  outcome: MoveOutcome,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ExportVersionItem {
+  content: String,
+  #[serde(default, skip_serializing_if = "String::is_empty")]
+  change_note: String,
+  #[serde(skip_serializing_if = "Option::is_none")]
+  created_at: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ExportPromptItem {
+  #[serde(skip)]
+  id: i64,
+  #[serde(default, skip_serializing_if = "String::is_empty")]
+  uuid: String,
+  title: String,
+  description: String,
+  content: String,
+  tags: Vec<String>,
+  is_favorite: bool,
+  score_avg: f64,
+  score_count: i64,
+  source_url: String,
+  #[serde(default, skip_serializing_if = "String::is_empty")]
+  author: String,
+  model: String,
+  language: String,
+  params: Value,
+  icon: String,
+  category: String,
+  meta: BTreeMap<String, String>,
+  example_output: String,
+  #[serde(default, skip_serializing_if = "Vec::is_empty")]
+  variables_schema: Vec<VariableSpec>,
+  #[serde(default, skip_serializing_if = "Vec::is_empty")]
+  variable_presets: Vec<PresetItem>,
+  #[serde(default, skip_serializing_if = "Vec::is_empty")]
+  test_cases: Vec<TestCaseItem>,
+  #[serde(default, skip_serializing_if = "Vec::is_empty")]
+  usage_logs: Vec<UsageLogItem>,
+  #[serde(skip_serializing_if = "Option::is_none")]
+  versions: Option<Vec<ExportVersionItem>>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct TestCaseItem {
+  name: String,
+  variables: Value,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct UsageLogItem {
+  #[serde(default)]
+  input_vars: Value,
+  #[serde(default)]
+  output_text: String,
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  rating: Option<i64>,
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  used_at: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct PresetItem {
+  name: String,
+  values: Value,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct ExportPayload {
+  schema_version: u64,
+  app_version: String,
+  #[serde(skip_serializing_if = "Option::is_none")]
+  exported_at: Option<String>,
+  prompts: Vec<ExportPromptItem>,
+  collections: Vec<ExportCollectionItem>,
+  #[serde(skip_serializing_if = "Vec::is_empty")]
+  snippets: Vec<SnippetItem>,
+  #[serde(skip_serializing_if = "Option::is_none")]
+  since: Option<String>,
+  #[serde(skip_serializing_if = "Vec::is_empty")]
+  tombstones: Vec<PromptTombstone>,
+  #[serde(skip)]
+  stable: bool,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct PromptTombstone {
+  uuid: String,
+  #[serde(default)]
+  deleted_at: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct SnippetItem {
+  name: String,
+  content: String,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+struct ExportOptions {
+  anonymize: bool,
+  include_versions: bool,
+  include_scores: bool,
+  include_notes: bool,
+  include_usage_logs: bool,
+  max_log_output_chars: i64,
+  collection_id: Option<i64>,
+  order: Option<String>,
+  ids: Option<Vec<i64>>,
+  tags: Vec<String>,
+  favorites_only: bool,
+  stable: bool,
+  since: Option<String>,
+}
+
+impl Default for ExportOptions {
+  fn default() -> Self {
+    Self {
+      anonymize: false,
+      include_versions: true,
+      include_scores: true,
+      include_notes: true,
+      include_usage_logs: false,
+      max_log_output_chars: 0,
+      collection_id: None,
+      order: None,
+      ids: None,
+      tags: Vec::new(),
+      favorites_only: false,
+      stable: false,
+      since: None,
+    }
+  }
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+struct MarkdownExportOptions {
+  #[serde(flatten)]
+  selection: ExportOptions,
+  include_toc: bool,
+  include_variables: bool,
+  include_change_note: bool,
+}
+
+impl Default for MarkdownExportOptions {
+  fn default() -> Self {
+    Self {
+      selection: ExportOptions::default(),
+      include_toc: true,
+      include_variables: true,
+      include_change_note: false,
+    }
+  }
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+struct ImportFileOptions {
+  format: Option<String>,
+  #[serde(flatten)]
+  import: ImportOptions,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct ExportFileSummary {
+  prompt_count: i64,
+  size_bytes: u64,
+  path: String,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct ExportManifestEntry {
+  id: i64,
+  title: String,
+  file: String,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct ExportManifest {
+  #[serde(skip_serializing_if = "Option::is_none")]
+  exported_at: Option<String>,
+  prompts: Vec<ExportManifestEntry>,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+#[serde(default)]
+struct PromptFrontmatter {
+  title: String,
+  #[serde(skip_serializing_if = "Vec::is_empty")]
+  tags: Vec<String>,
+  favorite: bool,
+  #[serde(skip_serializing_if = "Option::is_none")]
+  created: Option<String>,
+  #[serde(skip_serializing_if = "Option::is_none")]
+  updated: Option<String>,
+  #[serde(skip_serializing_if = "Option::is_none")]
+  score: Option<f64>,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct ExportCollectionItem {
+  name: String,
+  parent_index: Option<usize>,
+  prompt_indexes: Vec<usize>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ImportCollectionItem {
+  name: String,
+  parent_index: Option<usize>,
+  #[serde(default)]
+  prompt_indexes: Vec<usize>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ImportVersionItem {
+  content: String,
+  change_note: Option<String>,
+  created_at: Option<String>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ImportPromptItem {
+  uuid: Option<String>,
+  title: String,
+  description: Option<String>,
+  content: String,
+  tags: Option<Vec<String>>,
+  is_favorite: Option<bool>,
+  score_avg: Option<f64>,
+  score_count: Option<i64>,
+  source_url: Option<String>,
+  author: Option<String>,
+  model: Option<String>,
+  language: Option<String>,
+  params: Option<Value>,
+  icon: Option<String>,
+  category: Option<String>,
+  meta: Option<BTreeMap<String, String>>,
+  example_output: Option<String>,
+  variables_schema: Option<Vec<VariableSpec>>,
+  variable_presets: Option<Vec<PresetItem>>,
+  test_cases: Option<Vec<TestCaseItem>>,
+  usage_logs: Option<Vec<UsageLogItem>>,
+  created_at: Option<String>,
+  updated_at: Option<String>,
+  versions: Option<Vec<ImportVersionItem>>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(untagged)]
+enum ImportPayload {
+  Wrapped {
+    prompts: Vec<ImportPromptItem>,
+    #[serde(default)]
+    collections: Vec<ImportCollectionItem>,
+    #[serde(default)]
+    snippets: Vec<SnippetItem>,
+    #[serde(default)]
+    tombstones: Vec<PromptTombstone>,
+    #[serde(rename = "schemaVersion")]
+    schema_version: Option<Value>,
+    #[serde(rename = "appVersion")]
+    app_version: Option<Value>,
+  },
+  Flat(Vec<ImportPromptItem>),
+}
+
+#[derive(Debug, Deserialize)]
+struct AwesomePromptItem {
+  act: String,
+  prompt: String,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(untagged)]
+enum AwesomePayload {
+  Wrapped { prompts: Vec<AwesomePromptItem> },
+  Flat(Vec<AwesomePromptItem>),
+}
+
+#[derive(Debug, Default)]
+struct ParsedImport {
+  items: Vec<ImportPromptItem>,
+  collections: Vec<ImportCollectionItem>,
+  snippets: Vec<SnippetItem>,
+  tombstones: Vec<PromptTombstone>,
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct ImportResult {
+  cancelled: bool,
+  imported: i64,
+  skipped: i64,
+  updated: i64,
+  deleted: i64,
+  #[serde(skip_serializing_if = "Vec::is_empty")]
+  skipped_items: Vec<SkippedItem>,
+  #[serde(skip_serializing_if = "Vec::is_empty")]
+  skipped_files: Vec<SkippedImportFile>,
+  #[serde(skip_serializing_if = "Vec::is_empty")]
+  warnings: Vec<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+enum ImportPreviewAction {
+  Create,
+  Update,
+  Skip,
+  Invalid,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct ImportPreviewItem {
+  title: String,
+  action: ImportPreviewAction,
+  conflict: bool,
+}
+
+#[derive(Debug, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct ImportPreview {
+  created: i64,
+  updated: i64,
+  skipped: i64,
+  invalid: i64,
+  deleted: i64,
+  conflicts: i64,
+  items: Vec<ImportPreviewItem>,
+  warnings: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct SkippedItem {
+  index: usize,
+  title: String,
+  reason: &'static str,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct SkippedImportFile {
+  path: String,
+  reason: String,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+enum ImportStrategy {
+  #[default]
+  Duplicate,
+  Skip,
+  Overwrite,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+struct ImportOptions {
+  strategy: ImportStrategy,
+  match_content: bool,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+struct DirectoryImportOptions {
+  recursive: bool,
+  #[serde(flatten)]
+  import: ImportOptions,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct ImportProgress {
+  processed: usize,
+  total: usize,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct ImportProgressEvent {
+  job_id: u64,
+  #[serde(flatten)]
+  progress: ImportProgress,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct ImportFinishedEvent {
+  job_id: u64,
+  #[serde(skip_serializing_if = "Option::is_none")]
+  result: Option<ImportResult>,
+  #[serde(skip_serializing_if = "Option::is_none")]
+  error: Option<AppError>,
+}
+
+fn now_iso() -> String {
+  Utc::now().to_rfc3339()
+}
+
+fn normalize_tags(tags: Vec<String>) -> Vec<String> {
+  let mut seen = HashSet::new();
+  let mut normalized = Vec::new();
+
+  for tag in tags {
+    let trimmed = tag.trim();
+    if trimmed.is_empty() {
+      continue;
+    }
+    let lower_key = trimmed.to_lowercase();
+    if seen.insert(lower_key) {
+      normalized.push(trimmed.to_string());
+    }
+  }
+
+  normalized
+}
+
+fn cap_tags(tags: &mut Vec<String>, limit: i64) -> Option<String> {
+  if limit == 0 || tags.len() as i64 <= limit {
+    return None;
+  }
+
+  let original_count = tags.len();
+  tags.truncate(limit as usize);
+  Some(format!(
+    "标签数量 {original_count} 超过上限 {limit}，多余的标签已被忽略"
+  ))
+}
+
+fn normalize_description(description: &str) -> Result<String, AppError> {
+  let normalized = description.trim().to_string();
+  if normalized.chars().count() > MAX_DESCRIPTION_CHARS {
+    return Err(AppError::Validation(format!(
+      "描述不能超过 {MAX_DESCRIPTION_CHARS} 个字符"
+    )));
+  }
+  Ok(normalized)
+}
+
+fn normalize_source_url(source_url: &str) -> Result<String, AppError> {
+  let normalized = source_url.trim().to_string();
+  if normalized.is_empty() {
+    return Ok(normalized);
+  }
+  let url = tauri::Url::parse(&normalized)
+    .map_err(|error| AppError::Validation(format!("来源链接格式不正确: {error}")))?;
+  if !matches!(url.scheme(), "http" | "https") || url.host_str().is_none() {
+    return Err(AppError::Validation(
+      "来源链接必须是 http 或 https 地址".to_string(),
+    ));
+  }
+  Ok(normalized)
+}
+
+fn normalize_author(author: &str) -> Result<String, AppError> {
+  let normalized = author.trim().to_string();
+  if normalized.chars().count() > MAX_AUTHOR_CHARS {
+    return Err(AppError::Validation(format!(
+      "作者不能超过 {MAX_AUTHOR_CHARS} 个字符"
+    )));
+  }
+  Ok(normalized)
+}
+
+fn normalize_icon(icon: &str) -> Result<String, AppError> {
+  let normalized = icon.trim().to_string();
+  if normalized.chars().count() > MAX_ICON_CHARS {
+    return Err(AppError::Validation(
+      "图标只能是一个 emoji 或不超过几个字符的短符号".to_string(),
+    ));
+  }
+  Ok(normalized)
+}
+
+fn normalize_category_name(name: &str) -> Result<String, AppError> {
+  let normalized = name.trim().to_string();
+  if normalized.is_empty() {
+    return Err(AppError::Validation("分类名称不能为空".to_string()));
+  }
+  if normalized.chars().count() > MAX_CATEGORY_NAME_CHARS {
+    return Err(AppError::Validation(format!(
+      "分类名称不能超过 {MAX_CATEGORY_NAME_CHARS} 个字符"
+    )));
+  }
+  Ok(normalized)
+}
+
+fn normalize_snippet_name(name: &str) -> Result<String, AppError> {
+  let normalized = name.trim().to_string();
+  if normalized.is_empty() {
+    return Err(AppError::Validation("片段名称不能为空".to_string()));
+  }
+  if normalized.chars().count() > MAX_SNIPPET_NAME_CHARS {
+    return Err(AppError::Validation(format!(
+      "片段名称不能超过 {MAX_SNIPPET_NAME_CHARS} 个字符"
+    )));
+  }
+  if normalized.contains(['{', '}']) || normalized.contains(char::is_whitespace) {
+    return Err(AppError::Validation(
+      "片段名称不能包含空白字符或花括号".to_string(),
+    ));
+  }
+  Ok(normalized)
+}
+
+fn normalize_meta_key(key: &str) -> Result<String, AppError> {
+  let normalized = key.trim().to_lowercase();
+  if normalized.is_empty() {
+    return Err(AppError::Validation("元数据键不能为空".to_string()));
+  }
+  if normalized.chars().count() > MAX_META_KEY_CHARS {
+    return Err(AppError::Validation(format!(
+      "元数据键不能超过 {MAX_META_KEY_CHARS} 个字符"
+    )));
+  }
+  Ok(normalized)
+}
+
+fn decode_variables_schema(raw: &str) -> Vec<VariableSpec> {
+  serde_json::from_str::<Vec<VariableSpec>>(raw).unwrap_or_default()
+}
+
+fn normalize_variables_schema(
+  content: &str,
+  schema: Vec<VariableSpec>,
+) -> Result<(Vec<VariableSpec>, Vec<String>), AppError> {
+  let content_variables = extract_variable_names(content);
+  let mut normalized: Vec<VariableSpec> = Vec::new();
+
+  for mut spec in schema {
+    spec.name = spec.name.trim().to_string();
+    spec.label = spec.label.trim().to_string();
+    spec.description = spec.description.trim().to_string();
+    let name = spec.name.clone();
+    if name.is_empty() {
+      return Err(AppError::Validation("变量名称不能为空".to_string()));
+    }
+    if normalized.iter().any(|existing| existing.name == name) {
+      return Err(AppError::Validation(format!("变量「{name}」重复定义")));
+    }
+    spec.options = spec
+      .options
+      .into_iter()
+      .map(|option| option.trim().to_string())
+      .filter(|option| !option.is_empty())
+      .collect();
+    if spec.kind == VariableKind::Select && spec.options.is_empty() {
+      return Err(AppError::Validation(format!(
+        "变量「{name}」为下拉选择类型，必须提供可选值"
+      )));
+    }
+    if let Some(default) = spec.default.as_deref() {
+      if spec.kind == VariableKind::Number && default.trim().parse::<f64>().is_err() {
+        return Err(AppError::Validation(format!(
+          "变量「{name}」的默认值必须是数字"
+        )));
+      }
+      if spec.kind == VariableKind::Select && !spec.options.iter().any(|option| option == default) {
+        return Err(AppError::Validation(format!(
+          "变量「{name}」的默认值不在可选值中"
+        )));
+      }
+    }
+    normalized.push(spec);
+  }
+
+  let mut warnings = unused_variable_warnings(&content_variables, &normalized);
+  if !normalized.is_empty() {
+    warnings.extend(
+      content_variables
+        .iter()
+        .filter(|name| !normalized.iter().any(|spec| &spec.name == *name))
+        .map(|name| format!("变量「{name}」未在变量定义中声明")),
+    );
+  }
+
+  Ok((normalized, warnings))
+}
+
+fn unused_variable_warnings(content_variables: &[String], schema: &[VariableSpec]) -> Vec<String> {
+  schema
+    .iter()
+    .filter(|spec| !content_variables.contains(&spec.name))
+    .map(|spec| format!("变量「{}」已定义但未在 Prompt 内容中使用", spec.name))
+    .collect()
+}
+
+fn validate_input_vars(
+  schema: &[VariableSpec],
+  values: &Map<String, Value>,
+) -> Vec<VariableValidationError> {
+  let mut errors = Vec::new();
+  for spec in schema {
+    let text = values
+      .get(&spec.name)
+      .map(variable_value_to_text)
+      .unwrap_or_default();
+    let text = text.trim();
+    let error = |message: String| VariableValidationError {
+      variable: spec.name.clone(),
+      message,
+    };
+
+    if text.is_empty() {
+      if spec.required {
+        errors.push(error(format!("变量「{}」为必填项", spec.name)));
+      }
+      continue;
+    }
+    match spec.kind {
+      VariableKind::Number if text.parse::<f64>().is_err() => {
+        errors.push(error(format!("变量「{}」必须是数字", spec.name)));
+      }
+      VariableKind::Select if !spec.options.iter().any(|option| option == text) => {
+        errors.push(error(format!(
+          "变量「{}」的值「{text}」不在可选值中",
+          spec.name
+        )));
+      }
+      _ => {}
+    }
+  }
+  errors
+}
+
+fn apply_variable_defaults(schema: &[VariableSpec], values: &mut Map<String, Value>) {
+  for spec in schema {
+    if let Some(default) = &spec.default {
+      if !values.contains_key(&spec.name) {
+        values.insert(spec.name.clone(), Value::String(default.clone()));
+      }
+    }
+  }
+}
+
+fn decode_meta(value: Option<String>) -> BTreeMap<String, String> {
+  value
+    .and_then(|raw| serde_json::from_str::<BTreeMap<String, String>>(&raw).ok())
+    .unwrap_or_default()
+}
+
+fn normalize_collection_name(name: &str) -> Result<String, AppError> {
+  let normalized = name.trim().to_string();
+  if normalized.is_empty() {
+    return Err(AppError::Validation("集合名称不能为空".to_string()));
+  }
+  if normalized.chars().count() > MAX_COLLECTION_NAME_CHARS {
+    return Err(AppError::Validation(format!(
+      "集合名称不能超过 {MAX_COLLECTION_NAME_CHARS} 个字符"
+    )));
+  }
+  Ok(normalized)
+}
+
+fn normalize_model(model: &str) -> String {
+  model.trim().to_lowercase()
+}
+
+fn is_cjk_char(character: char) -> bool {
+  matches!(
+    character,
+    '\u{3400}'..='\u{4DBF}' | '\u{4E00}'..='\u{9FFF}' | '\u{F900}'..='\u{FAFF}' | '\u{20000}'..='\u{2FA1F}'
+  )
+}
+
+fn detect_language(content: &str) -> String {
+  let mut cjk_count = 0_usize;
+  let mut latin_count = 0_usize;
+  for character in content.chars() {
+    if is_cjk_char(character) {
+      cjk_count += 1;
+    } else if character.is_ascii_alphabetic() {
+      latin_count += 1;
+    }
+  }
+
+  if cjk_count + latin_count == 0 {
+    return String::new();
+  }
+  if cjk_count * 4 >= latin_count {
+    "zh".to_string()
+  } else {
+    "en".to_string()
+  }
+}
+
+fn compute_text_stats(content: &str) -> TextStats {
+  let mut char_count = 0_i64;
+  let mut word_count = 0_i64;
+  let mut cjk_count = 0_i64;
+  let mut in_word = false;
+
+  for character in content.chars() {
+    char_count += 1;
+    if is_cjk_char(character) {
+      cjk_count += 1;
+      word_count += 1;
+      in_word = false;
+    } else if character.is_whitespace() {
+      in_word = false;
+    } else if !in_word {
+      word_count += 1;
+      in_word = true;
+    }
+  }
+
+  let other_count = char_count - cjk_count;
+  let estimated_tokens = (other_count as f64 / 4.0 + cjk_count as f64 / 1.7).ceil() as i64;
+
+  TextStats {
+    char_count,
+    word_count,
+    estimated_tokens,
+  }
+}
+
+fn normalize_language(language: &str) -> String {
+  language.trim().to_lowercase().replace('_', "-")
+}
+
+fn variable_value_to_text(value: &Value) -> String {
+  match value {
+    Value::Null => String::new(),
+    Value::String(text) => text.clone(),
+    other => other.to_string(),
+  }
+}
+
+fn split_block_tag(tag: &str) -> (&str, &str) {
+  tag
+    .split_once(char::is_whitespace)
+    .map(|(helper, argument)| (helper, argument.trim()))
+    .unwrap_or((tag, ""))
+}
+
+fn current_template_nodes<'a>(
+  root: &'a mut Vec<TemplateNode>,
+  stack: &'a mut [OpenTemplateBlock],
+) -> &'a mut Vec<TemplateNode> {
+  match stack.last_mut() {
+    Some(block) => match &mut block.else_nodes {
+      Some(else_nodes) => else_nodes,
+      None => &mut block.nodes,
+    },
+    None => root,
+  }
+}
+
+fn push_template_text(nodes: &mut Vec<TemplateNode>, text: &str) {
+  if let Some(TemplateNode::Text(existing)) = nodes.last_mut() {
+    existing.push_str(text);
+  } else {
+    nodes.push(TemplateNode::Text(text.to_string()));
+  }
+}
+
+fn parse_template(content: &str) -> Result<Vec<TemplateNode>, AppError> {
+  let mut root = Vec::new();
+  let mut stack: Vec<OpenTemplateBlock> = Vec::new();
+  let mut cursor = 0;
+
+  while let Some(character) = content[cursor..].chars().next() {
+    let rest = &content[cursor..];
+    if let Some(escape) = ["\\{\\{", "\\{{"]
+      .into_iter()
+      .find(|escape| rest.starts_with(escape))
+    {
+      push_template_text(current_template_nodes(&mut root, &mut stack), "{{");
+      cursor += escape.len();
+      continue;
+    }
+
+    if let Some(after_open) = rest.strip_prefix("{{") {
+      if let Some(end) = after_open.find("}}") {
+        let raw_name = &after_open[..end];
+        let raw = &rest[..end + 4];
+        let name = raw_name.trim();
+        if !raw_name.contains('{') && !raw_name.contains('}') {
+          if let Some(tag) = name.strip_prefix('#') {
+            let (helper, argument) = split_block_tag(tag);
+            let helper = match helper {
+              "if" => "if",
+              "each" => "each",
+              _ => {
+                return Err(AppError::Validation(format!(
+                  "未知的模板块：{raw}（仅支持 #if 与 #each）"
+                )))
+              }
+            };
+            if argument.is_empty() {
+              return Err(AppError::Validation(format!("{raw} 缺少变量名")));
+            }
+            stack.push(OpenTemplateBlock {
+              helper,
+              name: argument.to_string(),
+              nodes: Vec::new(),
+              else_nodes: None,
+            });
+          } else if name == "else" {
+            match stack.last_mut() {
+              Some(block) if block.helper == "if" && block.else_nodes.is_none() => {
+                block.else_nodes = Some(Vec::new());
+              }
+              _ => {
+                return Err(AppError::Validation(
+                  "{{else}} 只能在 {{#if}} 块中出现一次".to_string(),
+                ))
+              }
+            }
+          } else if let Some(helper) = name.strip_prefix('/') {
+            let Some(block) = stack.pop() else {
+              return Err(AppError::Validation(format!("{raw} 没有对应的开始标签")));
+            };
+            if block.helper != helper.trim() {
+              return Err(AppError::Validation(format!(
+                "{raw} 与 {{{{#{} {}}}}} 不匹配",
+                block.helper, block.name
+              )));
+            }
+            let node = if block.helper == "if" {
+              TemplateNode::If {
+                name: block.name,
+                then_nodes: block.nodes,
+                else_nodes: block.else_nodes.unwrap_or_default(),
+              }
+            } else {
+              TemplateNode::Each {
+                name: block.name,
+                body: block.nodes,
+              }
+            };
+            current_template_nodes(&mut root, &mut stack).push(node);
+          } else if name.is_empty() || name.starts_with('>') {
+            push_template_text(current_template_nodes(&mut root, &mut stack), raw);
+          } else {
+            current_template_nodes(&mut root, &mut stack).push(TemplateNode::Variable {
+              name: name.to_string(),
+              raw: raw.to_string(),
+            });
+          }
+          cursor += end + 4;
+          continue;
+        }
+      }
+    }
+
+    push_template_text(
+      current_template_nodes(&mut root, &mut stack),
+      &rest[..character.len_utf8()],
+    );
+    cursor += character.len_utf8();
+  }
+
+  if let Some(block) = stack.last() {
+    return Err(AppError::Validation(format!(
+      "{{{{#{} {}}}}} 缺少对应的 {{{{/{}}}}}",
+      block.helper, block.name, block.helper
+    )));
+  }
+  Ok(root)
+}
+
+fn resolve_template_value(
+  name: &str,
+  state: &mut TemplateRenderState<'_>,
+  scope: &[(Value, usize)],
+) -> Option<Value> {
+  if let Some((item, index)) = scope.last() {
+    if name == "this" {
+      return Some(item.clone());
+    }
+    if name == "@index" {
+      return Some(Value::from(*index));
+    }
+    if let Some(path) = name.strip_prefix("this.") {
+      return path
+        .split('.')
+        .try_fold(item, |value, key| value.get(key))
+        .cloned();
+    }
+    if let Some(value) = item.get(name) {
+      return Some(value.clone());
+    }
+  }
+
+  let (key, value) = state.values.get_key_value(name)?;
+  state.matched_names.insert(key.clone());
+  Some(value.clone())
+}
+
+fn is_template_truthy(value: Option<&Value>) -> bool {
+  match value {
+    None | Some(Value::Null) => false,
+    Some(Value::Bool(flag)) => *flag,
+    Some(Value::Number(number)) => number.as_f64() != Some(0.0),
+    Some(Value::String(text)) => !text.trim().is_empty(),
+    Some(Value::Array(items)) => !items.is_empty(),
+    Some(Value::Object(fields)) => !fields.is_empty(),
+  }
+}
+
+fn render_template_nodes(
+  nodes: &[TemplateNode],
+  state: &mut TemplateRenderState<'_>,
+  scope: &mut Vec<(Value, usize)>,
+  output: &mut String,
+) {
+  for node in nodes {
+    match node {
+      TemplateNode::Text(text) => output.push_str(text),
+      TemplateNode::Variable { name, raw } => match resolve_template_value(name, state, scope) {
+        Some(value) => output.push_str(&variable_value_to_text(&value)),
+        None => {
+          if scope.is_empty()
+            && !state
+              .missing_variables
+              .iter()
+              .any(|missing| missing == name)
+          {
+            state.missing_variables.push(name.clone());
+          }
+          if !state.missing_as_empty {
+            output.push_str(raw);
+          }
+        }
+      },
+      TemplateNode::If {
+        name,
+        then_nodes,
+        else_nodes,
+      } => {
+        let value = resolve_template_value(name, state, scope);
+        let branch = if is_template_truthy(value.as_ref()) {
+          then_nodes
+        } else {
+          else_nodes
+        };
+        render_template_nodes(branch, state, scope, output);
+      }
+      TemplateNode::Each { name, body } => {
+        let items = match resolve_template_value(name, state, scope) {
+          Some(Value::Array(items)) => items,
+          Some(Value::Null) => Vec::new(),
+          Some(other) => vec![other],
+          None => {
+            if scope.is_empty()
+              && !state
+                .missing_variables
+                .iter()
+                .any(|missing| missing == name)
+            {
+              state.missing_variables.push(name.clone());
+            }
+            Vec::new()
+          }
+        };
+        for (index, item) in items.into_iter().enumerate() {
+          scope.push((item, index));
+          render_template_nodes(body, state, scope, output);
+          scope.pop();
+        }
+      }
+    }
+  }
+}
+
+fn render_template(
+  content: &str,
+  values: &Map<String, Value>,
+  missing_as_empty: bool,
+) -> Result<RenderedTemplate, AppError> {
+  let nodes = parse_template(content)?;
+  let mut state = TemplateRenderState {
+    values,
+    missing_as_empty,
+    missing_variables: Vec::new(),
+    matched_names: HashSet::new(),
+  };
+  let mut text = String::with_capacity(content.len());
+  render_template_nodes(&nodes, &mut state, &mut Vec::new(), &mut text);
+
+  let extra_variables = values
+    .keys()
+    .filter(|key| !state.matched_names.contains(*key))
+    .cloned()
+    .collect();
+
+  Ok(RenderedTemplate {
+    text,
+    missing_variables: state.missing_variables,
+    extra_variables,
+  })
+}
+
+fn record_template_reference(
+  variables: &mut Vec<TemplateVariable>,
+  each_stack: &[(usize, String)],
+  name: &str,
+  offset: usize,
+) -> (usize, String) {
+  let field = name
+    .strip_prefix("this.")
+    .or((!each_stack.is_empty()).then_some(name));
+  if let (Some(field), Some((variable_index, prefix))) = (field, each_stack.last()) {
+    let path = format!("{prefix}{field}");
+    let fields = &mut variables[*variable_index].fields;
+    if !fields.contains(&path) {
+      fields.push(path.clone());
+    }
+    return (*variable_index, format!("{path}."));
+  }
+
+  if let Some(variable_index) = variables.iter().position(|variable| variable.name == name) {
+    variables[variable_index].offsets.push(offset);
+    return (variable_index, String::new());
+  }
+  variables.push(TemplateVariable {
+    name: name.to_string(),
+    offsets: vec![offset],
+    fields: Vec::new(),
+  });
+  (variables.len() - 1, String::new())
+}
+
+fn scan_template_variables(content: &str) -> TemplateVariables {
+  let mut variables: Vec<TemplateVariable> = Vec::new();
+  let mut each_stack: Vec<(usize, String)> = Vec::new();
+  let mut warnings = Vec::new();
+  let mut cursor = 0;
+
+  while let Some(relative_start) = content[cursor..].find("{{") {
+    let start = cursor + relative_start;
+    let name_start = start + 2;
+    if content[..start].ends_with('\\') {
+      cursor = name_start;
+      continue;
+    }
+    let offset = content[..start].chars().count();
+    let Some(relative_end) = content[name_start..].find("}}") else {
+      warnings.push(format!("位置 {offset} 处的 {{{{ 没有匹配的 }}}}"));
+      break;
+    };
+
+    let name_end = name_start + relative_end;
+    let raw_name = &content[name_start..name_end];
+    if raw_name.contains('{') || raw_name.contains('}') {
+      cursor = start + 1;
+      continue;
+    }
+
+    let name = raw_name.trim();
+    cursor = name_end + 2;
+    if name.is_empty() {
+      warnings.push(format!("位置 {offset} 处存在空的变量占位符"));
+    } else if let Some(tag) = name.strip_prefix('#') {
+      let (helper, argument) = split_block_tag(tag);
+      if argument.is_empty() {
+        continue;
+      }
+      let context = record_template_reference(&mut variables, &each_stack, argument, offset);
+      if helper == "each" {
+        each_stack.push(context);
+      }
+    } else if let Some(helper) = name.strip_prefix('/') {
+      if helper.trim() == "each" {
+        each_stack.pop();
+      }
+    } else if !(name.starts_with('>') || name.starts_with('@') || name == "this" || name == "else")
+    {
+      record_template_reference(&mut variables, &each_stack, name, offset);
+    }
+  }
+
+  TemplateVariables {
+    variables,
+    warnings,
+  }
+}
+
+fn extract_variable_names(content: &str) -> Vec<String> {
+  scan_template_variables(content)
+    .variables
+    .into_iter()
+    .map(|variable| variable.name)
+    .collect()
+}
+
+fn snippet_include_names(content: &str) -> Vec<String> {
+  let mut names = Vec::new();
+  let mut cursor = 0;
+  while let Some(relative_start) = content[cursor..].find("{{>") {
+    let start = cursor + relative_start;
+    let name_start = start + 3;
+    let Some(relative_end) = content[name_start..].find("}}") else {
+      break;
+    };
+    let name = content[name_start..name_start + relative_end]
+      .trim()
+      .to_lowercase();
+    if !content[..start].ends_with('\\') && !name.is_empty() && !names.contains(&name) {
+      names.push(name);
+    }
+    cursor = name_start + relative_end + 2;
+  }
+  names
+}
+
+fn expand_snippet_includes(
+  content: &str,
+  snippets: &HashMap<String, String>,
+  depth: usize,
+) -> Result<String, AppError> {
+  if depth > MAX_SNIPPET_DEPTH {
+    return Err(AppError::Validation(format!(
+      "片段嵌套超过 {MAX_SNIPPET_DEPTH} 层，请检查是否存在循环引用"
+    )));
+  }
+
+  let mut output = String::with_capacity(content.len());
+  let mut cursor = 0;
+  while let Some(relative_start) = content[cursor..].find("{{>") {
+    let start = cursor + relative_start;
+    let name_start = start + 3;
+    let Some(relative_end) = content[name_start..].find("}}") else {
+      break;
+    };
+    let name_end = name_start + relative_end;
+    output.push_str(&content[cursor..start]);
+    if content[..start].ends_with('\\') {
+      output.push_str(&content[start..name_end + 2]);
+    } else {
+      let name = content[name_start..name_end].trim();
+      let snippet = snippets
+        .get(&name.to_lowercase())
+        .ok_or_else(|| AppError::NotFound(format!("片段「{name}」不存在")))?;
+      output.push_str(&expand_snippet_includes(snippet, snippets, depth + 1)?);
+    }
+    cursor = name_end + 2;
+  }
+
+  output.push_str(&content[cursor..]);
+  Ok(output)
+}
+
+fn lint_finding(
+  severity: LintSeverity,
+  code: &'static str,
+  message: String,
+  span: Option<(usize, usize)>,
+) -> LintFinding {
+  LintFinding {
+    severity,
+    code,
+    message,
+    start: span.map(|(start, _)| start),
+    end: span.map(|(_, end)| end),
+  }
+}
+
+fn is_template_variable_name(name: &str) -> bool {
+  name
+    .chars()
+    .all(|character| character.is_alphanumeric() || matches!(character, '_' | '-' | '.'))
+}
+
+fn lint_template(content: &str, schema: &[VariableSpec]) -> Vec<LintFinding> {
+  let chars: Vec<char> = content.chars().collect();
+  let starts_with_at = |index: usize, pattern: &str| {
+    pattern
+      .chars()
+      .enumerate()
+      .all(|(offset, expected)| chars.get(index + offset) == Some(&expected))
+  };
+
+  let mut findings = Vec::new();
+  let mut used_names: HashSet<String> = HashSet::new();
+  let mut each_depth = 0_usize;
+  let mut escaped_opens = 0_usize;
+  let mut index = 0;
+  while index < chars.len() {
+    if let Some(escape) = ["\\{\\{", "\\{{"]
+      .into_iter()
+      .find(|escape| starts_with_at(index, escape))
+    {
+      escaped_opens += 1;
+      index += escape.chars().count();
+      continue;
+    }
+
+    if starts_with_at(index, "{{") {
+      let Some(close) = (index + 2..chars.len()).find(|position| starts_with_at(*position, "}}"))
+      else {
+        findings.push(lint_finding(
+          LintSeverity::Error,
+          "unclosedPlaceholder",
+          format!("{{{{ 没有匹配的 }}}}；{TEMPLATE_ESCAPE_HINT}"),
+          Some((index, index + 2)),
+        ));
+        index += 2;
+        continue;
+      };
+      let raw_name = chars[index + 2..close].iter().collect::<String>();
+      if raw_name.contains('{') {
+        findings.push(lint_finding(
+          LintSeverity::Info,
+          "strayBrace",
+          "孤立的 {".to_string(),
+          Some((index, index + 1)),
+        ));
+        index += 1;
+        continue;
+      }
+
+      let name = raw_name.trim();
+      let span = Some((index, close + 2));
+      if name.is_empty() {
+        findings.push(lint_finding(
+          LintSeverity::Warning,
+          "emptyPlaceholder",
+          "空的变量占位符".to_string(),
+          span,
+        ));
+      } else if let Some(helper) = name.strip_prefix('/') {
+        if helper.trim() == "each" {
+          each_depth = each_depth.saturating_sub(1);
+        }
+      } else {
+        let (helper, name) = match name.strip_prefix('#') {
+          Some(tag) => split_block_tag(tag),
+          None => ("", name),
+        };
+        let is_reserved = name.is_empty()
+          || name == "this"
+          || name == "else"
+          || name.starts_with("this.")
+          || name.starts_with(['>', '@']);
+        if helper.is_empty() && !is_reserved && !is_template_variable_name(name) {
+          findings.push(lint_finding(
+            LintSeverity::Warning,
+            "suspiciousPlaceholder",
+            format!("「{{{{{raw_name}}}}}」不像是模板变量；{TEMPLATE_ESCAPE_HINT}"),
+            span,
+          ));
+        } else if !is_reserved {
+          if each_depth == 0 && !schema.is_empty() && !schema.iter().any(|spec| spec.name == name) {
+            findings.push(lint_finding(
+              LintSeverity::Warning,
+              "undeclaredVariable",
+              format!("变量「{name}」未在变量定义中声明"),
+              span,
+            ));
+          }
+          used_names.insert(name.to_string());
+        }
+        if helper == "each" {
+          each_depth += 1;
+        }
+      }
+      index = close + 2;
+      continue;
+    }
+
+    if chars[index] == '}' && starts_with_at(index, "}}") {
+      if escaped_opens > 0 {
+        escaped_opens -= 1;
+        index += 2;
+        continue;
+      }
+      findings.push(lint_finding(
+        LintSeverity::Warning,
+        "strayBrace",
+        format!("}}}} 没有匹配的 {{{{；{TEMPLATE_ESCAPE_HINT}"),
+        Some((index, index + 2)),
+      ));
+      index += 2;
+      continue;
+    }
+
+    if chars[index] == '{' || chars[index] == '}' {
+      findings.push(lint_finding(
+        LintSeverity::Info,
+        "strayBrace",
+        format!("孤立的 {}", chars[index]),
+        Some((index, index + 1)),
+      ));
+    }
+
+    let closing = match chars[index] {
+      '<' => Some('>'),
+      '[' => Some(']'),
+      '【' => Some('】'),
+      _ => None,
+    };
+    if let Some(closing) = closing {
+      let limit = chars.len().min(index + LINT_EXAMPLE_MAX_CHARS + 2);
+      let close = (index + 1..limit)
+        .take_while(|position| chars[*position] != '\n')
+        .find(|position| chars[*position] == closing);
+      if let Some(close) = close {
+        let inner = chars[index + 1..close]
+          .iter()
+          .collect::<String>()
+          .to_lowercase();
+        if LINT_EXAMPLE_HINTS.iter().any(|hint| inner.contains(hint)) {
+          findings.push(lint_finding(
+            LintSeverity::Warning,
+            "exampleValue",
+            format!(
+              "疑似未替换的示例值：{}",
+              chars[index..=close].iter().collect::<String>()
+            ),
+            Some((index, close + 1)),
+          ));
+        }
+      }
+    }
+
+    index += 1;
+  }
+
+  for marker in LINT_TODO_MARKERS {
+    for (byte_offset, _) in content.match_indices(marker) {
+      let start = content[..byte_offset].chars().count();
+      findings.push(lint_finding(
+        LintSeverity::Warning,
+        "todoMarker",
+        format!("内容中包含 {marker} 标记"),
+        Some((start, start + marker.chars().count())),
+      ));
+    }
+  }
+
+  for spec in schema {
+    if !used_names.contains(&spec.name) {
+      findings.push(lint_finding(
+        LintSeverity::Warning,
+        "unusedVariable",
+        format!("变量「{}」已定义但未在内容中使用", spec.name),
+        None,
+      ));
+    }
+  }
+
+  findings.sort_by_key(|finding| finding.start.unwrap_or(usize::MAX));
+  findings
+}
+
+fn trigrams(text: &str) -> HashSet<[char; 3]> {
+  let normalized = text
+    .to_lowercase()
+    .split_whitespace()
+    .collect::<Vec<_>>()
+    .join(" ");
+  let padded = format!("  {normalized} ").chars().collect::<Vec<_>>();
+  padded
+    .windows(3)
+    .map(|window| [window[0], window[1], window[2]])
+    .collect()
+}
+
+fn trigram_similarity(a: &str, b: &str) -> f64 {
+  let left = trigrams(a);
+  let right = trigrams(b);
+  let union = left.union(&right).count();
+  if union == 0 {
+    return 0.0;
+  }
+  left.intersection(&right).count() as f64 / union as f64
+}
+
+fn normalize_title(title: &str) -> Result<String, AppError> {
+  let normalized_title = title.trim();
+  if normalized_title.is_empty() {
+    return Err(AppError::Validation("标题不能为空".to_string()));
+  }
+  Ok(normalized_title.to_string())
+}
+
+fn title_length_warning(title: &str) -> Option<String> {
+  let char_count = title.chars().count();
+  (char_count > RECOMMENDED_TITLE_CHARS)
+    .then(|| format!("标题长度为 {char_count} 个字符，建议不超过 {RECOMMENDED_TITLE_CHARS} 个字符"))
+}
+
+fn brace_balance_warning(content: &str) -> Option<String> {
+  let open_count = content.matches("{{").count();
+  let close_count = content.matches("}}").count();
+  (open_count != close_count).then(|| {
+    format!("内容中的 {{{{ 与 }}}} 数量不匹配（{open_count} 个 {{{{，{close_count} 个 }}}}）")
+  })
+}
+
+fn empty_variable_warning(content: &str) -> Option<String> {
+  let has_empty_variable = content.split("{{").skip(1).any(|segment| {
+    segment
+      .split_once("}}")
+      .is_some_and(|(name, _)| name.trim().is_empty())
+  });
+  has_empty_variable.then(|| "内容中存在空的变量占位符 {{ }}".to_string())
+}
+
+fn collect_prompt_warnings(title: &str, content: &str) -> Vec<String> {
+  [
+    title_length_warning(title),
+    brace_balance_warning(content),
+    empty_variable_warning(content),
+  ]
+  .into_iter()
+  .flatten()
+  .collect()
+}
+
+fn render_markdown_html(markdown: &str) -> String {
+  let mut options = Options::empty();
+  options.insert(Options::ENABLE_TABLES);
+  options.insert(Options::ENABLE_STRIKETHROUGH);
+  options.insert(Options::ENABLE_TASKLISTS);
+
+  let parser = Parser::new_ext(markdown, options);
+  let mut unsafe_html = String::new();
+  html::push_html(&mut unsafe_html, parser);
+  ammonia::clean(&unsafe_html)
+}
+
+fn preferred_tag_casing(casings: BTreeMap<String, i64>) -> String {
+  casings
+    .into_iter()
+    .max_by(|left, right| left.1.cmp(&right.1).then_with(|| right.0.cmp(&left.0)))
+    .map(|(casing, _)| casing)
+    .unwrap_or_default()
+}
+
+fn aggregate_tag_counts(tag_lists: Vec<(Vec<String>, bool)>) -> Vec<TagInfo> {
+  let mut counts: BTreeMap<String, (i64, i64, BTreeMap<String, i64>)> = BTreeMap::new();
+  for (tags, is_favorite) in tag_lists {
+    let mut seen = HashSet::new();
+    for tag_name in tags {
+      let folded_key = tag_name.to_lowercase();
+      if !seen.insert(folded_key.clone()) {
+        continue;
+      }
+      let (count, favorite_count, casings) = counts.entry(folded_key).or_default();
+      *count += 1;
+      if is_favorite {
+        *favorite_count += 1;
+      }
+      *casings.entry(tag_name).or_insert(0) += 1;
+    }
+  }
+
+  let mut tag_items: Vec<TagInfo> = counts
+    .into_values()
+    .map(|(count, favorite_count, casings)| TagInfo {
+      name: preferred_tag_casing(casings),
+      count,
+      favorite_count,
+      pinned: false,
+    })
+    .collect();
+  tag_items.sort_by(|left, right| {
+    right
+      .count
+      .cmp(&left.count)
+      .then_with(|| left.name.cmp(&right.name))
+  });
+
+  tag_items
+}
+
+fn encode_tags(tags: &[String]) -> String {
+  serde_json::to_string(tags).unwrap_or_else(|_| "[]".to_string())
+}
+
+fn decode_tags(value: &str) -> Vec<String> {
+  serde_json::from_str::<Vec<String>>(value).unwrap_or_default()
+}
+
+fn encode_params(params: Option<Value>) -> Result<Option<String>, AppError> {
+  match params {
+    None | Some(Value::Null) => Ok(None),
+    Some(Value::Object(values)) => serde_json::to_string(&values)
+      .map(Some)
+      .map_err(AppError::from),
+    Some(_) => Err(AppError::Validation("模型参数必须是 JSON 对象".to_string())),
+  }
+}
+
+fn decode_params(value: &str) -> Value {
+  match serde_json::from_str::<Value>(value) {
+    Ok(Value::Object(values)) => Value::Object(values),
+    _ => Value::Object(Map::new()),
+  }
+}
+
+fn open_connection(db_path: &Path) -> Result<Connection, AppError> {
+  let connection = Connection::open(db_path).map_err(AppError::from)?;
+  configure_connection(&connection)?;
   Ok(connection)
 }
 
-fn initialize_database(db_path: &Path) -> Result<(), String> {
-  let connection = open_connection(db_path)?;
-  connection
-    .execute_batch(
+fn configure_connection(connection: &Connection) -> Result<(), AppError> {
+  connection
+    .busy_timeout(DATABASE_BUSY_TIMEOUT)
+    .map_err(AppError::from)?;
+  connection
+    .execute("PRAGMA foreign_keys = ON", [])
+    .map_err(AppError::from)?;
+  Ok(())
+}
+
+#[cfg(test)]
+pub(crate) fn init_in_memory() -> Connection {
+  let mut connection = Connection::open_in_memory().expect("failed to open in-memory database");
+  configure_connection(&connection).expect("failed to configure in-memory database");
+  initialize_schema(&mut connection).expect("failed to initialize in-memory database");
+  connection
+}
+
+fn retry_on_busy<T>(mut operation: impl FnMut() -> Result<T, AppError>) -> Result<T, AppError> {
+  let mut attempt = 1;
+  loop {
+    match operation() {
+      Err(AppError::Busy(message)) if attempt < MAX_BUSY_ATTEMPTS => {
+        log::warn!("database busy on attempt {attempt}, retrying: {message}");
+        std::thread::sleep(Duration::from_millis(BUSY_RETRY_DELAY_MS * attempt));
+        attempt += 1;
+      }
+      result => return result,
+    }
+  }
+}
+
+fn run_migrations(connection: &mut Connection) -> Result<(), AppError> {
+  let current_version = connection
+    .query_row("PRAGMA user_version", [], |row| row.get::<_, i64>(0))
+    .map_err(AppError::from)?;
+
+  for (index, migration) in SCHEMA_MIGRATIONS.iter().enumerate() {
+    let target_version = index as i64 + 1;
+    if target_version <= current_version {
+      continue;
+    }
+
+    let transaction = connection.transaction().map_err(AppError::from)?;
+    transaction
+      .execute_batch(migration)
+      .map_err(|error| AppError::Database(format!("数据库迁移 {target_version} 失败: {error}")))?;
+    transaction
+      .pragma_update(None, "user_version", target_version)
+      .map_err(AppError::from)?;
+    transaction.commit().map_err(AppError::from)?;
+  }
+
+  Ok(())
+}
+
+fn initialize_database(db_path: &Path) -> Result<(), AppError> {
+  let mut connection = open_connection(db_path)?;
+  initialize_schema(&mut connection)
+}
+
+fn initialize_schema(connection: &mut Connection) -> Result<(), AppError> {
+  connection
+    .execute_batch(
+      "
+      CREATE TABLE IF NOT EXISTS prompts (
+        id INTEGER PRIMARY KEY AUTOINCREMENT,
+        title TEXT NOT NULL,
+        content TEXT NOT NULL,
+        tags TEXT NOT NULL DEFAULT '[]',
+        is_favorite INTEGER NOT NULL DEFAULT 0,
+        score_avg REAL NOT NULL DEFAULT 0,
+        score_count INTEGER NOT NULL DEFAULT 0,
+        created_at TEXT NOT NULL,
+        updated_at TEXT NOT NULL
+      );
+
+      CREATE TABLE IF NOT EXISTS prompt_versions (
+        id INTEGER PRIMARY KEY AUTOINCREMENT,
+        prompt_id INTEGER NOT NULL,
+        content TEXT NOT NULL,
+        change_note TEXT NOT NULL DEFAULT '',
+        created_at TEXT NOT NULL,
+        FOREIGN KEY(prompt_id) REFERENCES prompts(id) ON DELETE CASCADE
+      );
+
+      CREATE TABLE IF NOT EXISTS usage_logs (
+        id INTEGER PRIMARY KEY AUTOINCREMENT,
+        prompt_id INTEGER NOT NULL,
+        input_vars TEXT NOT NULL DEFAULT '{}',
+        output_text TEXT NOT NULL,
+        rating INTEGER,
+        used_at TEXT NOT NULL,
+        FOREIGN KEY(prompt_id) REFERENCES prompts(id) ON DELETE CASCADE
+      );
+
+      CREATE TABLE IF NOT EXISTS app_settings (
+        key TEXT PRIMARY KEY,
+        value TEXT NOT NULL,
+        updated_at TEXT NOT NULL
+      );
+
+      CREATE INDEX IF NOT EXISTS idx_prompts_updated_at ON prompts(updated_at);
+      CREATE INDEX IF NOT EXISTS idx_prompt_versions_prompt_id ON prompt_versions(prompt_id);
+      CREATE INDEX IF NOT EXISTS idx_usage_logs_prompt_id ON usage_logs(prompt_id);
+      ",
+    )
+    .map_err(AppError::from)?;
+
+  run_migrations(connection)?;
+  backfill_version_hashes(connection)?;
+  backfill_compressed_search_content(connection)?;
+
+  connection
+    .execute(
+      "
+      INSERT INTO app_settings (key, value, updated_at)
+      VALUES (?1, ?2, ?3)
+      ON CONFLICT(key) DO NOTHING
+      ",
+      params![
+        GLOBAL_SHORTCUT_SETTING_KEY,
+        DEFAULT_GLOBAL_SHORTCUT,
+        now_iso()
+      ],
+    )
+    .map_err(AppError::from)?;
+
+  Ok(())
+}
+
+fn normalize_shortcut(shortcut: &str) -> Result<String, AppError> {
+  let normalized = shortcut.trim().to_string();
+  if normalized.is_empty() {
+    return Err(AppError::Validation("全局快捷键不能为空".to_string()));
+  }
+  Ok(normalized)
+}
+
+fn read_global_shortcut_setting(connection: &Connection) -> Result<String, AppError> {
+  let stored_value = connection
+    .query_row(
+      "SELECT value FROM app_settings WHERE key = ?1 LIMIT 1",
+      params![GLOBAL_SHORTCUT_SETTING_KEY],
+      |row| row.get::<_, String>(0),
+    )
+    .optional()
+    .map_err(AppError::from)?;
+
+  let normalized = stored_value
+    .as_deref()
+    .and_then(|value| normalize_shortcut(value).ok())
+    .unwrap_or_else(|| DEFAULT_GLOBAL_SHORTCUT.to_string());
+
+  Ok(normalized)
+}
+
+fn persist_global_shortcut_setting(
+  connection: &Connection,
+  shortcut: &str,
+) -> Result<(), AppError> {
+  connection
+    .execute(
+      "
+      INSERT INTO app_settings (key, value, updated_at)
+      VALUES (?1, ?2, ?3)
+      ON CONFLICT(key) DO UPDATE SET value = excluded.value, updated_at = excluded.updated_at
+      ",
+      params![GLOBAL_SHORTCUT_SETTING_KEY, shortcut, now_iso()],
+    )
+    .map_err(AppError::from)?;
+
+  Ok(())
+}
+
+fn read_setting(connection: &Connection, key: &str) -> Result<Option<String>, AppError> {
+  connection
+    .query_row(
+      "SELECT value FROM app_settings WHERE key = ?1 LIMIT 1",
+      params![key],
+      |row| row.get::<_, String>(0),
+    )
+    .optional()
+    .map_err(AppError::from)
+}
+
+fn write_setting(connection: &Connection, key: &str, value: &str) -> Result<(), AppError> {
+  connection
+    .execute(
+      "
+      INSERT INTO app_settings (key, value, updated_at)
+      VALUES (?1, ?2, ?3)
+      ON CONFLICT(key) DO UPDATE SET value = excluded.value, updated_at = excluded.updated_at
+      ",
+      params![key, value, now_iso()],
+    )
+    .map_err(AppError::from)?;
+
+  Ok(())
+}
+
+fn read_usage_log_limit(connection: &Connection) -> Result<i64, AppError> {
+  let limit = read_setting(connection, USAGE_LOG_LIMIT_SETTING_KEY)?
+    .and_then(|value| value.trim().parse::<i64>().ok())
+    .filter(|value| *value >= 0)
+    .unwrap_or(DEFAULT_USAGE_LOG_LIMIT);
+
+  Ok(limit)
+}
+
+fn read_max_tag_count(connection: &Connection) -> Result<i64, AppError> {
+  let limit = read_setting(connection, MAX_TAGS_SETTING_KEY)?
+    .and_then(|value| value.trim().parse::<i64>().ok())
+    .filter(|value| *value >= 0)
+    .unwrap_or(DEFAULT_MAX_TAGS);
+
+  Ok(limit)
+}
+
+fn read_output_text_limit(connection: &Connection) -> Result<i64, AppError> {
+  let limit = read_setting(connection, OUTPUT_TEXT_LIMIT_SETTING_KEY)?
+    .and_then(|value| value.trim().parse::<i64>().ok())
+    .filter(|value| *value >= 0)
+    .unwrap_or(DEFAULT_OUTPUT_TEXT_LIMIT);
+
+  Ok(limit)
+}
+
+fn truncate_output_text(output_text: &str, limit: i64) -> (String, Option<i64>) {
+  let original_length = output_text.chars().count() as i64;
+  if limit == 0 || original_length <= limit {
+    return (output_text.to_string(), None);
+  }
+
+  let mut truncated = output_text.chars().take(limit as usize).collect::<String>();
+  truncated.push_str(OUTPUT_TRUNCATION_MARKER);
+  (truncated, Some(original_length))
+}
+
+fn read_content_compression_threshold(connection: &Connection) -> Result<i64, AppError> {
+  let threshold = read_setting(connection, CONTENT_COMPRESSION_SETTING_KEY)?
+    .and_then(|value| value.trim().parse::<i64>().ok())
+    .filter(|value| *value >= 0)
+    .unwrap_or(0);
+
+  Ok(threshold)
+}
+
+fn read_content_normalization(connection: &Connection) -> Result<bool, AppError> {
+  let enabled = read_setting(connection, CONTENT_NORMALIZATION_SETTING_KEY)?
+    .is_some_and(|value| value.trim() == "true");
+
+  Ok(enabled)
+}
+
+fn normalize_prompt_content(content: &str) -> String {
+  content
+    .replace("\r\n", "\n")
+    .replace('\r', "\n")
+    .split('\n')
+    .map(|line| line.trim_end_matches([' ', '\t']))
+    .collect::<Vec<_>>()
+    .join("\n")
+}
+
+fn read_pinned_tags(connection: &Connection) -> Result<Vec<String>, AppError> {
+  let pinned_tags = read_setting(connection, PINNED_TAGS_SETTING_KEY)?
+    .and_then(|value| serde_json::from_str::<Vec<String>>(&value).ok())
+    .unwrap_or_default();
+
+  Ok(pinned_tags)
+}
+
+fn write_pinned_tags(connection: &Connection, pinned_tags: &[String]) -> Result<(), AppError> {
+  let value = serde_json::to_string(pinned_tags).map_err(AppError::from)?;
+  write_setting(connection, PINNED_TAGS_SETTING_KEY, &value)
+}
+
+fn read_auto_backup_config(connection: &Connection) -> Result<AutoBackupConfig, AppError> {
+  let read_count = |key: &str, default: i64| -> Result<i64, AppError> {
+    Ok(
+      read_setting(connection, key)?
+        .and_then(|value| value.trim().parse::<i64>().ok())
+        .filter(|value| *value >= 1)
+        .unwrap_or(default),
+    )
+  };
+
+  Ok(AutoBackupConfig {
+    enabled: read_setting(connection, AUTO_BACKUP_ENABLED_SETTING_KEY)?
+      .as_deref()
+      .map(str::trim)
+      != Some("false"),
+    interval_days: read_count(
+      AUTO_BACKUP_INTERVAL_SETTING_KEY,
+      DEFAULT_AUTO_BACKUP_INTERVAL_DAYS,
+    )?,
+    keep: read_count(AUTO_BACKUP_KEEP_SETTING_KEY, DEFAULT_AUTO_BACKUP_KEEP)?,
+    last_backup_at: read_setting(connection, LAST_BACKUP_AT_SETTING_KEY)?,
+  })
+}
+
+fn prune_backups(backup_dir: &Path, keep: i64) -> Result<(), AppError> {
+  let mut backup_files = fs::read_dir(backup_dir)
+    .map_err(|error| AppError::Io(format!("读取备份目录失败: {error}")))?
+    .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+    .filter(|path| {
+      path
+        .file_name()
+        .and_then(|name| name.to_str())
+        .is_some_and(|name| name.starts_with(BACKUP_FILE_PREFIX) && name.ends_with(".db"))
+    })
+    .collect::<Vec<_>>();
+  backup_files.sort();
+
+  let excess = backup_files.len().saturating_sub(keep.max(1) as usize);
+  for path in backup_files.into_iter().take(excess) {
+    fs::remove_file(&path)
+      .map_err(|error| AppError::Io(format!("删除旧备份 {} 失败: {error}", path.display())))?;
+  }
+  Ok(())
+}
+
+fn run_auto_backup_if_due(db_path: &Path) -> Result<Option<PathBuf>, AppError> {
+  let connection = open_connection(db_path)?;
+  let config = read_auto_backup_config(&connection)?;
+  if !config.enabled {
+    return Ok(None);
+  }
+
+  let now = Utc::now();
+  let is_due = config
+    .last_backup_at
+    .as_deref()
+    .and_then(|value| DateTime::parse_from_rfc3339(value).ok())
+    .map(|last_backup_at| {
+      now.signed_duration_since(last_backup_at.with_timezone(&Utc))
+        >= chrono::Duration::days(config.interval_days)
+    });
+  if is_due == Some(false) {
+    return Ok(None);
+  }
+
+  let backup_dir = db_path
+    .parent()
+    .map(|parent| parent.join(BACKUP_DIR_NAME))
+    .unwrap_or_else(|| PathBuf::from(BACKUP_DIR_NAME));
+  fs::create_dir_all(&backup_dir)
+    .map_err(|error| AppError::Io(format!("创建备份目录失败: {error}")))?;
+  let backup_path = backup_dir.join(format!(
+    "{BACKUP_FILE_PREFIX}{}.db",
+    now.format("%Y%m%d-%H%M%S")
+  ));
+  connection
+    .execute(
+      "VACUUM INTO ?1",
+      params![backup_path.to_string_lossy().to_string()],
+    )
+    .map_err(AppError::from)?;
+  write_setting(&connection, LAST_BACKUP_AT_SETTING_KEY, &now.to_rfc3339())?;
+  prune_backups(&backup_dir, config.keep)?;
+  Ok(Some(backup_path))
+}
+
+fn validate_rating(score: i64) -> Result<(), AppError> {
+  if !(1..=5).contains(&score) {
+    return Err(AppError::Validation(
+      "评分范围必须在 1 到 5 之间".to_string(),
+    ));
+  }
+  Ok(())
+}
+
+fn apply_prompt_rating(
+  connection: &Connection,
+  prompt_id: i64,
+  score: i64,
+) -> Result<bool, AppError> {
+  let score_state = connection
+    .query_row(
+      "SELECT score_avg, score_count FROM prompts WHERE id = ?1",
+      params![prompt_id],
+      |row| Ok((row.get::<_, f64>(0)?, row.get::<_, i64>(1)?)),
+    )
+    .optional()
+    .map_err(AppError::from)?;
+
+  let Some((score_avg, score_count)) = score_state else {
+    return Ok(false);
+  };
+
+  let next_count = score_count + 1;
+  let next_avg = ((score_avg * score_count as f64) + score as f64) / next_count as f64;
+  connection
+    .execute(
+      "
+      UPDATE prompts
+      SET score_avg = ?1, score_count = ?2
+      WHERE id = ?3
+      ",
+      params![next_avg, next_count, prompt_id],
+    )
+    .map_err(AppError::from)?;
+
+  Ok(true)
+}
+
+fn record_direct_rating(
+  connection: &Connection,
+  prompt_id: i64,
+  score: i64,
+) -> Result<bool, AppError> {
+  let updated_rows = connection
+    .execute(
+      "
+      UPDATE prompts
+      SET direct_rating_sum = direct_rating_sum + ?1, direct_rating_count = direct_rating_count + 1
+      WHERE id = ?2
+      ",
+      params![score, prompt_id],
+    )
+    .map_err(AppError::from)?;
+  if updated_rows == 0 {
+    return Ok(false);
+  }
+
+  recompute_prompt_score(connection, prompt_id)?;
+  Ok(true)
+}
+
+fn adopt_unrecorded_ratings(connection: &Connection, prompt_id: i64) -> Result<(), AppError> {
+  connection
+    .execute(
+      "
+      UPDATE prompts
+      SET direct_rating_count = MAX(score_count - (SELECT COUNT(rating) FROM usage_logs WHERE usage_logs.prompt_id = prompts.id), 0),
+          direct_rating_sum = CASE
+            WHEN score_count > (SELECT COUNT(rating) FROM usage_logs WHERE usage_logs.prompt_id = prompts.id)
+            THEN MAX(score_avg * score_count - (SELECT COALESCE(SUM(rating), 0) FROM usage_logs WHERE usage_logs.prompt_id = prompts.id), 0)
+            ELSE 0
+          END
+      WHERE id = ?1
+      ",
+      params![prompt_id],
+    )
+    .map_err(AppError::from)?;
+  Ok(())
+}
+
+fn recompute_prompt_score(connection: &Connection, prompt_id: i64) -> Result<(), AppError> {
+  let (rating_sum, rating_count) = connection
+    .query_row(
+      "
+      SELECT
+        direct_rating_sum + (SELECT COALESCE(SUM(rating), 0) FROM usage_logs WHERE prompt_id = prompts.id),
+        direct_rating_count + (SELECT COUNT(rating) FROM usage_logs WHERE prompt_id = prompts.id)
+      FROM prompts
+      WHERE id = ?1
+      ",
+      params![prompt_id],
+      |row| Ok((row.get::<_, f64>(0)?, row.get::<_, i64>(1)?)),
+    )
+    .map_err(AppError::from)?;
+  let score_count = rating_count;
+  let score_avg = if score_count > 0 {
+    rating_sum / score_count as f64
+  } else {
+    0.0
+  };
+
+  connection
+    .execute(
+      "UPDATE prompts SET score_avg = ?1, score_count = ?2 WHERE id = ?3",
+      params![score_avg, score_count, prompt_id],
+    )
+    .map_err(AppError::from)?;
+
+  Ok(())
+}
+
+fn evict_usage_logs(connection: &Connection, prompt_id: i64, limit: i64) -> Result<i64, AppError> {
+  if limit == 0 {
+    return Ok(0);
+  }
+
+  let mut statement = connection
+    .prepare(
+      "
+      SELECT id, rating
+      FROM usage_logs
+      WHERE prompt_id = ?1
+      ORDER BY used_at DESC, id DESC
+      LIMIT -1 OFFSET ?2
+      ",
+    )
+    .map_err(AppError::from)?;
+  let rows = statement
+    .query_map(params![prompt_id, limit], |row| {
+      Ok((row.get::<_, i64>(0)?, row.get::<_, Option<i64>>(1)?))
+    })
+    .map_err(AppError::from)?;
+
+  let mut evicted_ids = Vec::new();
+  let mut evicted_rating = false;
+  for row in rows {
+    let (log_id, rating) = row.map_err(AppError::from)?;
+    evicted_rating |= rating.is_some();
+    evicted_ids.push(log_id);
+  }
+
+  for log_id in &evicted_ids {
+    connection
+      .execute("DELETE FROM usage_logs WHERE id = ?1", params![log_id])
+      .map_err(AppError::from)?;
+  }
+
+  if evicted_rating {
+    recompute_prompt_score(connection, prompt_id)?;
+  }
+
+  Ok(evicted_ids.len() as i64)
+}
+
+fn apply_global_shortcut<R: tauri::Runtime>(
+  app_handle: &AppHandle<R>,
+  shortcut: &str,
+) -> Result<(), AppError> {
+  let shortcut_manager = app_handle.global_shortcut();
+  shortcut_manager
+    .unregister_all()
+    .map_err(|error| AppError::Validation(error.to_string()))?;
+  shortcut_manager
+    .register(shortcut)
+    .map_err(|error| AppError::Validation(error.to_string()))?;
+
+  Ok(())
+}
+
+fn read_prompt_content(
+  row: &rusqlite::Row<'_>,
+  content_index: usize,
+  compressed_index: usize,
+) -> rusqlite::Result<String> {
+  if row.get::<_, i64>(compressed_index)? == 0 {
+    return row.get(content_index);
+  }
+
+  let compressed: Vec<u8> = row.get(content_index)?;
+  let conversion_error = |error: Box<dyn std::error::Error + Send + Sync>| {
+    rusqlite::Error::FromSqlConversionFailure(content_index, rusqlite::types::Type::Blob, error)
+  };
+  let bytes =
+    zstd::decode_all(compressed.as_slice()).map_err(|error| conversion_error(Box::new(error)))?;
+  String::from_utf8(bytes).map_err(|error| conversion_error(Box::new(error)))
+}
+
+fn compress_stored_content(
+  connection: &Connection,
+  prompt_id: i64,
+  content: &str,
+  threshold: i64,
+) -> Result<(), AppError> {
+  if threshold <= 0 || content.len() as i64 <= threshold {
+    return Ok(());
+  }
+
+  let compressed = zstd::encode_all(content.as_bytes(), CONTENT_COMPRESSION_LEVEL)
+    .map_err(|error| AppError::Serialization(format!("压缩 Prompt 内容失败: {error}")))?;
+  connection
+    .execute(
+      "UPDATE prompts SET content = ?1, content_compressed = 1 WHERE id = ?2",
+      params![compressed, prompt_id],
+    )
+    .map_err(AppError::from)?;
+  Ok(())
+}
+
+fn row_to_prompt(row: &rusqlite::Row<'_>) -> rusqlite::Result<PromptRecord> {
+  let tags_raw: String = row.get(3)?;
+  let content = read_prompt_content(row, 2, 17)?;
+  let stats = compute_text_stats(&content);
+  Ok(PromptRecord {
+    id: row.get(0)?,
+    title: row.get(1)?,
+    content,
+    tags: decode_tags(&tags_raw),
+    is_favorite: row.get::<_, i64>(4)? == 1,
+    score_avg: row.get(5)?,
+    score_count: row.get(6)?,
+    created_at: row.get(7)?,
+    updated_at: row.get(8)?,
+    description: row.get(9)?,
+    source_url: row.get(10)?,
+    author: row.get(20)?,
+    model: row.get(11)?,
+    language: row.get(12)?,
+    params: decode_params(&row.get::<_, String>(13)?),
+    icon: row.get(14)?,
+    category_id: row.get(15)?,
+    meta: decode_meta(row.get(16)?),
+    variables_schema: decode_variables_schema(&row.get::<_, String>(18)?),
+    last_used_at: row.get(19)?,
+    char_count: stats.char_count,
+    word_count: stats.word_count,
+    estimated_tokens: stats.estimated_tokens,
+    collections: None,
+    uuid: row.get(21)?,
+  })
+}
+
+fn fetch_prompt(connection: &Connection, prompt_id: i64) -> Result<Option<PromptRecord>, AppError> {
+  let mut statement = connection
+    .prepare(&format!(
+      "SELECT {PROMPT_COLUMNS} FROM prompts WHERE id = ?1 LIMIT 1"
+    ))
+    .map_err(AppError::from)?;
+
+  statement
+    .query_row(params![prompt_id], row_to_prompt)
+    .optional()
+    .map_err(AppError::from)
+}
+
+fn fetch_example_output(connection: &Connection, prompt_id: i64) -> Result<String, AppError> {
+  connection
+    .query_row(
+      "SELECT example_output FROM prompts WHERE id = ?1",
+      params![prompt_id],
+      |row| row.get::<_, String>(0),
+    )
+    .optional()
+    .map(Option::unwrap_or_default)
+    .map_err(AppError::from)
+}
+
+fn row_to_prompt_version(row: &rusqlite::Row<'_>) -> rusqlite::Result<PromptVersionRecord> {
+  let content: String = row.get(2)?;
+  let stats = compute_text_stats(&content);
+  Ok(PromptVersionRecord {
+    id: row.get(0)?,
+    prompt_id: row.get(1)?,
+    content,
+    change_note: row.get(3)?,
+    created_at: row.get(4)?,
+    char_count: stats.char_count,
+    word_count: stats.word_count,
+    estimated_tokens: stats.estimated_tokens,
+  })
+}
+
+fn fetch_prompt_versions(
+  connection: &Connection,
+  prompt_id: i64,
+) -> Result<Vec<PromptVersionRecord>, AppError> {
+  let mut statement = connection
+    .prepare(
+      "
+      SELECT id, prompt_id, content, change_note, created_at
+      FROM prompt_versions
+      WHERE prompt_id = ?1
+      ORDER BY created_at DESC, id DESC
+      ",
+    )
+    .map_err(AppError::from)?;
+
+  let rows = statement
+    .query_map(params![prompt_id], row_to_prompt_version)
+    .map_err(AppError::from)?;
+
+  let mut versions = Vec::new();
+  for row in rows {
+    versions.push(row.map_err(AppError::from)?);
+  }
+  Ok(versions)
+}
+
+fn fetch_last_input_vars(connection: &Connection, prompt_id: i64) -> Result<Value, AppError> {
+  let input_vars = connection
+    .query_row(
+      "
+      SELECT input_vars
+      FROM usage_logs
+      WHERE prompt_id = ?1 AND input_vars <> '{}'
+      ORDER BY id DESC
+      LIMIT 1
+      ",
+      params![prompt_id],
+      |row| row.get::<_, String>(0),
+    )
+    .optional()
+    .map_err(AppError::from)?;
+
+  Ok(
+    input_vars
+      .and_then(|value| serde_json::from_str(&value).ok())
+      .unwrap_or_else(|| Value::Object(Map::new())),
+  )
+}
+
+fn row_to_snippet(row: &rusqlite::Row<'_>) -> rusqlite::Result<SnippetRecord> {
+  Ok(SnippetRecord {
+    id: row.get(0)?,
+    name: row.get(1)?,
+    content: row.get(2)?,
+    created_at: row.get(3)?,
+    updated_at: row.get(4)?,
+  })
+}
+
+fn fetch_snippets(connection: &Connection) -> Result<Vec<SnippetRecord>, AppError> {
+  let mut statement = connection
+    .prepare(
+      "SELECT id, name, content, created_at, updated_at FROM snippets ORDER BY name COLLATE NOCASE ASC",
+    )
+    .map_err(AppError::from)?;
+  let rows = statement
+    .query_map([], row_to_snippet)
+    .map_err(AppError::from)?;
+
+  let mut snippets = Vec::new();
+  for row in rows {
+    snippets.push(row.map_err(AppError::from)?);
+  }
+  Ok(snippets)
+}
+
+fn fetch_snippet(
+  connection: &Connection,
+  snippet_id: i64,
+) -> Result<Option<SnippetRecord>, AppError> {
+  connection
+    .query_row(
+      "SELECT id, name, content, created_at, updated_at FROM snippets WHERE id = ?1",
+      params![snippet_id],
+      row_to_snippet,
+    )
+    .optional()
+    .map_err(AppError::from)
+}
+
+fn load_snippet_contents(connection: &Connection) -> Result<HashMap<String, String>, AppError> {
+  Ok(
+    fetch_snippets(connection)?
+      .into_iter()
+      .map(|snippet| (snippet.name.to_lowercase(), snippet.content))
+      .collect(),
+  )
+}
+
+fn normalize_test_case_name(name: &str) -> Result<String, AppError> {
+  let normalized = name.trim().to_string();
+  if normalized.is_empty() {
+    return Err(AppError::Validation("测试用例名称不能为空".to_string()));
+  }
+  if normalized.chars().count() > MAX_TEST_CASE_NAME_CHARS {
+    return Err(AppError::Validation(format!(
+      "测试用例名称不能超过 {MAX_TEST_CASE_NAME_CHARS} 个字符"
+    )));
+  }
+  Ok(normalized)
+}
+
+fn row_to_test_case(row: &rusqlite::Row<'_>) -> rusqlite::Result<PromptTestCaseRecord> {
+  let variables_raw: String = row.get(3)?;
+  Ok(PromptTestCaseRecord {
+    id: row.get(0)?,
+    prompt_id: row.get(1)?,
+    name: row.get(2)?,
+    variables: serde_json::from_str(&variables_raw).unwrap_or_else(|_| Value::Object(Map::new())),
+    created_at: row.get(4)?,
+    updated_at: row.get(5)?,
+  })
+}
+
+fn fetch_test_cases(
+  connection: &Connection,
+  prompt_id: i64,
+) -> Result<Vec<PromptTestCaseRecord>, AppError> {
+  let mut statement = connection
+    .prepare(
+      "
+      SELECT id, prompt_id, name, variables_json, created_at, updated_at
+      FROM prompt_test_cases
+      WHERE prompt_id = ?1
+      ORDER BY name COLLATE NOCASE ASC
+      ",
+    )
+    .map_err(AppError::from)?;
+  let rows = statement
+    .query_map(params![prompt_id], row_to_test_case)
+    .map_err(AppError::from)?;
+
+  let mut test_cases = Vec::new();
+  for row in rows {
+    test_cases.push(row.map_err(AppError::from)?);
+  }
+  Ok(test_cases)
+}
+
+fn fetch_test_case(
+  connection: &Connection,
+  test_case_id: i64,
+) -> Result<Option<PromptTestCaseRecord>, AppError> {
+  connection
+    .query_row(
+      "SELECT id, prompt_id, name, variables_json, created_at, updated_at FROM prompt_test_cases WHERE id = ?1",
+      params![test_case_id],
+      row_to_test_case,
+    )
+    .optional()
+    .map_err(AppError::from)
+}
+
+fn insert_test_case(
+  connection: &Connection,
+  prompt_id: i64,
+  name: &str,
+  variables: &Map<String, Value>,
+) -> Result<bool, AppError> {
+  let variables_json = serde_json::to_string(variables).map_err(AppError::from)?;
+  let inserted_rows = connection
+    .execute(
+      "
+      INSERT INTO prompt_test_cases (prompt_id, name, variables_json, created_at, updated_at)
+      VALUES (?1, ?2, ?3, ?4, ?4)
+      ON CONFLICT(prompt_id, name) DO NOTHING
+      ",
+      params![prompt_id, name, variables_json, now_iso()],
+    )
+    .map_err(AppError::from)?;
+  Ok(inserted_rows > 0)
+}
+
+fn normalize_preset_name(name: &str) -> Result<String, AppError> {
+  let normalized = name.trim().to_string();
+  if normalized.is_empty() {
+    return Err(AppError::Validation("预设名称不能为空".to_string()));
+  }
+  if normalized.chars().count() > MAX_PRESET_NAME_CHARS {
+    return Err(AppError::Validation(format!(
+      "预设名称不能超过 {MAX_PRESET_NAME_CHARS} 个字符"
+    )));
+  }
+  Ok(normalized)
+}
+
+fn row_to_variable_preset(row: &rusqlite::Row<'_>) -> rusqlite::Result<VariablePresetRecord> {
+  let values_raw: String = row.get(3)?;
+  Ok(VariablePresetRecord {
+    id: row.get(0)?,
+    prompt_id: row.get(1)?,
+    name: row.get(2)?,
+    values: serde_json::from_str(&values_raw).unwrap_or_else(|_| Value::Object(Map::new())),
+    created_at: row.get(4)?,
+    updated_at: row.get(5)?,
+  })
+}
+
+fn fetch_variable_presets(
+  connection: &Connection,
+  prompt_id: i64,
+) -> Result<Vec<VariablePresetRecord>, AppError> {
+  let mut statement = connection
+    .prepare(
+      "
+      SELECT id, prompt_id, name, values_json, created_at, updated_at
+      FROM variable_presets
+      WHERE prompt_id = ?1
+      ORDER BY name COLLATE NOCASE ASC
+      ",
+    )
+    .map_err(AppError::from)?;
+  let rows = statement
+    .query_map(params![prompt_id], row_to_variable_preset)
+    .map_err(AppError::from)?;
+
+  let mut presets = Vec::new();
+  for row in rows {
+    presets.push(row.map_err(AppError::from)?);
+  }
+  Ok(presets)
+}
+
+fn fetch_variable_preset(
+  connection: &Connection,
+  preset_id: i64,
+) -> Result<Option<VariablePresetRecord>, AppError> {
+  connection
+    .query_row(
+      "SELECT id, prompt_id, name, values_json, created_at, updated_at FROM variable_presets WHERE id = ?1",
+      params![preset_id],
+      row_to_variable_preset,
+    )
+    .optional()
+    .map_err(AppError::from)
+}
+
+fn upsert_variable_preset(
+  connection: &Connection,
+  prompt_id: i64,
+  name: &str,
+  values: &Map<String, Value>,
+) -> Result<i64, AppError> {
+  let values_json = serde_json::to_string(values).map_err(AppError::from)?;
+  let timestamp = now_iso();
+  connection
+    .execute(
+      "
+      INSERT INTO variable_presets (prompt_id, name, values_json, created_at, updated_at)
+      VALUES (?1, ?2, ?3, ?4, ?4)
+      ON CONFLICT(prompt_id, name) DO UPDATE SET
+        values_json = excluded.values_json,
+        updated_at = excluded.updated_at
+      ",
+      params![prompt_id, name, values_json, timestamp],
+    )
+    .map_err(AppError::from)?;
+
+  connection
+    .query_row(
+      "SELECT id FROM variable_presets WHERE prompt_id = ?1 AND name = ?2",
+      params![prompt_id, name],
+      |row| row.get(0),
+    )
+    .map_err(AppError::from)
+}
+
+fn ensure_category(connection: &Connection, name: &str) -> Result<i64, AppError> {
+  let timestamp = now_iso();
+  connection
+    .execute(
+      "
+      INSERT INTO categories (name, created_at, updated_at)
+      VALUES (?1, ?2, ?2)
+      ON CONFLICT(name) DO NOTHING
+      ",
+      params![name, timestamp],
+    )
+    .map_err(AppError::from)?;
+
+  connection
+    .query_row(
+      "SELECT id FROM categories WHERE name = ?1",
+      params![name],
+      |row| row.get::<_, i64>(0),
+    )
+    .map_err(AppError::from)
+}
+
+fn fetch_category(
+  connection: &Connection,
+  category_id: i64,
+) -> Result<Option<CategoryRecord>, AppError> {
+  connection
+    .query_row(
+      "
+      SELECT categories.id, categories.name, COUNT(prompts.id), categories.created_at, categories.updated_at
+      FROM categories
+      LEFT JOIN prompts ON prompts.category_id = categories.id
+      WHERE categories.id = ?1
+      GROUP BY categories.id
+      ",
+      params![category_id],
+      |row| {
+        Ok(CategoryRecord {
+          id: row.get(0)?,
+          name: row.get(1)?,
+          count: row.get(2)?,
+          created_at: row.get(3)?,
+          updated_at: row.get(4)?,
+        })
+      },
+    )
+    .optional()
+    .map_err(AppError::from)
+}
+
+fn upsert_prompt_meta(
+  connection: &Connection,
+  prompt_id: i64,
+  key: &str,
+  value: &str,
+) -> Result<(), AppError> {
+  let key_exists = connection
+    .query_row(
+      "SELECT 1 FROM prompt_meta WHERE prompt_id = ?1 AND key = ?2",
+      params![prompt_id, key],
+      |_| Ok(()),
+    )
+    .optional()
+    .map_err(AppError::from)?
+    .is_some();
+
+  if !key_exists {
+    let key_count = connection
+      .query_row(
+        "SELECT COUNT(*) FROM prompt_meta WHERE prompt_id = ?1",
+        params![prompt_id],
+        |row| row.get::<_, i64>(0),
+      )
+      .map_err(AppError::from)?;
+    if key_count as usize >= MAX_META_KEYS_PER_PROMPT {
+      return Err(AppError::Validation(format!(
+        "每个 Prompt 最多只能设置 {MAX_META_KEYS_PER_PROMPT} 个元数据字段"
+      )));
+    }
+  }
+
+  connection
+    .execute(
+      "
+      INSERT INTO prompt_meta (prompt_id, key, value)
+      VALUES (?1, ?2, ?3)
+      ON CONFLICT(prompt_id, key) DO UPDATE SET value = excluded.value
+      ",
+      params![prompt_id, key, value],
+    )
+    .map_err(AppError::from)?;
+
+  Ok(())
+}
+
+fn fetch_smart_collection(
+  connection: &Connection,
+  smart_collection_id: i64,
+) -> Result<Option<SmartCollectionRecord>, AppError> {
+  let row = connection
+    .query_row(
+      "SELECT id, name, filter, created_at, updated_at FROM smart_collections WHERE id = ?1",
+      params![smart_collection_id],
+      |row| {
+        Ok((
+          row.get::<_, i64>(0)?,
+          row.get::<_, String>(1)?,
+          row.get::<_, String>(2)?,
+          row.get::<_, String>(3)?,
+          row.get::<_, String>(4)?,
+        ))
+      },
+    )
+    .optional()
+    .map_err(AppError::from)?;
+
+  let Some((id, name, raw_filter, created_at, updated_at)) = row else {
+    return Ok(None);
+  };
+  let filter = serde_json::from_str::<PromptFilter>(&raw_filter)
+    .map_err(|error| AppError::Serialization(format!("智能集合「{name}」的规则无效: {error}")))?;
+  let prompt_count = count_prompts(connection, &filter)?;
+
+  Ok(Some(SmartCollectionRecord {
+    id,
+    name,
+    filter,
+    prompt_count,
+    created_at,
+    updated_at,
+  }))
+}
+
+fn row_to_collection(row: &rusqlite::Row<'_>) -> rusqlite::Result<CollectionRecord> {
+  Ok(CollectionRecord {
+    id: row.get(0)?,
+    name: row.get(1)?,
+    parent_id: row.get(2)?,
+    depth: 0,
+    prompt_count: row.get(3)?,
+    created_at: row.get(4)?,
+    updated_at: row.get(5)?,
+  })
+}
+
+fn fetch_collection_ancestor_ids(
+  connection: &Connection,
+  collection_id: i64,
+) -> Result<Vec<i64>, AppError> {
+  let mut statement = connection
+    .prepare(
+      "
+      WITH RECURSIVE ancestors(id) AS (
+        SELECT parent_id FROM collections WHERE id = ?1 AND parent_id IS NOT NULL
+        UNION
+        SELECT collections.parent_id
+        FROM collections
+        JOIN ancestors ON collections.id = ancestors.id
+        WHERE collections.parent_id IS NOT NULL
+      )
+      SELECT id FROM ancestors
+      ",
+    )
+    .map_err(AppError::from)?;
+  let rows = statement
+    .query_map(params![collection_id], |row| row.get::<_, i64>(0))
+    .map_err(AppError::from)?;
+
+  let mut ancestor_ids = Vec::new();
+  for row in rows {
+    ancestor_ids.push(row.map_err(AppError::from)?);
+  }
+  Ok(ancestor_ids)
+}
+
+fn fetch_collection_subtree_ids(
+  connection: &Connection,
+  collection_id: i64,
+) -> Result<Vec<i64>, AppError> {
+  let mut statement = connection
+    .prepare(
+      "
+      WITH RECURSIVE subtree(id) AS (
+        SELECT ?1
+        UNION
+        SELECT collections.id FROM collections JOIN subtree ON collections.parent_id = subtree.id
+      )
+      SELECT id FROM subtree
+      ",
+    )
+    .map_err(AppError::from)?;
+  let rows = statement
+    .query_map(params![collection_id], |row| row.get::<_, i64>(0))
+    .map_err(AppError::from)?;
+
+  let mut subtree_ids = Vec::new();
+  for row in rows {
+    subtree_ids.push(row.map_err(AppError::from)?);
+  }
+  Ok(subtree_ids)
+}
+
+fn fetch_collection(
+  connection: &Connection,
+  collection_id: i64,
+) -> Result<Option<CollectionRecord>, AppError> {
+  let collection = connection
+    .query_row(
+      &format!("SELECT {COLLECTION_COLUMNS} FROM collections WHERE collections.id = ?1"),
+      params![collection_id],
+      row_to_collection,
+    )
+    .optional()
+    .map_err(AppError::from)?;
+
+  let Some(mut collection) = collection else {
+    return Ok(None);
+  };
+  collection.depth = fetch_collection_ancestor_ids(connection, collection_id)?.len() as i64;
+  Ok(Some(collection))
+}
+
+fn load_collection_tree(connection: &Connection) -> Result<Vec<CollectionRecord>, AppError> {
+  let mut statement = connection
+    .prepare(&format!(
+      "SELECT {COLLECTION_COLUMNS} FROM collections ORDER BY collections.position ASC, collections.name COLLATE NOCASE ASC, collections.id ASC"
+    ))
+    .map_err(AppError::from)?;
+  let rows = statement
+    .query_map([], row_to_collection)
+    .map_err(AppError::from)?;
+
+  let mut all_collections = Vec::new();
+  for row in rows {
+    all_collections.push(row.map_err(AppError::from)?);
+  }
+
+  let known_ids = all_collections
+    .iter()
+    .map(|collection| collection.id)
+    .collect::<HashSet<_>>();
+  let mut children: HashMap<Option<i64>, Vec<CollectionRecord>> = HashMap::new();
+  for collection in all_collections {
+    let parent_key = collection
+      .parent_id
+      .filter(|parent_id| known_ids.contains(parent_id));
+    children.entry(parent_key).or_default().push(collection);
+  }
+
+  let mut ordered = Vec::new();
+  let mut stack: Vec<(CollectionRecord, i64)> = children
+    .remove(&None)
+    .unwrap_or_default()
+    .into_iter()
+    .rev()
+    .map(|collection| (collection, 0))
+    .collect();
+  while let Some((mut collection, depth)) = stack.pop() {
+    collection.depth = depth;
+    if let Some(child_collections) = children.remove(&Some(collection.id)) {
+      stack.extend(
+        child_collections
+          .into_iter()
+          .rev()
+          .map(|child| (child, depth + 1)),
+      );
+    }
+    ordered.push(collection);
+  }
+
+  Ok(ordered)
+}
+
+fn ensure_collection(
+  connection: &Connection,
+  name: &str,
+  parent_id: Option<i64>,
+) -> Result<i64, AppError> {
+  let existing_id = connection
+    .query_row(
+      "
+      SELECT id FROM collections
+      WHERE name = ?1 COLLATE NOCASE AND parent_id IS ?2
+      ORDER BY id
+      LIMIT 1
+      ",
+      params![name, parent_id],
+      |row| row.get::<_, i64>(0),
+    )
+    .optional()
+    .map_err(AppError::from)?;
+  if let Some(collection_id) = existing_id {
+    return Ok(collection_id);
+  }
+
+  let timestamp = now_iso();
+  connection
+    .execute(
+      "
+      INSERT INTO collections (name, parent_id, position, created_at, updated_at)
+      VALUES (?1, ?2, (SELECT COALESCE(MAX(position), -1) + 1 FROM collections), ?3, ?3)
+      ",
+      params![name, parent_id, timestamp],
+    )
+    .map_err(AppError::from)?;
+  Ok(connection.last_insert_rowid())
+}
+
+fn add_prompt_to_collection(
+  connection: &Connection,
+  collection_id: i64,
+  prompt_id: i64,
+) -> Result<bool, AppError> {
+  let inserted_rows = connection
+    .execute(
+      "
+      INSERT OR IGNORE INTO collection_prompts (collection_id, prompt_id, added_at, position)
+      SELECT ?1, id, ?3, (SELECT COALESCE(MAX(position), -1) + 1 FROM collection_prompts WHERE collection_id = ?1)
+      FROM prompts WHERE id = ?2
+      ",
+      params![collection_id, prompt_id, now_iso()],
+    )
+    .map_err(AppError::from)?;
+  Ok(inserted_rows > 0)
+}
+
+fn attach_prompt_collections(
+  connection: &Connection,
+  prompts: &mut [PromptRecord],
+) -> Result<(), AppError> {
+  let mut statement = connection
+    .prepare(
+      "
+      SELECT collection_prompts.prompt_id, collections.id, collections.name
+      FROM collection_prompts
+      JOIN collections ON collections.id = collection_prompts.collection_id
+      ORDER BY collections.position ASC, collections.name COLLATE NOCASE ASC, collections.id ASC
+      ",
+    )
+    .map_err(AppError::from)?;
+  let rows = statement
+    .query_map([], |row| {
+      Ok((
+        row.get::<_, i64>(0)?,
+        CollectionRef {
+          id: row.get(1)?,
+          name: row.get(2)?,
+        },
+      ))
+    })
+    .map_err(AppError::from)?;
+
+  let mut memberships: HashMap<i64, Vec<CollectionRef>> = HashMap::new();
+  for row in rows {
+    let (prompt_id, collection) = row.map_err(AppError::from)?;
+    memberships.entry(prompt_id).or_default().push(collection);
+  }
+
+  for prompt in prompts {
+    prompt.collections = Some(memberships.remove(&prompt.id).unwrap_or_default());
+  }
+  Ok(())
+}
+
+fn content_hash(content: &str) -> String {
+  let hash = content
+    .bytes()
+    .fold(0xcbf2_9ce4_8422_2325_u64, |hash, byte| {
+      (hash ^ u64::from(byte)).wrapping_mul(0x0100_0000_01b3)
+    });
+  format!("{hash:016x}")
+}
+
+fn canonical_prompt_digest(title: &str, content: &str, tags: Vec<String>) -> String {
+  let mut canonical_tags: Vec<String> = tags
+    .iter()
+    .map(|tag| tag.trim().to_lowercase())
+    .filter(|tag| !tag.is_empty())
+    .collect();
+  canonical_tags.sort();
+  canonical_tags.dedup();
+
+  let canonical = format!(
+    "{}\u{1f}{}\u{1f}{}",
+    title.trim(),
+    canonical_tags.join("\u{1e}"),
+    normalize_prompt_content(content).trim_end_matches('\n')
+  );
+  content_hash(&canonical)
+}
+
+fn compute_library_fingerprint(connection: &Connection) -> Result<String, AppError> {
+  let mut statement = connection
+    .prepare("SELECT title, content, content_compressed, tags FROM prompts")
+    .map_err(AppError::from)?;
+  let rows = statement
+    .query_map([], |row| {
+      Ok(canonical_prompt_digest(
+        &row.get::<_, String>(0)?,
+        &read_prompt_content(row, 1, 2)?,
+        decode_tags(&row.get::<_, String>(3)?),
+      ))
+    })
+    .map_err(AppError::from)?;
+  let mut digests = rows
+    .collect::<Result<Vec<_>, _>>()
+    .map_err(AppError::from)?;
+  digests.sort();
+
+  Ok(content_hash(&format!(
+    "{}:{}",
+    digests.len(),
+    digests.join(",")
+  )))
+}
+
+#[tauri::command]
+fn library_fingerprint(state: tauri::State<'_, AppState>) -> Result<String, AppError> {
+  let connection = open_connection(&state.db_path)?;
+  compute_library_fingerprint(&connection)
+}
+
+fn latest_version_hash(
+  connection: &Connection,
+  prompt_id: i64,
+) -> Result<Option<String>, AppError> {
+  connection
+    .query_row(
+      "SELECT content_hash FROM prompt_versions WHERE prompt_id = ?1 ORDER BY created_at DESC, id DESC LIMIT 1",
+      params![prompt_id],
+      |row| row.get::<_, String>(0),
+    )
+    .optional()
+    .map_err(AppError::from)
+}
+
+fn backfill_version_hashes(connection: &Connection) -> Result<(), AppError> {
+  let mut statement = connection
+    .prepare("SELECT id, content FROM prompt_versions WHERE content_hash = ''")
+    .map_err(AppError::from)?;
+  let rows = statement
+    .query_map([], |row| {
+      Ok((row.get::<_, i64>(0)?, row.get::<_, String>(1)?))
+    })
+    .map_err(AppError::from)?;
+
+  let mut hashes = Vec::new();
+  for row in rows {
+    let (version_id, content) = row.map_err(AppError::from)?;
+    hashes.push((version_id, content_hash(&content)));
+  }
+  for (version_id, hash) in hashes {
+    connection
+      .execute(
+        "UPDATE prompt_versions SET content_hash = ?1 WHERE id = ?2",
+        params![hash, version_id],
+      )
+      .map_err(AppError::from)?;
+  }
+  Ok(())
+}
+
+fn backfill_compressed_search_content(connection: &Connection) -> Result<i64, AppError> {
+  let mut statement = connection
+    .prepare(
+      "
+      SELECT id, content, content_compressed FROM prompts
+      WHERE content_compressed = 1
+        AND id IN (SELECT rowid FROM prompts_fts WHERE content = '')
+      ",
+    )
+    .map_err(AppError::from)?;
+  let rows = statement
+    .query_map([], |row| {
+      Ok((row.get::<_, i64>(0)?, read_prompt_content(row, 1, 2)?))
+    })
+    .map_err(AppError::from)?;
+
+  let mut contents = Vec::new();
+  for row in rows {
+    contents.push(row.map_err(AppError::from)?);
+  }
+  for (prompt_id, content) in &contents {
+    connection
+      .execute(
+        "UPDATE prompts_fts SET content = ?1 WHERE rowid = ?2",
+        params![content, prompt_id],
+      )
+      .map_err(AppError::from)?;
+  }
+  Ok(contents.len() as i64)
+}
+
+fn insert_prompt_version(
+  connection: &Connection,
+  prompt_id: i64,
+  content: &str,
+  change_note: &str,
+  created_at: &str,
+) -> Result<(), AppError> {
+  connection
+    .execute(
+      "
+      INSERT INTO prompt_versions (prompt_id, content, change_note, created_at, content_hash)
+      VALUES (?1, ?2, ?3, ?4, ?5)
+      ",
+      params![
+        prompt_id,
+        content,
+        change_note,
+        created_at,
+        content_hash(content)
+      ],
+    )
+    .map_err(AppError::from)?;
+  Ok(())
+}
+
+#[tauri::command]
+fn get_global_shortcut(state: tauri::State<'_, AppState>) -> Result<String, AppError> {
+  let connection = open_connection(&state.db_path)?;
+  read_global_shortcut_setting(&connection)
+}
+
+#[tauri::command]
+fn update_global_shortcut(
+  app: tauri::AppHandle,
+  state: tauri::State<'_, AppState>,
+  shortcut: String,
+) -> Result<String, AppError> {
+  let normalized_shortcut = normalize_shortcut(&shortcut)?;
+  let connection = open_connection(&state.db_path)?;
+  let previous_shortcut = read_global_shortcut_setting(&connection)?;
+
+  if previous_shortcut == normalized_shortcut {
+    return Ok(previous_shortcut);
+  }
+
+  if let Err(error) = apply_global_shortcut(&app, &normalized_shortcut) {
+    let _ = apply_global_shortcut(&app, &previous_shortcut);
+    return Err(AppError::Validation(format!("全局快捷键注册失败：{error}")));
+  }
+
+  persist_global_shortcut_setting(&connection, &normalized_shortcut)?;
+  log::info!("global shortcut updated: {normalized_shortcut}");
+
+  Ok(normalized_shortcut)
+}
+
+fn database_size_bytes(db_path: &Path) -> Result<u64, AppError> {
+  let main_size = fs::metadata(db_path)
+    .map_err(|error| AppError::Io(format!("读取数据库文件信息失败: {error}")))?
+    .len();
+  let mut wal_path = db_path.as_os_str().to_owned();
+  wal_path.push("-wal");
+  let wal_size = match fs::metadata(PathBuf::from(wal_path)) {
+    Ok(metadata) => metadata.len(),
+    Err(error) if error.kind() == std::io::ErrorKind::NotFound => 0,
+    Err(error) => {
+      return Err(AppError::Io(format!("读取 WAL 文件信息失败: {error}")));
+    }
+  };
+  Ok(main_size + wal_size)
+}
+
+#[tauri::command]
+fn get_database_info(state: tauri::State<'_, AppState>) -> Result<DatabaseInfo, AppError> {
+  let connection = open_connection(&state.db_path)?;
+  let schema_version = connection
+    .query_row("PRAGMA user_version", [], |row| row.get::<_, i64>(0))
+    .map_err(AppError::from)?;
+  let size_bytes = database_size_bytes(&state.db_path)?;
+
+  Ok(DatabaseInfo {
+    sqlite_version: rusqlite::version().to_string(),
+    schema_version,
+    expected_schema_version: SCHEMA_MIGRATIONS.len() as i64,
+    path: state.db_path.display().to_string(),
+    size_bytes,
+  })
+}
+
+#[tauri::command]
+fn app_ready(state: tauri::State<'_, AppState>) -> Result<bool, AppError> {
+  let connection = open_connection(&state.db_path)?;
+  for table in REQUIRED_TABLES {
+    let exists = connection
+      .query_row(
+        "SELECT 1 FROM sqlite_master WHERE type = 'table' AND name = ?1",
+        params![table],
+        |_| Ok(()),
+      )
+      .optional()
+      .map_err(AppError::from)?
+      .is_some();
+    if !exists {
+      return Err(AppError::Database(format!("数据库缺少数据表 {table}")));
+    }
+  }
+
+  let schema_version = connection
+    .query_row("PRAGMA user_version", [], |row| row.get::<_, i64>(0))
+    .map_err(AppError::from)?;
+  let expected_schema_version = SCHEMA_MIGRATIONS.len() as i64;
+  if schema_version != expected_schema_version {
+    return Err(AppError::Database(format!(
+      "数据库结构版本为 {schema_version}，应用需要 {expected_schema_version}"
+    )));
+  }
+  Ok(true)
+}
+
+fn prompt_filter_clause(filter: &PromptFilter) -> (String, Vec<SqlValue>) {
+  let mut sql = String::from(" WHERE 1 = 1");
+  let mut query_params: Vec<SqlValue> = Vec::new();
+
+  if let Some(search_term) = filter
+    .search
+    .as_deref()
+    .map(str::trim)
+    .filter(|value| !value.is_empty())
+  {
+    sql.push_str(
+      " AND (title LIKE ? OR description LIKE ? OR tags LIKE ? OR (CASE WHEN content_compressed = 1 THEN (SELECT prompts_fts.content FROM prompts_fts WHERE prompts_fts.rowid = prompts.id) ELSE content END) LIKE ?)",
+    );
+    let pattern = SqlValue::Text(format!("%{search_term}%"));
+    query_params.push(pattern.clone());
+    query_params.push(pattern.clone());
+    query_params.push(pattern.clone());
+    query_params.push(pattern);
+  }
+
+  if let Some(tag_filter) = filter
+    .tag
+    .as_deref()
+    .map(str::trim)
+    .filter(|value| !value.is_empty())
+  {
+    sql.push_str(" AND tags LIKE ?");
+    query_params.push(SqlValue::Text(format!("%\"{tag_filter}\"%")));
+  }
+
+  if let Some(model_filter) = filter
+    .model
+    .as_deref()
+    .map(normalize_model)
+    .filter(|value| !value.is_empty())
+  {
+    sql.push_str(" AND model = ?");
+    query_params.push(SqlValue::Text(model_filter));
+  }
+
+  if let Some(author_filter) = filter
+    .author
+    .as_deref()
+    .map(str::trim)
+    .filter(|value| !value.is_empty())
+  {
+    sql.push_str(" AND author = ? COLLATE NOCASE");
+    query_params.push(SqlValue::Text(author_filter.to_string()));
+  }
+
+  if let Some(language_filter) = filter
+    .language
+    .as_deref()
+    .map(normalize_language)
+    .filter(|value| !value.is_empty())
+  {
+    sql.push_str(" AND language = ?");
+    query_params.push(SqlValue::Text(language_filter));
+  }
+
+  if let Some(category_filter) = filter.category_id {
+    sql.push_str(" AND category_id = ?");
+    query_params.push(SqlValue::Integer(category_filter));
+  }
+
+  for (key, value) in &filter.metadata_filters {
+    let normalized_key = key.trim().to_lowercase();
+    if normalized_key.is_empty() {
+      continue;
+    }
+    sql.push_str(
+      " AND EXISTS (SELECT 1 FROM prompt_meta WHERE prompt_meta.prompt_id = prompts.id AND prompt_meta.key = ? AND prompt_meta.value = ?)",
+    );
+    query_params.push(SqlValue::Text(normalized_key));
+    query_params.push(SqlValue::Text(value.clone()));
+  }
+
+  if let Some(collection_filter) = filter.collection_id {
+    if filter.include_descendants {
+      sql.push_str(
+        "
+        AND EXISTS (
+          SELECT 1 FROM collection_prompts
+          WHERE collection_prompts.prompt_id = prompts.id
+            AND collection_prompts.collection_id IN (
+              WITH RECURSIVE subtree(id) AS (
+                SELECT ?
+                UNION
+                SELECT collections.id FROM collections JOIN subtree ON collections.parent_id = subtree.id
+              )
+              SELECT id FROM subtree
+            )
+        )
+        ",
+      );
+    } else {
+      sql.push_str(
+        " AND EXISTS (SELECT 1 FROM collection_prompts WHERE collection_prompts.prompt_id = prompts.id AND collection_prompts.collection_id = ?)",
+      );
+    }
+    query_params.push(SqlValue::Integer(collection_filter));
+  }
+
+  if filter.unfiled_only {
+    sql.push_str(
+      " AND NOT EXISTS (SELECT 1 FROM collection_prompts WHERE collection_prompts.prompt_id = prompts.id)",
+    );
+  }
+
+  if filter.favorites_only {
+    sql.push_str(" AND is_favorite = 1");
+  }
+
+  if let Some(min_score) = filter.min_score {
+    sql.push_str(" AND score_avg >= ?");
+    query_params.push(SqlValue::Real(min_score));
+  }
+
+  (sql, query_params)
+}
+
+fn prompt_sort_clause(filter: &PromptFilter) -> (&'static str, Vec<SqlValue>) {
+  match (filter.sort_by.as_deref(), filter.collection_id) {
+    (Some("manual"), Some(collection_id)) => (
+      "COALESCE((SELECT MIN(position) FROM collection_prompts WHERE collection_prompts.prompt_id = prompts.id AND collection_prompts.collection_id = ?), 9223372036854775807) ASC, updated_at DESC",
+      vec![SqlValue::Integer(collection_id)],
+    ),
+    (Some("score"), _) => ("score_avg DESC, updated_at DESC", Vec::new()),
+    (Some("created"), _) => ("created_at DESC", Vec::new()),
+    _ => ("updated_at DESC", Vec::new()),
+  }
+}
+
+fn prompt_query_sql(filter: &PromptFilter, page: Option<(i64, i64)>) -> (String, Vec<SqlValue>) {
+  let (where_clause, mut query_params) = prompt_filter_clause(filter);
+  let (sort_clause, sort_params) = prompt_sort_clause(filter);
+  let mut sql =
+    format!("SELECT {PROMPT_COLUMNS} FROM prompts{where_clause} ORDER BY {sort_clause}");
+  query_params.extend(sort_params);
+  if let Some((limit, offset)) = page {
+    sql.push_str(" LIMIT ? OFFSET ?");
+    query_params.push(SqlValue::Integer(limit));
+    query_params.push(SqlValue::Integer(offset));
+  }
+  (sql, query_params)
+}
+
+fn query_prompts(
+  connection: &Connection,
+  filter: &PromptFilter,
+  page: Option<(i64, i64)>,
+) -> Result<Vec<PromptRecord>, AppError> {
+  let (sql, query_params) = prompt_query_sql(filter, page);
+  let mut statement = connection.prepare(&sql).map_err(AppError::from)?;
+  let rows = statement
+    .query_map(params_from_iter(query_params.iter()), row_to_prompt)
+    .map_err(AppError::from)?;
+
+  let mut prompts = Vec::new();
+  for row in rows {
+    prompts.push(row.map_err(AppError::from)?);
+  }
+  Ok(prompts)
+}
+
+fn stream_prompt_batches(
+  connection: &Connection,
+  filter: &PromptFilter,
+  batch_size: usize,
+  mut on_batch: impl FnMut(usize, Vec<PromptRecord>),
+) -> Result<(usize, usize), AppError> {
+  let (sql, query_params) = prompt_query_sql(filter, None);
+  let mut statement = connection.prepare(&sql).map_err(AppError::from)?;
+  let rows = statement
+    .query_map(params_from_iter(query_params.iter()), row_to_prompt)
+    .map_err(AppError::from)?;
+
+  let mut total = 0;
+  let mut batch_count = 0;
+  let mut batch = Vec::with_capacity(batch_size);
+  for row in rows {
+    batch.push(row.map_err(AppError::from)?);
+    total += 1;
+    if batch.len() == batch_size {
+      on_batch(
+        batch_count,
+        std::mem::replace(&mut batch, Vec::with_capacity(batch_size)),
+      );
+      batch_count += 1;
+    }
+  }
+  if !batch.is_empty() {
+    on_batch(batch_count, batch);
+    batch_count += 1;
+  }
+  Ok((total, batch_count))
+}
+
+#[tauri::command]
+async fn stream_prompts(
+  app: AppHandle,
+  state: tauri::State<'_, AppState>,
+  filters: Option<PromptFilter>,
+  page_size: Option<i64>,
+  request_id: Option<u64>,
+) -> Result<usize, AppError> {
+  let db_path = state.db_path.clone();
+  let filter = filters.unwrap_or_default();
+  let batch_size = page_size
+    .unwrap_or(DEFAULT_PAGE_SIZE)
+    .clamp(1, MAX_PAGE_SIZE) as usize;
+
+  tauri::async_runtime::spawn_blocking(move || {
+    let connection = open_connection(&db_path)?;
+    let (total, batches) =
+      stream_prompt_batches(&connection, &filter, batch_size, |batch_index, prompts| {
+        let batch = PromptBatchEvent {
+          request_id,
+          batch_index,
+          prompts,
+        };
+        if let Err(error) = app.emit(PROMPT_BATCH_EVENT, batch) {
+          log::warn!("failed to emit prompt batch: {error}");
+        }
+      })?;
+
+    let done = PromptDoneEvent {
+      request_id,
+      total,
+      batches,
+    };
+    if let Err(error) = app.emit(PROMPT_DONE_EVENT, done) {
+      log::warn!("failed to emit prompt stream completion: {error}");
+    }
+    Ok(total)
+  })
+  .await
+  .map_err(|error| AppError::Database(format!("列表查询任务异常终止: {error}")))?
+}
+
+fn count_prompts(connection: &Connection, filter: &PromptFilter) -> Result<i64, AppError> {
+  let (where_clause, query_params) = prompt_filter_clause(filter);
+  connection
+    .query_row(
+      &format!("SELECT COUNT(*) FROM prompts{where_clause}"),
+      params_from_iter(query_params.iter()),
+      |row| row.get(0),
+    )
+    .map_err(AppError::from)
+}
+
+#[tauri::command]
+fn list_prompts(
+  state: tauri::State<'_, AppState>,
+  filter: Option<ListPromptsInput>,
+) -> Result<Vec<PromptRecord>, AppError> {
+  let connection = open_connection(&state.db_path)?;
+  list_prompts_with(&connection, filter.unwrap_or_default())
+}
+
+fn list_prompts_with(
+  connection: &Connection,
+  input: ListPromptsInput,
+) -> Result<Vec<PromptRecord>, AppError> {
+  let ListPromptsInput {
+    mut filter,
+    fuzzy,
+    include_collections,
+  } = input;
+  let fuzzy_query = filter
+    .search
+    .as_deref()
+    .map(str::trim)
+    .filter(|value| fuzzy && !value.is_empty())
+    .map(str::to_string);
+  if fuzzy_query.is_some() {
+    filter.search = None;
+  }
+
+  let mut prompts = query_prompts(connection, &filter, None)?;
+  if let Some(query) = fuzzy_query {
+    let mut scored = prompts
+      .into_iter()
+      .map(|prompt| (trigram_similarity(&query, &prompt.title), prompt))
+      .filter(|(similarity, _)| *similarity >= FUZZY_SEARCH_THRESHOLD)
+      .collect::<Vec<_>>();
+    scored.sort_by(|left, right| right.0.total_cmp(&left.0));
+    prompts = scored.into_iter().map(|(_, prompt)| prompt).collect();
+  }
+  if include_collections {
+    attach_prompt_collections(connection, &mut prompts)?;
+  }
+  Ok(prompts)
+}
+
+#[tauri::command]
+fn list_recent_prompts(
+  state: tauri::State<'_, AppState>,
+  limit: i64,
+) -> Result<Vec<PromptRecord>, AppError> {
+  let connection = open_connection(&state.db_path)?;
+  list_recent_prompts_with(&connection, limit)
+}
+
+fn list_recent_prompts_with(
+  connection: &Connection,
+  limit: i64,
+) -> Result<Vec<PromptRecord>, AppError> {
+  let mut statement = connection
+    .prepare(&format!(
+      "SELECT {PROMPT_COLUMNS} FROM prompts ORDER BY updated_at DESC, id DESC LIMIT ?1"
+    ))
+    .map_err(AppError::from)?;
+  let rows = statement
+    .query_map(params![limit.clamp(1, MAX_RECENT_PROMPTS)], row_to_prompt)
+    .map_err(AppError::from)?;
+
+  let mut prompts = Vec::new();
+  for row in rows {
+    prompts.push(row.map_err(AppError::from)?);
+  }
+  Ok(prompts)
+}
+
+#[tauri::command]
+fn list_top_prompts(
+  state: tauri::State<'_, AppState>,
+  min_count: i64,
+  limit: i64,
+) -> Result<Vec<PromptRecord>, AppError> {
+  let connection = open_connection(&state.db_path)?;
+  list_top_prompts_with(&connection, min_count, limit)
+}
+
+fn list_top_prompts_with(
+  connection: &Connection,
+  min_count: i64,
+  limit: i64,
+) -> Result<Vec<PromptRecord>, AppError> {
+  let mut statement = connection
+    .prepare(&format!(
+      "
+      SELECT {PROMPT_COLUMNS} FROM prompts
+      WHERE score_count >= ?1 AND score_count > 0
+      ORDER BY score_avg DESC, score_count DESC, id ASC
+      LIMIT ?2
+      "
+    ))
+    .map_err(AppError::from)?;
+  let rows = statement
+    .query_map(
+      params![min_count, limit.clamp(1, MAX_PAGE_SIZE)],
+      row_to_prompt,
+    )
+    .map_err(AppError::from)?;
+
+  let mut prompts = Vec::new();
+  for row in rows {
+    prompts.push(row.map_err(AppError::from)?);
+  }
+  Ok(prompts)
+}
+
+#[tauri::command]
+fn list_unused_prompts(state: tauri::State<'_, AppState>) -> Result<Vec<PromptRecord>, AppError> {
+  let connection = open_connection(&state.db_path)?;
+  list_unused_prompts_with(&connection)
+}
+
+fn list_unused_prompts_with(connection: &Connection) -> Result<Vec<PromptRecord>, AppError> {
+  let mut statement = connection
+    .prepare(&format!(
+      "
+      SELECT {PROMPT_COLUMNS}
+      FROM prompts
+      WHERE NOT EXISTS (SELECT 1 FROM usage_logs WHERE usage_logs.prompt_id = prompts.id)
+      ORDER BY created_at ASC, id ASC
+      "
+    ))
+    .map_err(AppError::from)?;
+  let rows = statement
+    .query_map([], row_to_prompt)
+    .map_err(AppError::from)?;
+
+  let mut prompts = Vec::new();
+  for row in rows {
+    prompts.push(row.map_err(AppError::from)?);
+  }
+  Ok(prompts)
+}
+
+#[tauri::command]
+fn list_tags(state: tauri::State<'_, AppState>) -> Result<Vec<TagInfo>, AppError> {
+  let connection = open_connection(&state.db_path)?;
+  load_tag_infos(&connection)
+}
+
+fn load_tag_infos(connection: &Connection) -> Result<Vec<TagInfo>, AppError> {
+  let mut statement = connection
+    .prepare("SELECT tags, is_favorite FROM prompts")
+    .map_err(AppError::from)?;
+  let rows = statement
+    .query_map([], |row| {
+      Ok((row.get::<_, String>(0)?, row.get::<_, i64>(1)? == 1))
+    })
+    .map_err(AppError::from)?;
+
+  let mut tag_lists = Vec::new();
+  for row in rows {
+    let (tags, is_favorite) = row.map_err(AppError::from)?;
+    tag_lists.push((decode_tags(&tags), is_favorite));
+  }
+
+  let pinned_keys = read_pinned_tags(connection)?
+    .into_iter()
+    .map(|tag| tag.to_lowercase())
+    .collect::<HashSet<_>>();
+  let mut tag_items = aggregate_tag_counts(tag_lists);
+  for tag_item in &mut tag_items {
+    tag_item.pinned = pinned_keys.contains(&tag_item.name.to_lowercase());
+  }
+  tag_items.sort_by_key(|tag_item| !tag_item.pinned);
+  Ok(tag_items)
+}
+
+#[tauri::command]
+fn list_pinned_tags(state: tauri::State<'_, AppState>) -> Result<Vec<String>, AppError> {
+  let connection = open_connection(&state.db_path)?;
+  read_pinned_tags(&connection)
+}
+
+#[tauri::command]
+fn pin_tag(state: tauri::State<'_, AppState>, name: String) -> Result<Vec<String>, AppError> {
+  let tag_name = name.trim().to_string();
+  if tag_name.is_empty() {
+    return Err(AppError::Validation("标签名称不能为空".to_string()));
+  }
+
+  let connection = open_connection(&state.db_path)?;
+  let mut pinned_tags = read_pinned_tags(&connection)?;
+  if !pinned_tags
+    .iter()
+    .any(|tag| tag.to_lowercase() == tag_name.to_lowercase())
+  {
+    pinned_tags.push(tag_name);
+    write_pinned_tags(&connection, &pinned_tags)?;
+  }
+  Ok(pinned_tags)
+}
+
+#[tauri::command]
+fn unpin_tag(state: tauri::State<'_, AppState>, name: String) -> Result<Vec<String>, AppError> {
+  let needle = name.trim().to_lowercase();
+  let connection = open_connection(&state.db_path)?;
+  let mut pinned_tags = read_pinned_tags(&connection)?;
+  let original_len = pinned_tags.len();
+  pinned_tags.retain(|tag| tag.to_lowercase() != needle);
+  if pinned_tags.len() != original_len {
+    write_pinned_tags(&connection, &pinned_tags)?;
+  }
+  Ok(pinned_tags)
+}
+
+fn collect_tag_details(connection: &Connection) -> Result<Vec<TagDetails>, AppError> {
+  let mut statement = connection
+    .prepare("SELECT tags, is_favorite, score_avg, score_count, updated_at FROM prompts")
+    .map_err(AppError::from)?;
+  let rows = statement
+    .query_map([], |row| {
+      Ok((
+        row.get::<_, String>(0)?,
+        row.get::<_, i64>(1)? == 1,
+        row.get::<_, f64>(2)?,
+        row.get::<_, i64>(3)?,
+        row.get::<_, String>(4)?,
+      ))
+    })
+    .map_err(AppError::from)?;
+
+  let mut tag_stats: BTreeMap<String, (TagDetails, BTreeMap<String, i64>, f64, i64)> =
+    BTreeMap::new();
+  for row in rows {
+    let (tags_raw, is_favorite, score_avg, score_count, updated_at) =
+      row.map_err(AppError::from)?;
+    let mut seen = HashSet::new();
+    for tag in decode_tags(&tags_raw) {
+      let folded_key = tag.to_lowercase();
+      if !seen.insert(folded_key.clone()) {
+        continue;
+      }
+
+      let (details, casings, score_total, scored_count) =
+        tag_stats.entry(folded_key).or_insert_with(|| {
+          (
+            TagDetails {
+              name: String::new(),
+              prompt_count: 0,
+              favorite_count: 0,
+              average_score: None,
+              last_updated_at: None,
+            },
+            BTreeMap::new(),
+            0.0,
+            0,
+          )
+        });
+      *casings.entry(tag).or_insert(0) += 1;
+      details.prompt_count += 1;
+      if is_favorite {
+        details.favorite_count += 1;
+      }
+      if score_count > 0 {
+        *score_total += score_avg;
+        *scored_count += 1;
+      }
+      if details.last_updated_at.as_deref() < Some(updated_at.as_str()) {
+        details.last_updated_at = Some(updated_at.clone());
+      }
+    }
+  }
+
+  Ok(
+    tag_stats
+      .into_values()
+      .map(|(mut details, casings, score_total, scored_count)| {
+        details.name = preferred_tag_casing(casings);
+        details.average_score = (scored_count > 0).then(|| score_total / scored_count as f64);
+        details
+      })
+      .collect(),
+  )
+}
+
+#[tauri::command]
+fn get_tag_details(
+  state: tauri::State<'_, AppState>,
+  name: String,
+) -> Result<TagDetails, AppError> {
+  let needle = name.trim().to_lowercase();
+  let connection = open_connection(&state.db_path)?;
+  collect_tag_details(&connection)?
+    .into_iter()
+    .find(|details| details.name.to_lowercase() == needle)
+    .ok_or_else(|| AppError::NotFound(format!("标签「{}」不存在", name.trim())))
+}
+
+#[tauri::command]
+fn get_tag_report(state: tauri::State<'_, AppState>) -> Result<Vec<TagDetails>, AppError> {
+  let connection = open_connection(&state.db_path)?;
+  let mut report = collect_tag_details(&connection)?;
+  report.sort_by(|left, right| {
+    right
+      .prompt_count
+      .cmp(&left.prompt_count)
+      .then_with(|| left.name.cmp(&right.name))
+  });
+  Ok(report)
+}
+
+#[tauri::command]
+fn list_models(state: tauri::State<'_, AppState>) -> Result<Vec<FacetCount>, AppError> {
+  let connection = open_connection(&state.db_path)?;
+  list_models_with(&connection)
+}
+
+fn list_models_with(connection: &Connection) -> Result<Vec<FacetCount>, AppError> {
+  let mut statement = connection
+    .prepare(
+      "
+      SELECT model, COUNT(*)
+      FROM prompts
+      WHERE model <> ''
+      GROUP BY model
+      ORDER BY COUNT(*) DESC, model ASC
+      ",
+    )
+    .map_err(AppError::from)?;
+  let rows = statement
+    .query_map([], |row| {
+      Ok(FacetCount {
+        name: row.get(0)?,
+        count: row.get(1)?,
+      })
+    })
+    .map_err(AppError::from)?;
+
+  let mut models = Vec::new();
+  for row in rows {
+    models.push(row.map_err(AppError::from)?);
+  }
+  Ok(models)
+}
+
+#[tauri::command]
+fn list_languages(state: tauri::State<'_, AppState>) -> Result<Vec<FacetCount>, AppError> {
+  let connection = open_connection(&state.db_path)?;
+  list_languages_with(&connection)
+}
+
+fn list_languages_with(connection: &Connection) -> Result<Vec<FacetCount>, AppError> {
+  let mut statement = connection
+    .prepare(
+      "
+      SELECT language, COUNT(*)
+      FROM prompts
+      WHERE language <> ''
+      GROUP BY language
+      ORDER BY COUNT(*) DESC, language ASC
+      ",
+    )
+    .map_err(AppError::from)?;
+  let rows = statement
+    .query_map([], |row| {
+      Ok(FacetCount {
+        name: row.get(0)?,
+        count: row.get(1)?,
+      })
+    })
+    .map_err(AppError::from)?;
+
+  let mut languages = Vec::new();
+  for row in rows {
+    languages.push(row.map_err(AppError::from)?);
+  }
+  Ok(languages)
+}
+
+#[tauri::command]
+fn rebuild_search_index(state: tauri::State<'_, AppState>) -> Result<i64, AppError> {
+  let mut connection = open_connection(&state.db_path)?;
+  let transaction = connection.transaction().map_err(AppError::from)?;
+  let indexed = rebuild_prompt_search_index(&transaction)?;
+  transaction.commit().map_err(AppError::from)?;
+  Ok(indexed)
+}
+
+fn rebuild_prompt_search_index(connection: &Connection) -> Result<i64, AppError> {
+  connection
+    .execute("DELETE FROM prompts_fts", [])
+    .map_err(AppError::from)?;
+  let indexed = connection
+    .execute(
+      "INSERT INTO prompts_fts(rowid, title, description, content, tags)
+       SELECT id, title, description, CASE WHEN content_compressed = 1 THEN '' ELSE content END, tags FROM prompts",
+      [],
+    )
+    .map_err(AppError::from)?;
+  backfill_compressed_search_content(connection)?;
+  Ok(indexed as i64)
+}
+
+#[tauri::command]
+fn list_categories(state: tauri::State<'_, AppState>) -> Result<Vec<CategoryRecord>, AppError> {
+  let connection = open_connection(&state.db_path)?;
+  list_categories_with(&connection)
+}
+
+fn list_categories_with(connection: &Connection) -> Result<Vec<CategoryRecord>, AppError> {
+  let mut statement = connection
+    .prepare(
+      "
+      SELECT categories.id, categories.name, COUNT(prompts.id), categories.created_at, categories.updated_at
+      FROM categories
+      LEFT JOIN prompts ON prompts.category_id = categories.id
+      GROUP BY categories.id
+      ORDER BY categories.name ASC
+      ",
+    )
+    .map_err(AppError::from)?;
+  let rows = statement
+    .query_map([], |row| {
+      Ok(CategoryRecord {
+        id: row.get(0)?,
+        name: row.get(1)?,
+        count: row.get(2)?,
+        created_at: row.get(3)?,
+        updated_at: row.get(4)?,
+      })
+    })
+    .map_err(AppError::from)?;
+
+  let mut categories = Vec::new();
+  for row in rows {
+    categories.push(row.map_err(AppError::from)?);
+  }
+  Ok(categories)
+}
+
+#[tauri::command]
+fn create_category(
+  state: tauri::State<'_, AppState>,
+  name: String,
+) -> Result<CategoryRecord, AppError> {
+  let normalized_name = normalize_category_name(&name)?;
+  let connection = open_connection(&state.db_path)?;
+  let timestamp = now_iso();
+  let inserted_rows = connection
+    .execute(
+      "
+      INSERT INTO categories (name, created_at, updated_at)
+      VALUES (?1, ?2, ?2)
+      ON CONFLICT(name) DO NOTHING
+      ",
+      params![normalized_name, timestamp],
+    )
+    .map_err(AppError::from)?;
+  if inserted_rows == 0 {
+    return Err(AppError::Validation(format!(
+      "分类「{normalized_name}」已存在"
+    )));
+  }
+
+  fetch_category(&connection, connection.last_insert_rowid())?
+    .ok_or_else(|| AppError::Database("读取新建分类失败".to_string()))
+}
+
+#[tauri::command]
+fn rename_category(
+  state: tauri::State<'_, AppState>,
+  id: i64,
+  name: String,
+) -> Result<CategoryRecord, AppError> {
+  let normalized_name = normalize_category_name(&name)?;
+  let connection = open_connection(&state.db_path)?;
+  let conflict_id = connection
+    .query_row(
+      "SELECT id FROM categories WHERE name = ?1 AND id <> ?2",
+      params![normalized_name, id],
+      |row| row.get::<_, i64>(0),
+    )
+    .optional()
+    .map_err(AppError::from)?;
+  if conflict_id.is_some() {
+    return Err(AppError::Validation(format!(
+      "分类「{normalized_name}」已存在"
+    )));
+  }
+
+  let updated_rows = connection
+    .execute(
+      "UPDATE categories SET name = ?1, updated_at = ?2 WHERE id = ?3",
+      params![normalized_name, now_iso(), id],
+    )
+    .map_err(AppError::from)?;
+  if updated_rows == 0 {
+    return Err(AppError::NotFound("指定的分类不存在".to_string()));
+  }
+
+  fetch_category(&connection, id)?
+    .ok_or_else(|| AppError::Database("读取更新后的分类失败".to_string()))
+}
+
+#[tauri::command]
+fn delete_category(
+  state: tauri::State<'_, AppState>,
+  id: i64,
+  reassign: Option<bool>,
+) -> Result<(), AppError> {
+  let mut connection = open_connection(&state.db_path)?;
+  let transaction = connection.transaction().map_err(AppError::from)?;
+
+  let Some(category) = fetch_category(&transaction, id)? else {
+    return Err(AppError::NotFound("指定的分类不存在".to_string()));
+  };
+
+  if category.count > 0 {
+    if !reassign.unwrap_or(false) {
+      return Err(AppError::Validation(format!(
+        "分类「{}」下仍有 {} 个 Prompt，无法删除",
+        category.name, category.count
+      )));
+    }
+    if category.name == UNCATEGORIZED_CATEGORY_NAME {
+      return Err(AppError::Validation(format!(
+        "「{UNCATEGORIZED_CATEGORY_NAME}」下仍有 Prompt，无法删除"
+      )));
+    }
+
+    let fallback_id = ensure_category(&transaction, UNCATEGORIZED_CATEGORY_NAME)?;
+    transaction
+      .execute(
+        "UPDATE prompts SET category_id = ?1 WHERE category_id = ?2",
+        params![fallback_id, id],
+      )
+      .map_err(AppError::from)?;
+  }
+
+  transaction
+    .execute("DELETE FROM categories WHERE id = ?1", params![id])
+    .map_err(AppError::from)?;
+  transaction.commit().map_err(AppError::from)?;
+  Ok(())
+}
+
+#[tauri::command]
+fn list_collections(state: tauri::State<'_, AppState>) -> Result<CollectionListing, AppError> {
+  let connection = open_connection(&state.db_path)?;
+  let unfiled_count = count_prompts(
+    &connection,
+    &PromptFilter {
+      unfiled_only: true,
+      ..PromptFilter::default()
+    },
+  )?;
+
+  Ok(CollectionListing {
+    collections: load_collection_tree(&connection)?,
+    unfiled_count,
+  })
+}
+
+#[tauri::command]
+fn create_collection(
+  state: tauri::State<'_, AppState>,
+  name: String,
+  parent_id: Option<i64>,
+) -> Result<CollectionRecord, AppError> {
+  let normalized_name = normalize_collection_name(&name)?;
+  let connection = open_connection(&state.db_path)?;
+  if let Some(parent_id) = parent_id {
+    if fetch_collection(&connection, parent_id)?.is_none() {
+      return Err(AppError::NotFound("指定的上级集合不存在".to_string()));
+    }
+  }
+
+  let timestamp = now_iso();
+  connection
+    .execute(
+      "
+      INSERT INTO collections (name, parent_id, position, created_at, updated_at)
+      VALUES (?1, ?2, (SELECT COALESCE(MAX(position), -1) + 1 FROM collections), ?3, ?3)
+      ",
+      params![normalized_name, parent_id, timestamp],
+    )
+    .map_err(AppError::from)?;
+
+  fetch_collection(&connection, connection.last_insert_rowid())?
+    .ok_or_else(|| AppError::Database("读取新建集合失败".to_string()))
+}
+
+#[tauri::command]
+fn rename_collection(
+  state: tauri::State<'_, AppState>,
+  id: i64,
+  name: String,
+) -> Result<CollectionRecord, AppError> {
+  let normalized_name = normalize_collection_name(&name)?;
+  let connection = open_connection(&state.db_path)?;
+  let updated_rows = connection
+    .execute(
+      "UPDATE collections SET name = ?1, updated_at = ?2 WHERE id = ?3",
+      params![normalized_name, now_iso(), id],
+    )
+    .map_err(AppError::from)?;
+  if updated_rows == 0 {
+    return Err(AppError::NotFound("指定的集合不存在".to_string()));
+  }
+
+  fetch_collection(&connection, id)?
+    .ok_or_else(|| AppError::Database("读取更新后的集合失败".to_string()))
+}
+
+#[tauri::command]
+fn move_collection(
+  state: tauri::State<'_, AppState>,
+  id: i64,
+  parent_id: Option<i64>,
+) -> Result<CollectionRecord, AppError> {
+  let connection = open_connection(&state.db_path)?;
+  if fetch_collection(&connection, id)?.is_none() {
+    return Err(AppError::NotFound("指定的集合不存在".to_string()));
+  }
+
+  if let Some(parent_id) = parent_id {
+    if fetch_collection(&connection, parent_id)?.is_none() {
+      return Err(AppError::NotFound("指定的上级集合不存在".to_string()));
+    }
+    if parent_id == id || fetch_collection_ancestor_ids(&connection, parent_id)?.contains(&id) {
+      return Err(AppError::Validation(
+        "不能将集合移动到自身或其子集合下".to_string(),
+      ));
+    }
+  }
+
+  connection
+    .execute(
+      "UPDATE collections SET parent_id = ?1, updated_at = ?2 WHERE id = ?3",
+      params![parent_id, now_iso(), id],
+    )
+    .map_err(AppError::from)?;
+
+  fetch_collection(&connection, id)?
+    .ok_or_else(|| AppError::Database("读取更新后的集合失败".to_string()))
+}
+
+#[tauri::command]
+fn delete_collection(
+  state: tauri::State<'_, AppState>,
+  id: i64,
+  delete_prompts: Option<bool>,
+  delete_children: Option<bool>,
+) -> Result<(), AppError> {
+  let mut connection = open_connection(&state.db_path)?;
+  let transaction = connection.transaction().map_err(AppError::from)?;
+
+  let Some(collection) = fetch_collection(&transaction, id)? else {
+    return Err(AppError::NotFound("指定的集合不存在".to_string()));
+  };
+
+  let affected_ids = if delete_children.unwrap_or(false) {
+    fetch_collection_subtree_ids(&transaction, id)?
+  } else {
+    transaction
+      .execute(
+        "UPDATE collections SET parent_id = ?1, updated_at = ?2 WHERE parent_id = ?3",
+        params![collection.parent_id, now_iso(), id],
+      )
+      .map_err(AppError::from)?;
+    vec![id]
+  };
+
+  if delete_prompts.unwrap_or(false) {
+    for collection_id in &affected_ids {
+      transaction
+        .execute(
+          "DELETE FROM prompts WHERE id IN (SELECT prompt_id FROM collection_prompts WHERE collection_id = ?1)",
+          params![collection_id],
+        )
+        .map_err(AppError::from)?;
+    }
+  }
+
+  transaction
+    .execute("DELETE FROM collections WHERE id = ?1", params![id])
+    .map_err(AppError::from)?;
+  transaction.commit().map_err(AppError::from)?;
+  Ok(())
+}
+
+#[tauri::command]
+fn add_prompts_to_collection(
+  state: tauri::State<'_, AppState>,
+  collection_id: i64,
+  prompt_ids: Vec<i64>,
+) -> Result<i64, AppError> {
+  let mut connection = open_connection(&state.db_path)?;
+  let transaction = connection.transaction().map_err(AppError::from)?;
+
+  if fetch_collection(&transaction, collection_id)?.is_none() {
+    return Err(AppError::NotFound("指定的集合不存在".to_string()));
+  }
+
+  let mut added_count = 0_i64;
+  for prompt_id in prompt_ids {
+    if add_prompt_to_collection(&transaction, collection_id, prompt_id)? {
+      added_count += 1;
+    }
+  }
+
+  transaction.commit().map_err(AppError::from)?;
+  Ok(added_count)
+}
+
+#[tauri::command]
+fn remove_prompts_from_collection(
+  state: tauri::State<'_, AppState>,
+  collection_id: i64,
+  prompt_ids: Vec<i64>,
+) -> Result<i64, AppError> {
+  let mut connection = open_connection(&state.db_path)?;
+  let transaction = connection.transaction().map_err(AppError::from)?;
+
+  let mut removed_count = 0_i64;
+  for prompt_id in prompt_ids {
+    removed_count += transaction
+      .execute(
+        "DELETE FROM collection_prompts WHERE collection_id = ?1 AND prompt_id = ?2",
+        params![collection_id, prompt_id],
+      )
+      .map_err(AppError::from)? as i64;
+  }
+
+  transaction.commit().map_err(AppError::from)?;
+  Ok(removed_count)
+}
+
+#[tauri::command]
+fn move_prompts_to_collection(
+  state: tauri::State<'_, AppState>,
+  prompt_ids: Vec<i64>,
+  from_collection_id: Option<i64>,
+  to_collection_id: Option<i64>,
+) -> Result<Vec<MovePromptResult>, AppError> {
+  let mut connection = open_connection(&state.db_path)?;
+  let transaction = connection.transaction().map_err(AppError::from)?;
+
+  if let Some(from_collection_id) = from_collection_id {
+    if fetch_collection(&transaction, from_collection_id)?.is_none() {
+      return Err(AppError::NotFound("指定的来源集合不存在".to_string()));
+    }
+  }
+  if let Some(to_collection_id) = to_collection_id {
+    if fetch_collection(&transaction, to_collection_id)?.is_none() {
+      return Err(AppError::NotFound("指定的目标集合不存在".to_string()));
+    }
+  }
+
+  let mut results = Vec::new();
+  for prompt_id in prompt_ids {
+    let prompt_exists = transaction
+      .query_row(
+        "SELECT 1 FROM prompts WHERE id = ?1",
+        params![prompt_id],
+        |_| Ok(()),
+      )
+      .optional()
+      .map_err(AppError::from)?
+      .is_some();
+    if !prompt_exists {
+      results.push(MovePromptResult {
+        prompt_id,
+        outcome: MoveOutcome::NotFound,
+      });
+      continue;
+    }
+
+    match (from_collection_id, to_collection_id) {
+      (Some(from_collection_id), to_collection_id)
+        if to_collection_id != Some(from_collection_id) =>
+      {
+        transaction
+          .execute(
+            "DELETE FROM collection_prompts WHERE collection_id = ?1 AND prompt_id = ?2",
+            params![from_collection_id, prompt_id],
+          )
+          .map_err(AppError::from)?;
+      }
+      (None, None) => {
+        transaction
+          .execute(
+            "DELETE FROM collection_prompts WHERE prompt_id = ?1",
+            params![prompt_id],
+          )
+          .map_err(AppError::from)?;
+      }
+      _ => {}
+    }
+
+    let outcome = match to_collection_id {
+      Some(to_collection_id) => {
+        if add_prompt_to_collection(&transaction, to_collection_id, prompt_id)? {
+          MoveOutcome::Moved
+        } else {
+          MoveOutcome::AlreadyInTarget
+        }
+      }
+      None => MoveOutcome::Unfiled,
+    };
+    results.push(MovePromptResult { prompt_id, outcome });
+  }
+
+  transaction.commit().map_err(AppError::from)?;
+  Ok(results)
+}
+
+#[tauri::command]
+fn move_prompt_to_collection(
+  state: tauri::State<'_, AppState>,
+  prompt_id: i64,
+  collection_id: Option<i64>,
+) -> Result<(), AppError> {
+  let mut connection = open_connection(&state.db_path)?;
+  let transaction = connection.transaction().map_err(AppError::from)?;
+
+  if fetch_prompt(&transaction, prompt_id)?.is_none() {
+    return Err(AppError::NotFound("指定的 Prompt 不存在".to_string()));
+  }
+  if let Some(collection_id) = collection_id {
+    if fetch_collection(&transaction, collection_id)?.is_none() {
+      return Err(AppError::NotFound("指定的集合不存在".to_string()));
+    }
+  }
+
+  transaction
+    .execute(
+      "DELETE FROM collection_prompts WHERE prompt_id = ?1 AND collection_id IS NOT ?2",
+      params![prompt_id, collection_id],
+    )
+    .map_err(AppError::from)?;
+  if let Some(collection_id) = collection_id {
+    add_prompt_to_collection(&transaction, collection_id, prompt_id)?;
+  }
+
+  transaction.commit().map_err(AppError::from)?;
+  Ok(())
+}
+
+#[tauri::command]
+fn reorder_collections(
+  state: tauri::State<'_, AppState>,
+  ordered_ids: Vec<i64>,
+) -> Result<Vec<CollectionRecord>, AppError> {
+  let mut connection = open_connection(&state.db_path)?;
+  let transaction = connection.transaction().map_err(AppError::from)?;
+
+  for (position, collection_id) in ordered_ids.iter().enumerate() {
+    let updated_rows = transaction
+      .execute(
+        "UPDATE collections SET position = ?1 WHERE id = ?2",
+        params![position as i64, collection_id],
+      )
+      .map_err(AppError::from)?;
+    if updated_rows == 0 {
+      return Err(AppError::NotFound(format!("集合 {collection_id} 不存在")));
+    }
+  }
+
+  transaction.commit().map_err(AppError::from)?;
+  load_collection_tree(&connection)
+}
+
+#[tauri::command]
+fn reorder_collection_prompts(
+  state: tauri::State<'_, AppState>,
+  collection_id: i64,
+  ordered_prompt_ids: Vec<i64>,
+) -> Result<(), AppError> {
+  let mut connection = open_connection(&state.db_path)?;
+  let transaction = connection.transaction().map_err(AppError::from)?;
+
+  if fetch_collection(&transaction, collection_id)?.is_none() {
+    return Err(AppError::NotFound("指定的集合不存在".to_string()));
+  }
+
+  let member_ids = {
+    let mut statement = transaction
+      .prepare(
+        "SELECT prompt_id FROM collection_prompts WHERE collection_id = ?1 ORDER BY position ASC, added_at ASC",
+      )
+      .map_err(AppError::from)?;
+    let rows = statement
+      .query_map(params![collection_id], |row| row.get::<_, i64>(0))
+      .map_err(AppError::from)?;
+    rows
+      .collect::<Result<Vec<_>, _>>()
+      .map_err(AppError::from)?
+  };
+
+  let mut seen = HashSet::new();
+  let mut final_order = Vec::new();
+  for prompt_id in ordered_prompt_ids {
+    if !member_ids.contains(&prompt_id) {
+      return Err(AppError::Validation(format!(
+        "Prompt {prompt_id} 不在该集合中"
+      )));
+    }
+    if seen.insert(prompt_id) {
+      final_order.push(prompt_id);
+    }
+  }
+  final_order.extend(
+    member_ids
+      .into_iter()
+      .filter(|prompt_id| !seen.contains(prompt_id)),
+  );
+
+  for (position, prompt_id) in final_order.iter().enumerate() {
+    transaction
+      .execute(
+        "UPDATE collection_prompts SET position = ?1 WHERE collection_id = ?2 AND prompt_id = ?3",
+        params![position as i64, collection_id, prompt_id],
+      )
+      .map_err(AppError::from)?;
+  }
+
+  transaction.commit().map_err(AppError::from)?;
+  Ok(())
+}
+
+#[tauri::command]
+fn list_smart_collections(
+  state: tauri::State<'_, AppState>,
+) -> Result<Vec<SmartCollectionRecord>, AppError> {
+  let connection = open_connection(&state.db_path)?;
+  list_smart_collections_with(&connection)
+}
+
+fn list_smart_collections_with(
+  connection: &Connection,
+) -> Result<Vec<SmartCollectionRecord>, AppError> {
+  let mut statement = connection
+    .prepare("SELECT id FROM smart_collections ORDER BY name COLLATE NOCASE, id")
+    .map_err(AppError::from)?;
+  let ids = statement
+    .query_map([], |row| row.get::<_, i64>(0))
+    .map_err(AppError::from)?
+    .collect::<Result<Vec<_>, _>>()
+    .map_err(AppError::from)?;
+
+  let mut smart_collections = Vec::new();
+  for id in ids {
+    if let Some(smart_collection) = fetch_smart_collection(connection, id)? {
+      smart_collections.push(smart_collection);
+    }
+  }
+  Ok(smart_collections)
+}
+
+#[tauri::command]
+fn create_smart_collection(
+  state: tauri::State<'_, AppState>,
+  name: String,
+  filter: PromptFilter,
+) -> Result<SmartCollectionRecord, AppError> {
+  let normalized_name = normalize_collection_name(&name)?;
+  let encoded_filter = serde_json::to_string(&filter).map_err(AppError::from)?;
+  let connection = open_connection(&state.db_path)?;
+  let timestamp = now_iso();
+  connection
+    .execute(
+      "INSERT INTO smart_collections (name, filter, created_at, updated_at) VALUES (?1, ?2, ?3, ?3)",
+      params![normalized_name, encoded_filter, timestamp],
+    )
+    .map_err(AppError::from)?;
+
+  fetch_smart_collection(&connection, connection.last_insert_rowid())?
+    .ok_or_else(|| AppError::Database("读取新建智能集合失败".to_string()))
+}
+
+#[tauri::command]
+fn update_smart_collection(
+  state: tauri::State<'_, AppState>,
+  id: i64,
+  name: Option<String>,
+  filter: Option<PromptFilter>,
+) -> Result<SmartCollectionRecord, AppError> {
+  let normalized_name = name.as_deref().map(normalize_collection_name).transpose()?;
+  let encoded_filter = filter
+    .as_ref()
+    .map(serde_json::to_string)
+    .transpose()
+    .map_err(AppError::from)?;
+  let connection = open_connection(&state.db_path)?;
+  let updated_rows = connection
+    .execute(
+      "UPDATE smart_collections SET name = COALESCE(?1, name), filter = COALESCE(?2, filter), updated_at = ?3 WHERE id = ?4",
+      params![normalized_name, encoded_filter, now_iso(), id],
+    )
+    .map_err(AppError::from)?;
+  if updated_rows == 0 {
+    return Err(AppError::NotFound("指定的智能集合不存在".to_string()));
+  }
+
+  fetch_smart_collection(&connection, id)?
+    .ok_or_else(|| AppError::Database("读取更新后的智能集合失败".to_string()))
+}
+
+#[tauri::command]
+fn delete_smart_collection(state: tauri::State<'_, AppState>, id: i64) -> Result<(), AppError> {
+  let connection = open_connection(&state.db_path)?;
+  let deleted_rows = connection
+    .execute("DELETE FROM smart_collections WHERE id = ?1", params![id])
+    .map_err(AppError::from)?;
+  if deleted_rows == 0 {
+    return Err(AppError::NotFound("指定的智能集合不存在".to_string()));
+  }
+  Ok(())
+}
+
+#[tauri::command]
+fn get_smart_collection_prompts(
+  state: tauri::State<'_, AppState>,
+  id: i64,
+  limit: Option<i64>,
+  offset: Option<i64>,
+) -> Result<Vec<PromptRecord>, AppError> {
+  let connection = open_connection(&state.db_path)?;
+  let smart_collection = fetch_smart_collection(&connection, id)?
+    .ok_or_else(|| AppError::NotFound("指定的智能集合不存在".to_string()))?;
+  let page = (
+    limit.unwrap_or(DEFAULT_PAGE_SIZE).clamp(1, MAX_PAGE_SIZE),
+    offset.unwrap_or(0).max(0),
+  );
+  query_prompts(&connection, &smart_collection.filter, Some(page))
+}
+
+#[tauri::command]
+fn get_prompt(
+  state: tauri::State<'_, AppState>,
+  id: i64,
+) -> Result<Option<PromptRecord>, AppError> {
+  let connection = open_connection(&state.db_path)?;
+  fetch_prompt(&connection, id)
+}
+
+#[tauri::command]
+fn get_prompt_detail(
+  state: tauri::State<'_, AppState>,
+  id: i64,
+) -> Result<Option<PromptDetail>, AppError> {
+  let connection = open_connection(&state.db_path)?;
+  let Some(mut prompt) = fetch_prompt(&connection, id)? else {
+    return Ok(None);
+  };
+  attach_prompt_collections(&connection, std::slice::from_mut(&mut prompt))?;
+
+  Ok(Some(PromptDetail {
+    example_output: fetch_example_output(&connection, id)?,
+    last_input_vars: fetch_last_input_vars(&connection, id)?,
+    versions: fetch_prompt_versions(&connection, id)?,
+    prompt,
+  }))
+}
+
+#[tauri::command]
+fn get_last_input_vars(
+  state: tauri::State<'_, AppState>,
+  prompt_id: i64,
+) -> Result<Value, AppError> {
+  let connection = open_connection(&state.db_path)?;
+  fetch_last_input_vars(&connection, prompt_id)
+}
+
+#[tauri::command]
+fn set_prompt_example(
+  state: tauri::State<'_, AppState>,
+  id: i64,
+  example_output: String,
+) -> Result<(), AppError> {
+  let connection = open_connection(&state.db_path)?;
+  let updated_rows = connection
+    .execute(
+      "UPDATE prompts SET example_output = ?1, updated_at = ?2 WHERE id = ?3",
+      params![example_output.trim(), now_iso(), id],
+    )
+    .map_err(AppError::from)?;
+  if updated_rows == 0 {
+    return Err(AppError::NotFound("指定的 Prompt 不存在".to_string()));
+  }
+  Ok(())
+}
+
+#[tauri::command]
+fn render_prompt_markdown_html(
+  state: tauri::State<'_, AppState>,
+  id: i64,
+  variables: Option<Value>,
+) -> Result<String, AppError> {
+  let connection = open_connection(&state.db_path)?;
+  render_prompt_preview_html(&connection, id, variables)
+}
+
+fn render_prompt_preview_html(
+  connection: &Connection,
+  id: i64,
+  variables: Option<Value>,
+) -> Result<String, AppError> {
+  let (rendered, _) = render_stored_prompt(connection, id, variables, None, false)?;
+  Ok(render_markdown_html(&rendered.text))
+}
+
+fn render_stored_prompt(
+  connection: &Connection,
+  prompt_id: i64,
+  variables: Option<Value>,
+  preset_id: Option<i64>,
+  missing_as_empty: bool,
+) -> Result<(RenderedTemplate, Map<String, Value>), AppError> {
+  let supplied_values = match variables {
+    Some(Value::Object(values)) => values,
+    Some(Value::Null) | None => Map::new(),
+    Some(_) => return Err(AppError::Validation("变量必须是 JSON 对象".to_string())),
+  };
+
+  let prompt = fetch_prompt(connection, prompt_id)?
+    .ok_or_else(|| AppError::NotFound("指定的 Prompt 不存在".to_string()))?;
+
+  let mut values = match preset_id {
+    Some(preset_id) => {
+      let preset = fetch_variable_preset(connection, preset_id)?
+        .filter(|preset| preset.prompt_id == prompt_id)
+        .ok_or_else(|| AppError::NotFound("指定的变量预设不存在".to_string()))?;
+      match preset.values {
+        Value::Object(values) => values,
+        _ => Map::new(),
+      }
+    }
+    None => Map::new(),
+  };
+  values.extend(supplied_values);
+  apply_variable_defaults(&prompt.variables_schema, &mut values);
+  let snippets = load_snippet_contents(connection)?;
+  let content = expand_snippet_includes(&prompt.content, &snippets, 0)?;
+  let rendered = render_template(&content, &values, missing_as_empty)?;
+  Ok((rendered, values))
+}
+
+#[tauri::command]
+fn render_prompt_template(
+  state: tauri::State<'_, AppState>,
+  prompt_id: i64,
+  variables: Option<Value>,
+  preset_id: Option<i64>,
+  missing_as_empty: Option<bool>,
+) -> Result<RenderedTemplate, AppError> {
+  let connection = open_connection(&state.db_path)?;
+  let (rendered, _) = render_stored_prompt(
+    &connection,
+    prompt_id,
+    variables,
+    preset_id,
+    missing_as_empty.unwrap_or(false),
+  )?;
+  Ok(rendered)
+}
+
+#[tauri::command]
+fn copy_rendered_prompt(
+  app: AppHandle,
+  state: tauri::State<'_, AppState>,
+  prompt_id: i64,
+  variables: Option<Value>,
+) -> Result<CopiedPrompt, AppError> {
+  let connection = open_connection(&state.db_path)?;
+  let (rendered, values) = render_stored_prompt(&connection, prompt_id, variables, None, false)?;
+  app
+    .clipboard()
+    .write_text(rendered.text.clone())
+    .map_err(|error| AppError::Io(format!("写入剪贴板失败: {error}")))?;
+
+  let input = LogUsageInput {
+    prompt_id,
+    input_vars: Value::Object(values),
+    output_text: String::new(),
+    rating: None,
+    check_variables: None,
+    validate: None,
+  };
+  let result = retry_on_busy(|| {
+    let mut connection = open_connection(&state.db_path)?;
+    record_prompt_usage(&mut connection, input.clone())
+  })?;
+  Ok(CopiedPrompt {
+    text: rendered.text,
+    log_id: result
+      .log_id
+      .ok_or_else(|| AppError::Database("记录使用日志失败".to_string()))?,
+  })
+}
+
+#[tauri::command]
+fn update_usage_log(
+  state: tauri::State<'_, AppState>,
+  id: i64,
+  output_text: Option<String>,
+  rating: Option<i64>,
+) -> Result<(), AppError> {
+  if let Some(score) = rating {
+    validate_rating(score)?;
+  }
+
+  retry_on_busy(|| {
+    let mut connection = open_connection(&state.db_path)?;
+    let transaction = connection.transaction().map_err(AppError::from)?;
+    let Some((prompt_id, existing_rating)) = transaction
+      .query_row(
+        "SELECT prompt_id, rating FROM usage_logs WHERE id = ?1",
+        params![id],
+        |row| Ok((row.get::<_, i64>(0)?, row.get::<_, Option<i64>>(1)?)),
+      )
+      .optional()
+      .map_err(AppError::from)?
+    else {
+      return Err(AppError::NotFound("指定的使用记录不存在".to_string()));
+    };
+
+    if let Some(text) = &output_text {
+      let output_text_limit = read_output_text_limit(&transaction)?;
+      let (text, original_length) = truncate_output_text(text, output_text_limit);
+      transaction
+        .execute(
+          "UPDATE usage_logs SET output_text = ?1, output_original_length = ?2 WHERE id = ?3",
+          params![text, original_length, id],
+        )
+        .map_err(AppError::from)?;
+    }
+    if let Some(score) = rating {
+      if existing_rating.is_some() {
+        return Err(AppError::Validation("该使用记录已评分".to_string()));
+      }
+      transaction
+        .execute(
+          "UPDATE usage_logs SET rating = ?1 WHERE id = ?2",
+          params![score, id],
+        )
+        .map_err(AppError::from)?;
+      apply_prompt_rating(&transaction, prompt_id, score)?;
+    }
+
+    transaction.commit().map_err(AppError::from)
+  })
+}
+
+#[tauri::command]
+fn extract_template_variables(content: String) -> TemplateVariables {
+  scan_template_variables(&content)
+}
+
+#[tauri::command]
+fn get_prompt_variables(
+  state: tauri::State<'_, AppState>,
+  prompt_id: i64,
+) -> Result<TemplateVariables, AppError> {
+  let connection = open_connection(&state.db_path)?;
+  let prompt = fetch_prompt(&connection, prompt_id)?
+    .ok_or_else(|| AppError::NotFound("指定的 Prompt 不存在".to_string()))?;
+  Ok(scan_template_variables(&prompt.content))
+}
+
+#[tauri::command]
+fn lint_prompt_template(
+  state: tauri::State<'_, AppState>,
+  prompt_id: i64,
+) -> Result<Vec<LintFinding>, AppError> {
+  let connection = open_connection(&state.db_path)?;
+  let prompt = fetch_prompt(&connection, prompt_id)?
+    .ok_or_else(|| AppError::NotFound("指定的 Prompt 不存在".to_string()))?;
+  Ok(lint_template(&prompt.content, &prompt.variables_schema))
+}
+
+#[tauri::command]
+fn list_all_versions(
+  state: tauri::State<'_, AppState>,
+  limit: i64,
+  offset: i64,
+) -> Result<VersionTimeline, AppError> {
+  let connection = open_connection(&state.db_path)?;
+  list_all_versions_with(&connection, limit, offset)
+}
+
+fn list_all_versions_with(
+  connection: &Connection,
+  limit: i64,
+  offset: i64,
+) -> Result<VersionTimeline, AppError> {
+  let total = connection
+    .query_row("SELECT COUNT(*) FROM prompt_versions", [], |row| row.get(0))
+    .map_err(AppError::from)?;
+
+  let mut statement = connection
+    .prepare(
+      "
+      SELECT prompt_versions.id, prompt_versions.prompt_id, prompt_versions.content,
+             prompt_versions.change_note, prompt_versions.created_at, prompts.title
+      FROM prompt_versions
+      JOIN prompts ON prompts.id = prompt_versions.prompt_id
+      ORDER BY prompt_versions.created_at DESC, prompt_versions.id DESC
+      LIMIT ?1 OFFSET ?2
+      ",
+    )
+    .map_err(AppError::from)?;
+  let rows = statement
+    .query_map(
+      params![limit.clamp(1, MAX_PAGE_SIZE), offset.max(0)],
+      |row| {
+        Ok(VersionWithPrompt {
+          version: row_to_prompt_version(row)?,
+          prompt_title: row.get(5)?,
+        })
+      },
+    )
+    .map_err(AppError::from)?;
+
+  let mut versions = Vec::new();
+  for row in rows {
+    versions.push(row.map_err(AppError::from)?);
+  }
+  Ok(VersionTimeline { versions, total })
+}
+
+#[tauri::command]
+fn list_prompt_versions(
+  state: tauri::State<'_, AppState>,
+  prompt_id: i64,
+) -> Result<Vec<PromptVersionRecord>, AppError> {
+  let connection = open_connection(&state.db_path)?;
+  fetch_prompt_versions(&connection, prompt_id)
+}
+
+#[tauri::command]
+fn reorder_version(
+  state: tauri::State<'_, AppState>,
+  version_id: i64,
+  new_created_at: String,
+) -> Result<(), AppError> {
+  let created_at = DateTime::parse_from_rfc3339(new_created_at.trim())
+    .map_err(|error| AppError::Validation(format!("时间格式无效（需为 RFC3339）: {error}")))?
+    .with_timezone(&Utc)
+    .to_rfc3339();
+
+  let connection = open_connection(&state.db_path)?;
+  let updated_rows = connection
+    .execute(
+      "UPDATE prompt_versions SET created_at = ?1 WHERE id = ?2",
+      params![created_at, version_id],
+    )
+    .map_err(AppError::from)?;
+  if updated_rows == 0 {
+    return Err(AppError::NotFound("指定的版本不存在".to_string()));
+  }
+  Ok(())
+}
+
+#[tauri::command]
+fn upsert_prompt(
+  state: tauri::State<'_, AppState>,
+  input: SavePromptInput,
+) -> Result<UpsertPromptResult, AppError> {
+  save_prompt_atomically(&state.db_path, &input)
+}
+
+fn save_prompt_atomically(
+  db_path: &Path,
+  input: &SavePromptInput,
+) -> Result<UpsertPromptResult, AppError> {
+  retry_on_busy(|| {
+    let mut connection = open_connection(db_path)?;
+    let transaction = connection
+      .transaction_with_behavior(TransactionBehavior::Immediate)
+      .map_err(AppError::from)?;
+    let result = save_prompt(&transaction, input.clone())?;
+    transaction.commit().map_err(AppError::from)?;
+    Ok(result)
+  })
+}
+
+#[tauri::command]
+fn split_prompt(
+  state: tauri::State<'_, AppState>,
+  id: i64,
+  extracted_content: String,
+  new_title: String,
+) -> Result<PromptRecord, AppError> {
+  retry_on_busy(|| {
+    let mut connection = open_connection(&state.db_path)?;
+    let transaction = connection
+      .transaction_with_behavior(TransactionBehavior::Immediate)
+      .map_err(AppError::from)?;
+    let source_prompt = fetch_prompt(&transaction, id)?
+      .ok_or_else(|| AppError::NotFound("指定的 Prompt 不存在".to_string()))?;
+    let result = save_prompt(
+      &transaction,
+      SavePromptInput {
+        title: new_title.clone(),
+        content: extracted_content.clone(),
+        tags: source_prompt.tags.clone(),
+        change_note: Some(format!("从「{}」拆分", source_prompt.title)),
+        ..SavePromptInput::default()
+      },
+    )?;
+    transaction.commit().map_err(AppError::from)?;
+    Ok(result.prompt)
+  })
+}
+
+fn save_prompt(
+  connection: &Connection,
+  input: SavePromptInput,
+) -> Result<UpsertPromptResult, AppError> {
+  let SavePromptInput {
+    id,
+    title,
+    content,
+    tags,
+    is_favorite,
+    change_note,
+    description,
+    source_url,
+    author,
+    model,
+    language,
+    params: prompt_params,
+    icon,
+    category_id,
+    example_output,
+    variables_schema,
+  } = input;
+
+  let content = if read_content_normalization(connection)? {
+    normalize_prompt_content(&content)
+  } else {
+    content
+  };
+  let normalized_title = normalize_title(&title)?;
+  if content.trim().is_empty() {
+    return Err(AppError::Validation("Prompt 内容不能为空".to_string()));
+  }
+  let mut warnings = collect_prompt_warnings(&normalized_title, &content);
+  let variables_schema_json = match variables_schema {
+    Some(schema) => {
+      let (schema, schema_warnings) = normalize_variables_schema(&content, schema)?;
+      warnings.extend(schema_warnings);
+      Some(serde_json::to_string(&schema).map_err(AppError::from)?)
+    }
+    None => None,
+  };
+
+  let normalized_description = description
+    .as_deref()
+    .map(normalize_description)
+    .transpose()?;
+  let normalized_source_url = source_url
+    .as_deref()
+    .map(normalize_source_url)
+    .transpose()?;
+  let normalized_author = author.as_deref().map(normalize_author).transpose()?;
+  let normalized_model = model.as_deref().map(normalize_model);
+  let params_json = encode_params(prompt_params)?;
+  let normalized_icon = icon.as_deref().map(normalize_icon).transpose()?;
+  let detected_language = detect_language(&content);
+  let normalized_language = language.as_deref().map(|value| {
+    let normalized = normalize_language(value);
+    if normalized.is_empty() {
+      detected_language.clone()
+    } else {
+      normalized
+    }
+  });
+  let mut normalized_tags = normalize_tags(tags);
+  warnings.extend(cap_tags(
+    &mut normalized_tags,
+    read_max_tag_count(connection)?,
+  ));
+  let tags_json = encode_tags(&normalized_tags);
+  let note = change_note.unwrap_or_default().trim().to_string();
+  let timestamp = now_iso();
+
+  if let Some(category_id) = category_id {
+    if fetch_category(connection, category_id)?.is_none() {
+      return Err(AppError::NotFound("指定的分类不存在".to_string()));
+    }
+  }
+
+  if let Some(prompt_id) = id {
+    let previous_prompt = connection
+      .query_row(
+        "SELECT content, content_compressed, variables_schema FROM prompts WHERE id = ?1",
+        params![prompt_id],
+        |row| Ok((read_prompt_content(row, 0, 1)?, row.get::<_, String>(2)?)),
+      )
+      .optional()
+      .map_err(AppError::from)?;
+
+    let Some((old_content, stored_schema)) = previous_prompt else {
+      return Err(AppError::NotFound("指定的 Prompt 不存在".to_string()));
+    };
+    if variables_schema_json.is_none() {
+      warnings.extend(unused_variable_warnings(
+        &extract_variable_names(&content),
+        &decode_variables_schema(&stored_schema),
+      ));
+    }
+
+    connection
+      .execute(
+        "
+        UPDATE prompts
+        SET title = ?1, content = ?2, content_compressed = 0, tags = ?3, is_favorite = ?4, updated_at = ?5,
+            description = COALESCE(?6, description), source_url = COALESCE(?7, source_url),
+            model = COALESCE(?8, model),
+            language = COALESCE(?9, NULLIF(language, ''), ?10), params = COALESCE(?11, params),
+            icon = COALESCE(?12, icon), category_id = COALESCE(?13, category_id),
+            example_output = COALESCE(?14, example_output),
+            variables_schema = COALESCE(?15, variables_schema),
+            author = COALESCE(?16, author)
+        WHERE id = ?17
+        ",
+        params![
+          normalized_title,
+          &content,
+          tags_json,
+          if is_favorite { 1 } else { 0 },
+          timestamp,
+          normalized_description,
+          normalized_source_url,
+          normalized_model,
+          normalized_language,
+          detected_language,
+          params_json,
+          normalized_icon,
+          category_id,
+          example_output.as_deref().map(str::trim),
+          variables_schema_json,
+          normalized_author,
+          prompt_id
+        ],
+      )
+      .map_err(AppError::from)?;
+    compress_stored_content(
+      connection,
+      prompt_id,
+      &content,
+      read_content_compression_threshold(connection)?,
+    )?;
+
+    let duplicates_latest_version = latest_version_hash(connection, prompt_id)?.as_deref()
+      == Some(content_hash(&content).as_str());
+    if !note.is_empty() || (old_content != content && !duplicates_latest_version) {
+      let version_note = if note.is_empty() {
+        "content updated".to_string()
+      } else {
+        note.clone()
+      };
+      insert_prompt_version(connection, prompt_id, &content, &version_note, &timestamp)?;
+    }
+
+    let prompt = fetch_prompt(connection, prompt_id)?
+      .ok_or_else(|| AppError::Database("读取更新后的 Prompt 失败".to_string()))?;
+    return Ok(UpsertPromptResult { prompt, warnings });
+  }
+
+  let category_id = match category_id {
+    Some(category_id) => category_id,
+    None => ensure_category(connection, UNCATEGORIZED_CATEGORY_NAME)?,
+  };
+
+  connection
+    .execute(
+      "
+      INSERT INTO prompts (title, content, tags, is_favorite, score_avg, score_count, created_at, updated_at, description, source_url, model, language, params, icon, category_id, example_output, variables_schema, author)
+      VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14, ?15, ?16, ?17, ?18)
+      ",
+      params![
+        normalized_title,
+        &content,
+        tags_json,
+        if is_favorite { 1 } else { 0 },
+        0.0_f64,
+        0_i64,
+        timestamp,
+        timestamp,
+        normalized_description.unwrap_or_default(),
+        normalized_source_url.unwrap_or_default(),
+        normalized_model.unwrap_or_default(),
+        normalized_language.unwrap_or(detected_language),
+        params_json.unwrap_or_else(|| "{}".to_string()),
+        normalized_icon.unwrap_or_default(),
+        category_id,
+        example_output.as_deref().map(str::trim).unwrap_or_default(),
+        variables_schema_json.unwrap_or_else(|| "[]".to_string()),
+        normalized_author.unwrap_or_default()
+      ],
+    )
+    .map_err(AppError::from)?;
+
+  let prompt_id = connection.last_insert_rowid();
+  compress_stored_content(
+    connection,
+    prompt_id,
+    &content,
+    read_content_compression_threshold(connection)?,
+  )?;
+  let initial_note = if note.is_empty() {
+    "initial version".to_string()
+  } else {
+    note
+  };
+  insert_prompt_version(connection, prompt_id, &content, &initial_note, &timestamp)?;
+
+  let prompt = fetch_prompt(connection, prompt_id)?
+    .ok_or_else(|| AppError::Database("读取新建 Prompt 失败".to_string()))?;
+  Ok(UpsertPromptResult { prompt, warnings })
+}
+
+#[tauri::command]
+fn rename_prompt(
+  state: tauri::State<'_, AppState>,
+  id: i64,
+  title: String,
+) -> Result<PromptRecord, AppError> {
+  let normalized_title = normalize_title(&title)?;
+  let connection = open_connection(&state.db_path)?;
+  let updated_rows = connection
+    .execute(
+      "UPDATE prompts SET title = ?1, updated_at = ?2 WHERE id = ?3",
+      params![normalized_title, now_iso(), id],
+    )
+    .map_err(AppError::from)?;
+  if updated_rows == 0 {
+    return Err(AppError::NotFound("指定的 Prompt 不存在".to_string()));
+  }
+
+  fetch_prompt(&connection, id)?
+    .ok_or_else(|| AppError::Database("读取更新后的 Prompt 失败".to_string()))
+}
+
+#[tauri::command]
+fn set_prompt_icon(
+  state: tauri::State<'_, AppState>,
+  id: i64,
+  icon: String,
+) -> Result<PromptRecord, AppError> {
+  let normalized_icon = normalize_icon(&icon)?;
+  let connection = open_connection(&state.db_path)?;
+  let updated_rows = connection
+    .execute(
+      "UPDATE prompts SET icon = ?1, updated_at = ?2 WHERE id = ?3",
+      params![normalized_icon, now_iso(), id],
+    )
+    .map_err(AppError::from)?;
+  if updated_rows == 0 {
+    return Err(AppError::NotFound("指定的 Prompt 不存在".to_string()));
+  }
+
+  fetch_prompt(&connection, id)?
+    .ok_or_else(|| AppError::Database("读取更新后的 Prompt 失败".to_string()))
+}
+
+#[tauri::command]
+fn get_prompt_meta(
+  state: tauri::State<'_, AppState>,
+  id: i64,
+) -> Result<BTreeMap<String, String>, AppError> {
+  let connection = open_connection(&state.db_path)?;
+  let prompt = fetch_prompt(&connection, id)?
+    .ok_or_else(|| AppError::NotFound("指定的 Prompt 不存在".to_string()))?;
+  Ok(prompt.meta)
+}
+
+#[tauri::command]
+fn set_prompt_meta(
+  state: tauri::State<'_, AppState>,
+  id: i64,
+  key: String,
+  value: String,
+) -> Result<PromptRecord, AppError> {
+  let normalized_key = normalize_meta_key(&key)?;
+  let connection = open_connection(&state.db_path)?;
+  if fetch_prompt(&connection, id)?.is_none() {
+    return Err(AppError::NotFound("指定的 Prompt 不存在".to_string()));
+  }
+
+  upsert_prompt_meta(&connection, id, &normalized_key, &value)?;
+  fetch_prompt(&connection, id)?
+    .ok_or_else(|| AppError::Database("读取更新后的 Prompt 失败".to_string()))
+}
+
+#[tauri::command]
+fn delete_prompt_meta(
+  state: tauri::State<'_, AppState>,
+  id: i64,
+  key: String,
+) -> Result<PromptRecord, AppError> {
+  let normalized_key = normalize_meta_key(&key)?;
+  let connection = open_connection(&state.db_path)?;
+  connection
+    .execute(
+      "DELETE FROM prompt_meta WHERE prompt_id = ?1 AND key = ?2",
+      params![id, normalized_key],
+    )
+    .map_err(AppError::from)?;
+
+  fetch_prompt(&connection, id)?
+    .ok_or_else(|| AppError::NotFound("指定的 Prompt 不存在".to_string()))
+}
+
+#[tauri::command]
+fn request_delete_prompt(
+  state: tauri::State<'_, AppState>,
+  id: i64,
+) -> Result<DeleteConfirmation, AppError> {
+  let connection = open_connection(&state.db_path)?;
+  issue_delete_token(&connection, &state.delete_tokens, id, Instant::now())
+}
+
+fn issue_delete_token(
+  connection: &Connection,
+  delete_tokens: &Mutex<HashMap<i64, (String, Instant)>>,
+  id: i64,
+  now: Instant,
+) -> Result<DeleteConfirmation, AppError> {
+  if fetch_prompt(connection, id)?.is_none() {
+    return Err(AppError::NotFound("指定的 Prompt 不存在".to_string()));
+  }
+
+  let mut hasher = RandomState::new().build_hasher();
+  hasher.write_i64(id);
+  hasher.write_u128(
+    std::time::SystemTime::now()
+      .duration_since(std::time::UNIX_EPOCH)
+      .unwrap_or_default()
+      .as_nanos(),
+  );
+  let token = format!("{:016x}", hasher.finish());
+
+  let mut delete_tokens = delete_tokens
+    .lock()
+    .unwrap_or_else(|poisoned| poisoned.into_inner());
+  delete_tokens.retain(|_, (_, expires_at)| *expires_at > now);
+  delete_tokens.insert(id, (token.clone(), now + DELETE_TOKEN_TTL));
+
+  Ok(DeleteConfirmation {
+    token,
+    expires_in_seconds: DELETE_TOKEN_TTL.as_secs(),
+  })
+}
+
+#[tauri::command]
+fn confirm_delete_prompt(
+  state: tauri::State<'_, AppState>,
+  id: i64,
+  token: String,
+) -> Result<(), AppError> {
+  consume_delete_token(&state.delete_tokens, id, &token, Instant::now())?;
+
+  let deleted_rows = retry_on_busy(|| {
+    let connection = open_connection(&state.db_path)?;
+    connection
+      .execute("DELETE FROM prompts WHERE id = ?1", params![id])
+      .map_err(AppError::from)
+  })?;
+  if deleted_rows == 0 {
+    return Err(AppError::NotFound("指定的 Prompt 不存在".to_string()));
+  }
+  Ok(())
+}
+
+fn consume_delete_token(
+  delete_tokens: &Mutex<HashMap<i64, (String, Instant)>>,
+  id: i64,
+  token: &str,
+  now: Instant,
+) -> Result<(), AppError> {
+  let mut delete_tokens = delete_tokens
+    .lock()
+    .unwrap_or_else(|poisoned| poisoned.into_inner());
+  match delete_tokens.get(&id) {
+    Some((_, expires_at)) if *expires_at <= now => {
+      delete_tokens.remove(&id);
+      Err(AppError::Validation(
+        "删除确认已过期，请重新发起删除".to_string(),
+      ))
+    }
+    Some((expected_token, _)) if *expected_token == token.trim() => {
+      delete_tokens.remove(&id);
+      Ok(())
+    }
+    _ => Err(AppError::Validation("删除确认令牌无效".to_string())),
+  }
+}
+
+#[tauri::command]
+fn add_comment(
+  state: tauri::State<'_, AppState>,
+  prompt_id: i64,
+  body: String,
+) -> Result<PromptCommentRecord, AppError> {
+  let normalized_body = body.trim().to_string();
+  if normalized_body.is_empty() {
+    return Err(AppError::Validation("评论内容不能为空".to_string()));
+  }
+
+  let connection = open_connection(&state.db_path)?;
+  if fetch_prompt(&connection, prompt_id)?.is_none() {
+    return Err(AppError::NotFound("指定的 Prompt 不存在".to_string()));
+  }
+
+  let created_at = now_iso();
+  connection
+    .execute(
+      "INSERT INTO prompt_comments (prompt_id, body, created_at) VALUES (?1, ?2, ?3)",
+      params![prompt_id, normalized_body, created_at],
+    )
+    .map_err(AppError::from)?;
+
+  Ok(PromptCommentRecord {
+    id: connection.last_insert_rowid(),
+    prompt_id,
+    body: normalized_body,
+    created_at,
+  })
+}
+
+#[tauri::command]
+fn list_comments(
+  state: tauri::State<'_, AppState>,
+  prompt_id: i64,
+) -> Result<Vec<PromptCommentRecord>, AppError> {
+  let connection = open_connection(&state.db_path)?;
+  list_comments_with(&connection, prompt_id)
+}
+
+fn list_comments_with(
+  connection: &Connection,
+  prompt_id: i64,
+) -> Result<Vec<PromptCommentRecord>, AppError> {
+  let mut statement = connection
+    .prepare(
+      "
+      SELECT id, prompt_id, body, created_at
+      FROM prompt_comments
+      WHERE prompt_id = ?1
+      ORDER BY created_at ASC, id ASC
+      ",
+    )
+    .map_err(AppError::from)?;
+  let rows = statement
+    .query_map(params![prompt_id], |row| {
+      Ok(PromptCommentRecord {
+        id: row.get(0)?,
+        prompt_id: row.get(1)?,
+        body: row.get(2)?,
+        created_at: row.get(3)?,
+      })
+    })
+    .map_err(AppError::from)?;
+
+  let mut comments = Vec::new();
+  for row in rows {
+    comments.push(row.map_err(AppError::from)?);
+  }
+  Ok(comments)
+}
+
+#[tauri::command]
+fn delete_comment(state: tauri::State<'_, AppState>, id: i64) -> Result<(), AppError> {
+  let connection = open_connection(&state.db_path)?;
+  connection
+    .execute("DELETE FROM prompt_comments WHERE id = ?1", params![id])
+    .map_err(AppError::from)?;
+  Ok(())
+}
+
+#[tauri::command]
+fn save_variable_preset(
+  state: tauri::State<'_, AppState>,
+  prompt_id: i64,
+  name: String,
+  values: Value,
+) -> Result<VariablePresetRecord, AppError> {
+  let normalized_name = normalize_preset_name(&name)?;
+  let Value::Object(values) = values else {
+    return Err(AppError::Validation("预设变量必须是 JSON 对象".to_string()));
+  };
+
+  let connection = open_connection(&state.db_path)?;
+  if fetch_prompt(&connection, prompt_id)?.is_none() {
+    return Err(AppError::NotFound("指定的 Prompt 不存在".to_string()));
+  }
+
+  let preset_id = upsert_variable_preset(&connection, prompt_id, &normalized_name, &values)?;
+  fetch_variable_preset(&connection, preset_id)?
+    .ok_or_else(|| AppError::Database("读取变量预设失败".to_string()))
+}
+
+#[tauri::command]
+fn list_variable_presets(
+  state: tauri::State<'_, AppState>,
+  prompt_id: i64,
+) -> Result<Vec<VariablePresetRecord>, AppError> {
+  let connection = open_connection(&state.db_path)?;
+  fetch_variable_presets(&connection, prompt_id)
+}
+
+#[tauri::command]
+fn delete_variable_preset(state: tauri::State<'_, AppState>, id: i64) -> Result<(), AppError> {
+  let connection = open_connection(&state.db_path)?;
+  let deleted_rows = connection
+    .execute("DELETE FROM variable_presets WHERE id = ?1", params![id])
+    .map_err(AppError::from)?;
+  if deleted_rows == 0 {
+    return Err(AppError::NotFound("指定的变量预设不存在".to_string()));
+  }
+  Ok(())
+}
+
+#[tauri::command]
+fn list_prompt_test_cases(
+  state: tauri::State<'_, AppState>,
+  prompt_id: i64,
+) -> Result<Vec<PromptTestCaseRecord>, AppError> {
+  let connection = open_connection(&state.db_path)?;
+  fetch_test_cases(&connection, prompt_id)
+}
+
+#[tauri::command]
+fn create_prompt_test_case(
+  state: tauri::State<'_, AppState>,
+  prompt_id: i64,
+  name: String,
+  variables: Value,
+) -> Result<PromptTestCaseRecord, AppError> {
+  let normalized_name = normalize_test_case_name(&name)?;
+  let Value::Object(variables) = variables else {
+    return Err(AppError::Validation(
+      "测试用例变量必须是 JSON 对象".to_string(),
+    ));
+  };
+
+  let connection = open_connection(&state.db_path)?;
+  if fetch_prompt(&connection, prompt_id)?.is_none() {
+    return Err(AppError::NotFound("指定的 Prompt 不存在".to_string()));
+  }
+  if !insert_test_case(&connection, prompt_id, &normalized_name, &variables)? {
+    return Err(AppError::Validation(format!(
+      "测试用例「{normalized_name}」已存在"
+    )));
+  }
+
+  fetch_test_case(&connection, connection.last_insert_rowid())?
+    .ok_or_else(|| AppError::Database("读取新建测试用例失败".to_string()))
+}
+
+#[tauri::command]
+fn update_prompt_test_case(
+  state: tauri::State<'_, AppState>,
+  id: i64,
+  name: String,
+  variables: Value,
+) -> Result<PromptTestCaseRecord, AppError> {
+  let normalized_name = normalize_test_case_name(&name)?;
+  let Value::Object(variables) = variables else {
+    return Err(AppError::Validation(
+      "测试用例变量必须是 JSON 对象".to_string(),
+    ));
+  };
+
+  let connection = open_connection(&state.db_path)?;
+  let Some(existing) = fetch_test_case(&connection, id)? else {
+    return Err(AppError::NotFound("指定的测试用例不存在".to_string()));
+  };
+  let conflict_id = connection
+    .query_row(
+      "SELECT id FROM prompt_test_cases WHERE prompt_id = ?1 AND name = ?2 AND id <> ?3",
+      params![existing.prompt_id, normalized_name, id],
+      |row| row.get::<_, i64>(0),
+    )
+    .optional()
+    .map_err(AppError::from)?;
+  if conflict_id.is_some() {
+    return Err(AppError::Validation(format!(
+      "测试用例「{normalized_name}」已存在"
+    )));
+  }
+
+  let variables_json = serde_json::to_string(&variables).map_err(AppError::from)?;
+  connection
+    .execute(
+      "UPDATE prompt_test_cases SET name = ?1, variables_json = ?2, updated_at = ?3 WHERE id = ?4",
+      params![normalized_name, variables_json, now_iso(), id],
+    )
+    .map_err(AppError::from)?;
+
+  fetch_test_case(&connection, id)?
+    .ok_or_else(|| AppError::Database("读取更新后的测试用例失败".to_string()))
+}
+
+#[tauri::command]
+fn delete_prompt_test_case(state: tauri::State<'_, AppState>, id: i64) -> Result<(), AppError> {
+  let connection = open_connection(&state.db_path)?;
+  let deleted_rows = connection
+    .execute("DELETE FROM prompt_test_cases WHERE id = ?1", params![id])
+    .map_err(AppError::from)?;
+  if deleted_rows == 0 {
+    return Err(AppError::NotFound("指定的测试用例不存在".to_string()));
+  }
+  Ok(())
+}
+
+#[tauri::command]
+fn run_prompt_test_cases(
+  state: tauri::State<'_, AppState>,
+  prompt_id: i64,
+  content_override: Option<String>,
+) -> Result<Vec<TestCaseRun>, AppError> {
+  let connection = open_connection(&state.db_path)?;
+  let prompt = fetch_prompt(&connection, prompt_id)?
+    .ok_or_else(|| AppError::NotFound("指定的 Prompt 不存在".to_string()))?;
+  let snippets = load_snippet_contents(&connection)?;
+  let content = expand_snippet_includes(
+    content_override.as_deref().unwrap_or(&prompt.content),
+    &snippets,
+    0,
+  )?;
+
+  let mut runs = Vec::new();
+  for test_case in fetch_test_cases(&connection, prompt_id)? {
+    let mut values = match test_case.variables {
+      Value::Object(values) => values,
+      _ => Map::new(),
+    };
+    apply_variable_defaults(&prompt.variables_schema, &mut values);
+    let rendered = render_template(&content, &values, false)?;
+    runs.push(TestCaseRun {
+      name: test_case.name,
+      text: rendered.text,
+      missing_variables: rendered.missing_variables,
+    });
+  }
+  Ok(runs)
+}
+
+#[tauri::command]
+fn list_snippets(state: tauri::State<'_, AppState>) -> Result<Vec<SnippetRecord>, AppError> {
+  let connection = open_connection(&state.db_path)?;
+  fetch_snippets(&connection)
+}
+
+#[tauri::command]
+fn create_snippet(
+  state: tauri::State<'_, AppState>,
+  name: String,
+  content: String,
+) -> Result<SnippetRecord, AppError> {
+  let normalized_name = normalize_snippet_name(&name)?;
+  let connection = open_connection(&state.db_path)?;
+  let timestamp = now_iso();
+  let inserted_rows = connection
+    .execute(
+      "
+      INSERT INTO snippets (name, content, created_at, updated_at)
+      VALUES (?1, ?2, ?3, ?3)
+      ON CONFLICT(name) DO NOTHING
+      ",
+      params![normalized_name, content, timestamp],
+    )
+    .map_err(AppError::from)?;
+  if inserted_rows == 0 {
+    return Err(AppError::Validation(format!(
+      "片段「{normalized_name}」已存在"
+    )));
+  }
+
+  fetch_snippet(&connection, connection.last_insert_rowid())?
+    .ok_or_else(|| AppError::Database("读取新建片段失败".to_string()))
+}
+
+#[tauri::command]
+fn update_snippet(
+  state: tauri::State<'_, AppState>,
+  id: i64,
+  name: String,
+  content: String,
+) -> Result<SnippetRecord, AppError> {
+  let normalized_name = normalize_snippet_name(&name)?;
+  let connection = open_connection(&state.db_path)?;
+  let conflict_id = connection
+    .query_row(
+      "SELECT id FROM snippets WHERE name = ?1 AND id <> ?2",
+      params![normalized_name, id],
+      |row| row.get::<_, i64>(0),
+    )
+    .optional()
+    .map_err(AppError::from)?;
+  if conflict_id.is_some() {
+    return Err(AppError::Validation(format!(
+      "片段「{normalized_name}」已存在"
+    )));
+  }
+
+  let updated_rows = connection
+    .execute(
+      "UPDATE snippets SET name = ?1, content = ?2, updated_at = ?3 WHERE id = ?4",
+      params![normalized_name, content, now_iso(), id],
+    )
+    .map_err(AppError::from)?;
+  if updated_rows == 0 {
+    return Err(AppError::NotFound("指定的片段不存在".to_string()));
+  }
+
+  fetch_snippet(&connection, id)?
+    .ok_or_else(|| AppError::Database("读取更新后的片段失败".to_string()))
+}
+
+#[tauri::command]
+fn delete_snippet(state: tauri::State<'_, AppState>, id: i64) -> Result<(), AppError> {
+  let connection = open_connection(&state.db_path)?;
+  let deleted_rows = connection
+    .execute("DELETE FROM snippets WHERE id = ?1", params![id])
+    .map_err(AppError::from)?;
+  if deleted_rows == 0 {
+    return Err(AppError::NotFound("指定的片段不存在".to_string()));
+  }
+  Ok(())
+}
+
+#[tauri::command]
+fn find_prompts_using_snippet(
+  state: tauri::State<'_, AppState>,
+  name: String,
+) -> Result<Vec<PromptRecord>, AppError> {
+  let connection = open_connection(&state.db_path)?;
+  let snippets = load_snippet_contents(&connection)?;
+  let mut affected_names: HashSet<String> = HashSet::from([name.trim().to_lowercase()]);
+  loop {
+    let dependents = snippets
+      .iter()
+      .filter(|(snippet_name, _)| !affected_names.contains(*snippet_name))
+      .filter(|(_, content)| {
+        snippet_include_names(content)
+          .iter()
+          .any(|included| affected_names.contains(included))
+      })
+      .map(|(snippet_name, _)| snippet_name.clone())
+      .collect::<Vec<_>>();
+    if dependents.is_empty() {
+      break;
+    }
+    affected_names.extend(dependents);
+  }
+
+  let prompts = query_prompts(&connection, &PromptFilter::default(), None)?;
+  Ok(
+    prompts
+      .into_iter()
+      .filter(|prompt| {
+        snippet_include_names(&prompt.content)
+          .iter()
+          .any(|included| affected_names.contains(included))
+      })
+      .collect(),
+  )
+}
+
+#[tauri::command]
+fn log_prompt_usage(
+  state: tauri::State<'_, AppState>,
+  input: LogUsageInput,
+) -> Result<LogUsageResult, AppError> {
+  retry_on_busy(|| {
+    let mut connection = open_connection(&state.db_path)?;
+    record_prompt_usage(&mut connection, input.clone())
+  })
+}
+
+fn record_prompt_usage(
+  connection: &mut Connection,
+  input: LogUsageInput,
+) -> Result<LogUsageResult, AppError> {
+  if let Some(score) = input.rating {
+    validate_rating(score)?;
+  }
+
+  let now = now_iso();
+  let transaction = connection.transaction().map_err(AppError::from)?;
+
+  let mut input_vars = input.input_vars;
+  let variables_schema = transaction
+    .query_row(
+      "SELECT variables_schema FROM prompts WHERE id = ?1",
+      params![input.prompt_id],
+      |row| row.get::<_, String>(0),
+    )
+    .optional()
+    .map_err(AppError::from)?
+    .map(|raw| decode_variables_schema(&raw))
+    .unwrap_or_default();
+  if let Value::Object(values) = &mut input_vars {
+    apply_variable_defaults(&variables_schema, values);
+  }
+
+  if input.validate.unwrap_or(false) {
+    let empty_values = Map::new();
+    let validation_errors = validate_input_vars(
+      &variables_schema,
+      input_vars.as_object().unwrap_or(&empty_values),
+    );
+    if !validation_errors.is_empty() {
+      return Ok(LogUsageResult {
+        logged: false,
+        log_id: None,
+        truncated: false,
+        evicted: 0,
+        missing_variables: Vec::new(),
+        extra_variables: Vec::new(),
+        validation_errors,
+      });
+    }
+  }
+  let input_vars_json = serde_json::to_string(&input_vars).map_err(AppError::from)?;
+
+  let mut missing_variables = Vec::new();
+  let mut extra_variables = Vec::new();
+  if input.check_variables.unwrap_or(false) {
+    let content = transaction
+      .query_row(
+        "SELECT content, content_compressed FROM prompts WHERE id = ?1",
+        params![input.prompt_id],
+        |row| read_prompt_content(row, 0, 1),
+      )
+      .optional()
+      .map_err(AppError::from)?
+      .ok_or_else(|| AppError::NotFound("记录使用日志失败：Prompt 不存在".to_string()))?;
+    let variable_names = extract_variable_names(&content);
+    let supplied_keys = input_vars
+      .as_object()
+      .map(|values| values.keys().cloned().collect::<Vec<_>>())
+      .unwrap_or_default();
+
+    missing_variables = variable_names
+      .iter()
+      .filter(|name| !supplied_keys.contains(*name))
+      .cloned()
+      .collect();
+    extra_variables = supplied_keys
+      .into_iter()
+      .filter(|key| !variable_names.contains(key))
+      .collect();
+  }
+
+  let output_text_limit = read_output_text_limit(&transaction)?;
+  let (output_text, original_length) = truncate_output_text(&input.output_text, output_text_limit);
+  transaction
+    .execute(
+      "
+      INSERT INTO usage_logs (prompt_id, input_vars, output_text, rating, used_at, output_original_length)
+      VALUES (?1, ?2, ?3, ?4, ?5, ?6)
+      ",
+      params![
+        input.prompt_id,
+        input_vars_json,
+        output_text,
+        input.rating,
+        now,
+        original_length
+      ],
+    )
+    .map_err(AppError::from)?;
+  let log_id = transaction.last_insert_rowid();
+  transaction
+    .execute(
+      "UPDATE prompts SET last_used_at = ?1 WHERE id = ?2",
+      params![now, input.prompt_id],
+    )
+    .map_err(AppError::from)?;
+
+  if let Some(score) = input.rating {
+    if !apply_prompt_rating(&transaction, input.prompt_id, score)? {
+      return Err(AppError::NotFound(
+        "记录使用日志失败：Prompt 不存在".to_string(),
+      ));
+    }
+  }
+
+  let usage_log_limit = read_usage_log_limit(&transaction)?;
+  let evicted = evict_usage_logs(&transaction, input.prompt_id, usage_log_limit)?;
+
+  transaction.commit().map_err(AppError::from)?;
+  Ok(LogUsageResult {
+    logged: true,
+    log_id: Some(log_id),
+    truncated: original_length.is_some(),
+    evicted,
+    missing_variables,
+    extra_variables,
+    validation_errors: Vec::new(),
+  })
+}
+
+#[tauri::command]
+fn log_usage_batch(
+  state: tauri::State<'_, AppState>,
+  items: Vec<LogUsageInput>,
+) -> Result<i64, AppError> {
+  retry_on_busy(|| {
+    let mut connection = open_connection(&state.db_path)?;
+    record_usage_batch(&mut connection, &items)
+  })
+}
+
+fn record_usage_batch(
+  connection: &mut Connection,
+  items: &[LogUsageInput],
+) -> Result<i64, AppError> {
+  for (index, item) in items.iter().enumerate() {
+    if let Some(score) = item.rating {
+      validate_rating(score).map_err(|_| {
+        AppError::Validation(format!("第 {} 条记录的评分必须在 1 到 5 之间", index + 1))
+      })?;
+    }
+    if !item.input_vars.is_object() {
+      return Err(AppError::Validation(format!(
+        "第 {} 条记录的 inputVars 必须是对象",
+        index + 1
+      )));
+    }
+    if item.check_variables.unwrap_or(false) {
+      return Err(AppError::Validation(format!(
+        "第 {} 条记录设置了 checkVariables，批量记录不支持返回变量检查结果，请改用单条记录",
+        index + 1
+      )));
+    }
+  }
+
+  let now = now_iso();
+  let transaction = connection
+    .transaction_with_behavior(TransactionBehavior::Immediate)
+    .map_err(AppError::from)?;
+  let output_text_limit = read_output_text_limit(&transaction)?;
+
+  let mut affected_prompt_ids = BTreeMap::new();
+  for (index, item) in items.iter().enumerate() {
+    if !affected_prompt_ids.contains_key(&item.prompt_id) {
+      let variables_schema = transaction
+        .query_row(
+          "SELECT variables_schema FROM prompts WHERE id = ?1",
+          params![item.prompt_id],
+          |row| row.get::<_, String>(0),
+        )
+        .optional()
+        .map_err(AppError::from)?
+        .map(|raw| decode_variables_schema(&raw))
+        .ok_or_else(|| {
+          AppError::NotFound(format!("第 {} 条记录对应的 Prompt 不存在", index + 1))
+        })?;
+      affected_prompt_ids.insert(item.prompt_id, variables_schema);
+    }
+
+    let mut input_vars = item.input_vars.clone();
+    if let (Value::Object(values), Some(variables_schema)) =
+      (&mut input_vars, affected_prompt_ids.get(&item.prompt_id))
+    {
+      apply_variable_defaults(variables_schema, values);
+      if item.validate.unwrap_or(false) {
+        let validation_errors = validate_input_vars(variables_schema, values);
+        if !validation_errors.is_empty() {
+          let details = validation_errors
+            .iter()
+            .map(|error| format!("{}：{}", error.variable, error.message))
+            .collect::<Vec<_>>()
+            .join("；");
+          return Err(AppError::Validation(format!(
+            "第 {} 条记录的变量校验失败：{details}",
+            index + 1
+          )));
+        }
+      }
+    }
+    let (output_text, original_length) = truncate_output_text(&item.output_text, output_text_limit);
+    transaction
+      .execute(
+        "
+        INSERT INTO usage_logs (prompt_id, input_vars, output_text, rating, used_at, output_original_length)
+        VALUES (?1, ?2, ?3, ?4, ?5, ?6)
+        ",
+        params![
+          item.prompt_id,
+          serde_json::to_string(&input_vars).map_err(AppError::from)?,
+          output_text,
+          item.rating,
+          now,
+          original_length
+        ],
+      )
+      .map_err(AppError::from)?;
+  }
+
+  let usage_log_limit = read_usage_log_limit(&transaction)?;
+  for prompt_id in affected_prompt_ids.keys() {
+    recompute_prompt_score(&transaction, *prompt_id)?;
+    transaction
+      .execute(
+        "UPDATE prompts SET last_used_at = ?1 WHERE id = ?2",
+        params![now, prompt_id],
+      )
+      .map_err(AppError::from)?;
+    evict_usage_logs(&transaction, *prompt_id, usage_log_limit)?;
+  }
+
+  transaction.commit().map_err(AppError::from)?;
+  Ok(items.len() as i64)
+}
+
+#[tauri::command]
+fn rate_prompt(
+  state: tauri::State<'_, AppState>,
+  id: i64,
+  rating: i64,
+) -> Result<PromptRecord, AppError> {
+  validate_rating(rating)?;
+  retry_on_busy(|| {
+    let mut connection = open_connection(&state.db_path)?;
+    let transaction = connection
+      .transaction_with_behavior(TransactionBehavior::Immediate)
+      .map_err(AppError::from)?;
+    if !record_direct_rating(&transaction, id, rating)? {
+      return Err(AppError::NotFound("指定的 Prompt 不存在".to_string()));
+    }
+
+    let prompt = fetch_prompt(&transaction, id)?
+      .ok_or_else(|| AppError::Database("读取更新后的 Prompt 失败".to_string()))?;
+    transaction.commit().map_err(AppError::from)?;
+    Ok(prompt)
+  })
+}
+
+#[tauri::command]
+fn get_usage_log_limit(state: tauri::State<'_, AppState>) -> Result<i64, AppError> {
+  let connection = open_connection(&state.db_path)?;
+  read_usage_log_limit(&connection)
+}
+
+#[tauri::command]
+fn update_usage_log_limit(state: tauri::State<'_, AppState>, limit: i64) -> Result<i64, AppError> {
+  if limit < 0 {
+    return Err(AppError::Validation(
+      "使用日志上限不能为负数（0 表示不限制）".to_string(),
+    ));
+  }
+
+  let connection = open_connection(&state.db_path)?;
+  write_setting(&connection, USAGE_LOG_LIMIT_SETTING_KEY, &limit.to_string())?;
+  Ok(limit)
+}
+
+#[tauri::command]
+fn get_output_text_limit(state: tauri::State<'_, AppState>) -> Result<i64, AppError> {
+  let connection = open_connection(&state.db_path)?;
+  read_output_text_limit(&connection)
+}
+
+#[tauri::command]
+fn update_output_text_limit(
+  state: tauri::State<'_, AppState>,
+  limit: i64,
+) -> Result<i64, AppError> {
+  if limit < 0 {
+    return Err(AppError::Validation(
+      "输出内容长度上限不能为负数（0 表示不限制）".to_string(),
+    ));
+  }
+
+  let connection = open_connection(&state.db_path)?;
+  write_setting(
+    &connection,
+    OUTPUT_TEXT_LIMIT_SETTING_KEY,
+    &limit.to_string(),
+  )?;
+  Ok(limit)
+}
+
+#[tauri::command]
+fn get_content_compression_threshold(state: tauri::State<'_, AppState>) -> Result<i64, AppError> {
+  let connection = open_connection(&state.db_path)?;
+  read_content_compression_threshold(&connection)
+}
+
+#[tauri::command]
+fn update_content_compression_threshold(
+  state: tauri::State<'_, AppState>,
+  threshold: i64,
+) -> Result<i64, AppError> {
+  if threshold < 0 {
+    return Err(AppError::Validation(
+      "压缩阈值不能为负数（0 表示关闭压缩）".to_string(),
+    ));
+  }
+
+  let connection = open_connection(&state.db_path)?;
+  write_setting(
+    &connection,
+    CONTENT_COMPRESSION_SETTING_KEY,
+    &threshold.to_string(),
+  )?;
+  Ok(threshold)
+}
+
+#[tauri::command]
+fn get_auto_backup_config(state: tauri::State<'_, AppState>) -> Result<AutoBackupConfig, AppError> {
+  let connection = open_connection(&state.db_path)?;
+  read_auto_backup_config(&connection)
+}
+
+#[tauri::command]
+fn configure_auto_backup(
+  state: tauri::State<'_, AppState>,
+  enabled: bool,
+  interval_days: i64,
+  keep: i64,
+) -> Result<AutoBackupConfig, AppError> {
+  if interval_days < 1 {
+    return Err(AppError::Validation("备份间隔至少为 1 天".to_string()));
+  }
+  if keep < 1 {
+    return Err(AppError::Validation("至少需要保留 1 份备份".to_string()));
+  }
+
+  let connection = open_connection(&state.db_path)?;
+  write_setting(
+    &connection,
+    AUTO_BACKUP_ENABLED_SETTING_KEY,
+    if enabled { "true" } else { "false" },
+  )?;
+  write_setting(
+    &connection,
+    AUTO_BACKUP_INTERVAL_SETTING_KEY,
+    &interval_days.to_string(),
+  )?;
+  write_setting(&connection, AUTO_BACKUP_KEEP_SETTING_KEY, &keep.to_string())?;
+  read_auto_backup_config(&connection)
+}
+
+#[tauri::command]
+fn get_max_tag_count(state: tauri::State<'_, AppState>) -> Result<i64, AppError> {
+  let connection = open_connection(&state.db_path)?;
+  read_max_tag_count(&connection)
+}
+
+#[tauri::command]
+fn update_max_tag_count(state: tauri::State<'_, AppState>, limit: i64) -> Result<i64, AppError> {
+  if limit < 0 {
+    return Err(AppError::Validation(
+      "标签数量上限不能为负数（0 表示不限制）".to_string(),
+    ));
+  }
+
+  let connection = open_connection(&state.db_path)?;
+  write_setting(&connection, MAX_TAGS_SETTING_KEY, &limit.to_string())?;
+  Ok(limit)
+}
+
+#[tauri::command]
+fn get_content_normalization(state: tauri::State<'_, AppState>) -> Result<bool, AppError> {
+  let connection = open_connection(&state.db_path)?;
+  read_content_normalization(&connection)
+}
+
+#[tauri::command]
+fn update_content_normalization(
+  state: tauri::State<'_, AppState>,
+  enabled: bool,
+) -> Result<bool, AppError> {
+  let connection = open_connection(&state.db_path)?;
+  write_setting(
+    &connection,
+    CONTENT_NORMALIZATION_SETTING_KEY,
+    if enabled { "true" } else { "false" },
+  )?;
+  Ok(enabled)
+}
+
+#[tauri::command]
+fn get_rating_trend(
+  state: tauri::State<'_, AppState>,
+  prompt_id: i64,
+  bucket: String,
+) -> Result<Vec<RatingBucket>, AppError> {
+  let bucket_kind = bucket.trim().to_lowercase();
+  if !matches!(bucket_kind.as_str(), "day" | "week" | "month") {
+    return Err(AppError::Validation(format!(
+      "不支持的统计粒度：{bucket}（可选 day、week、month）"
+    )));
+  }
+
+  let connection = open_connection(&state.db_path)?;
+  let mut statement = connection
+    .prepare(
+      "
+      SELECT rating, used_at
+      FROM usage_logs
+      WHERE prompt_id = ?1 AND rating IS NOT NULL
+      ",
+    )
+    .map_err(AppError::from)?;
+  let rows = statement
+    .query_map(params![prompt_id], |row| {
+      Ok((row.get::<_, i64>(0)?, row.get::<_, String>(1)?))
+    })
+    .map_err(AppError::from)?;
+
+  let mut totals: BTreeMap<String, (i64, i64)> = BTreeMap::new();
+  for row in rows {
+    let (rating, used_at) = row.map_err(AppError::from)?;
+    let Ok(parsed) = DateTime::parse_from_rfc3339(&used_at) else {
+      continue;
+    };
+    let used_at_utc = parsed.with_timezone(&Utc);
+    let bucket_key = match bucket_kind.as_str() {
+      "day" => used_at_utc.format("%Y-%m-%d").to_string(),
+      "week" => {
+        let iso_week = used_at_utc.iso_week();
+        format!("{}-W{:02}", iso_week.year(), iso_week.week())
+      }
+      _ => used_at_utc.format("%Y-%m").to_string(),
+    };
+    let entry = totals.entry(bucket_key).or_insert((0, 0));
+    entry.0 += rating;
+    entry.1 += 1;
+  }
+
+  Ok(
+    totals
+      .into_iter()
+      .map(|(bucket, (sum, count))| RatingBucket {
+        bucket,
+        average_rating: sum as f64 / count as f64,
+        count,
+      })
+      .collect(),
+  )
+}
+
+fn load_category_names(connection: &Connection) -> Result<HashMap<i64, String>, AppError> {
+  let mut statement = connection
+    .prepare("SELECT id, name FROM categories")
+    .map_err(AppError::from)?;
+  let rows = statement
+    .query_map([], |row| {
+      Ok((row.get::<_, i64>(0)?, row.get::<_, String>(1)?))
+    })
+    .map_err(AppError::from)?;
+
+  let mut names = HashMap::new();
+  for row in rows {
+    let (category_id, name) = row.map_err(AppError::from)?;
+    names.insert(category_id, name);
+  }
+  Ok(names)
+}
+
+fn load_export_collections(
+  connection: &Connection,
+  prompt_indexes: &HashMap<i64, usize>,
+) -> Result<Vec<ExportCollectionItem>, AppError> {
+  let tree = load_collection_tree(connection)?;
+  let collection_indexes = tree
+    .iter()
+    .enumerate()
+    .map(|(index, collection)| (collection.id, index))
+    .collect::<HashMap<_, _>>();
+  let mut collections = tree
+    .iter()
+    .map(|collection| ExportCollectionItem {
+      name: collection.name.clone(),
+      parent_index: collection
+        .parent_id
+        .and_then(|parent_id| collection_indexes.get(&parent_id).copied()),
+      prompt_indexes: Vec::new(),
+    })
+    .collect::<Vec<_>>();
+
+  let mut statement = connection
+    .prepare(
+      "
+      SELECT collection_id, prompt_id
+      FROM collection_prompts
+      ORDER BY added_at ASC, prompt_id ASC
+      ",
+    )
+    .map_err(AppError::from)?;
+  let rows = statement
+    .query_map([], |row| Ok((row.get::<_, i64>(0)?, row.get::<_, i64>(1)?)))
+    .map_err(AppError::from)?;
+
+  for row in rows {
+    let (collection_id, prompt_id) = row.map_err(AppError::from)?;
+    let (Some(collection_index), Some(prompt_index)) = (
+      collection_indexes.get(&collection_id),
+      prompt_indexes.get(&prompt_id),
+    ) else {
+      continue;
+    };
+    collections[*collection_index]
+      .prompt_indexes
+      .push(*prompt_index);
+  }
+
+  Ok(collections)
+}
+
+fn export_order_clause(order: Option<&str>) -> Result<&'static str, AppError> {
+  match order.map(|value| value.trim().to_lowercase()).as_deref() {
+    None | Some("") | Some("updated") => Ok("updated_at DESC, id DESC"),
+    Some("title") => Ok("title COLLATE NOCASE ASC, id ASC"),
+    Some("created") => Ok("created_at ASC, id ASC"),
+    Some("id") => Ok("id ASC"),
+    Some(other) => Err(AppError::Validation(format!(
+      "不支持的导出排序：{other}（可选 updated、title、created、id）"
+    ))),
+  }
+}
+
+fn fetch_export_usage_logs(
+  connection: &Connection,
+  prompt_id: i64,
+  options: &ExportOptions,
+) -> Result<Vec<UsageLogItem>, AppError> {
+  let mut statement = connection
+    .prepare(
+      "
+      SELECT input_vars, output_text, rating, used_at
+      FROM usage_logs
+      WHERE prompt_id = ?1
+      ORDER BY used_at ASC, id ASC
+      ",
+    )
+    .map_err(AppError::from)?;
+  let rows = statement
+    .query_map(params![prompt_id], |row| {
+      Ok((
+        row.get::<_, String>(0)?,
+        row.get::<_, String>(1)?,
+        row.get::<_, Option<i64>>(2)?,
+        row.get::<_, String>(3)?,
+      ))
+    })
+    .map_err(AppError::from)?;
+
+  let mut usage_logs = Vec::new();
+  for row in rows {
+    let (input_vars, output_text, rating, used_at) = row.map_err(AppError::from)?;
+    let (output_text, _) = truncate_output_text(&output_text, options.max_log_output_chars.max(0));
+    usage_logs.push(UsageLogItem {
+      input_vars: serde_json::from_str(&input_vars).unwrap_or_else(|_| Value::Object(Map::new())),
+      output_text,
+      rating: if options.include_scores { rating } else { None },
+      used_at: (!options.anonymize).then_some(used_at),
+    });
+  }
+  Ok(usage_logs)
+}
+
+fn normalize_since(value: &str) -> Result<String, AppError> {
+  DateTime::parse_from_rfc3339(value.trim())
+    .map(|timestamp| timestamp.with_timezone(&Utc).to_rfc3339())
+    .map_err(|_| AppError::Validation(format!("since 必须是 RFC3339 时间：{value}")))
+}
+
+fn fetch_prompt_tombstones(
+  connection: &Connection,
+  since: &str,
+) -> Result<Vec<PromptTombstone>, AppError> {
+  let mut statement = connection
+    .prepare(
+      "SELECT uuid, deleted_at FROM deleted_prompts WHERE deleted_at > ?1 ORDER BY deleted_at ASC, uuid ASC",
+    )
+    .map_err(AppError::from)?;
+  let rows = statement
+    .query_map(params![since], |row| {
+      Ok(PromptTombstone {
+        uuid: row.get(0)?,
+        deleted_at: row.get(1)?,
+      })
+    })
+    .map_err(AppError::from)?;
+  rows.collect::<Result<Vec<_>, _>>().map_err(AppError::from)
+}
+
+fn build_export_payload(
+  connection: &Connection,
+  options: &ExportOptions,
+) -> Result<ExportPayload, AppError> {
+  let category_names = load_category_names(connection)?;
+  let scoped_collection = match options.collection_id {
+    Some(collection_id) => Some(
+      fetch_collection(connection, collection_id)?
+        .ok_or_else(|| AppError::NotFound("指定的集合不存在".to_string()))?,
+    ),
+    None => None,
+  };
+
+  let filter = PromptFilter {
+    collection_id: scoped_collection.as_ref().map(|collection| collection.id),
+    favorites_only: options.favorites_only,
+    ..PromptFilter::default()
+  };
+  let since = options.since.as_deref().map(normalize_since).transpose()?;
+  let (where_clause, mut query_params) = prompt_filter_clause(&filter);
+  let mut sql = format!("SELECT {PROMPT_COLUMNS} FROM prompts{where_clause}");
+  if let Some(since) = &since {
+    sql.push_str(
+      " AND (updated_at > ? OR EXISTS (SELECT 1 FROM prompt_versions WHERE prompt_versions.prompt_id = prompts.id AND prompt_versions.created_at > ?))",
+    );
+    query_params.push(SqlValue::Text(since.clone()));
+    query_params.push(SqlValue::Text(since.clone()));
+  }
+  if let Some(ids) = &options.ids {
+    if ids.is_empty() {
+      sql.push_str(" AND 0");
+    } else {
+      sql.push_str(&format!(" AND id IN ({})", vec!["?"; ids.len()].join(", ")));
+      query_params.extend(ids.iter().map(|id| SqlValue::Integer(*id)));
+    }
+  }
+  let tag_filters = options
+    .tags
+    .iter()
+    .map(|tag| tag.trim())
+    .filter(|tag| !tag.is_empty())
+    .collect::<Vec<_>>();
+  if !tag_filters.is_empty() {
+    sql.push_str(&format!(
+      " AND ({})",
+      vec!["tags LIKE ?"; tag_filters.len()].join(" OR ")
+    ));
+    query_params.extend(
+      tag_filters
+        .iter()
+        .map(|tag| SqlValue::Text(format!("%\"{tag}\"%"))),
+    );
+  }
+  sql.push_str(" ORDER BY ");
+  let order = match options.order.as_deref() {
+    None if options.stable => Some("title"),
+    order => order,
+  };
+  sql.push_str(export_order_clause(order)?);
+
+  let mut statement = connection.prepare(&sql).map_err(AppError::from)?;
+  let rows = statement
+    .query_map(params_from_iter(query_params.iter()), row_to_prompt)
+    .map_err(AppError::from)?;
+
+  let mut export_prompts = Vec::new();
+  let mut prompt_indexes = HashMap::new();
+  for row in rows {
+    let prompt = row.map_err(AppError::from)?;
+    prompt_indexes.insert(prompt.id, export_prompts.len());
+    let example_output = fetch_example_output(connection, prompt.id)?;
+    let variable_presets = fetch_variable_presets(connection, prompt.id)?
+      .into_iter()
+      .map(|preset| PresetItem {
+        name: preset.name,
+        values: preset.values,
+      })
+      .collect();
+    let test_cases = fetch_test_cases(connection, prompt.id)?
+      .into_iter()
+      .map(|test_case| TestCaseItem {
+        name: test_case.name,
+        variables: test_case.variables,
+      })
+      .collect();
+    let usage_logs = if options.include_usage_logs {
+      fetch_export_usage_logs(connection, prompt.id, options)?
+    } else {
+      Vec::new()
+    };
+    let versions = if options.include_versions {
+      let mut versions = fetch_prompt_versions(connection, prompt.id)?;
+      if options.stable {
+        versions.reverse();
+      }
+      Some(
+        versions
+          .into_iter()
+          .map(|version| ExportVersionItem {
+            content: version.content,
+            change_note: if options.include_notes {
+              version.change_note
+            } else {
+              String::new()
+            },
+            created_at: (!options.anonymize).then_some(version.created_at),
+          })
+          .collect::<Vec<_>>(),
+      )
+    } else {
+      None
+    };
+
+    export_prompts.push(ExportPromptItem {
+      id: prompt.id,
+      uuid: prompt.uuid,
+      title: prompt.title,
+      description: prompt.description,
+      content: prompt.content,
+      tags: prompt.tags,
+      is_favorite: prompt.is_favorite,
+      score_avg: if options.anonymize || !options.include_scores {
+        0.0
+      } else {
+        prompt.score_avg
+      },
+      score_count: if options.anonymize || !options.include_scores {
+        0
+      } else {
+        prompt.score_count
+      },
+      source_url: prompt.source_url,
+      author: prompt.author,
+      model: prompt.model,
+      language: prompt.language,
+      params: prompt.params,
+      icon: prompt.icon,
+      category: prompt
+        .category_id
+        .and_then(|category_id| category_names.get(&category_id).cloned())
+        .unwrap_or_default(),
+      meta: prompt.meta,
+      example_output,
+      variables_schema: prompt.variables_schema,
+      variable_presets,
+      test_cases,
+      usage_logs,
+      versions,
+    });
+  }
+
+  let collections = match scoped_collection {
+    Some(collection) => vec![ExportCollectionItem {
+      name: collection.name,
+      parent_index: None,
+      prompt_indexes: (0..export_prompts.len()).collect(),
+    }],
+    None => load_export_collections(connection, &prompt_indexes)?,
+  };
+
+  let snippets = fetch_snippets(connection)?
+    .into_iter()
+    .map(|snippet| SnippetItem {
+      name: snippet.name,
+      content: snippet.content,
+    })
+    .collect();
+  let tombstones = match &since {
+    Some(since) => fetch_prompt_tombstones(connection, since)?,
+    None => Vec::new(),
+  };
+
+  Ok(ExportPayload {
+    schema_version: EXPORT_SCHEMA_VERSION,
+    app_version: env!("CARGO_PKG_VERSION").to_string(),
+    exported_at: (!options.anonymize && !options.stable).then(now_iso),
+    prompts: export_prompts,
+    collections,
+    snippets,
+    since,
+    tombstones,
+    stable: options.stable,
+  })
+}
+
+fn csv_escape(field: &str) -> String {
+  if field.contains([',', '"', '\n', '\r']) {
+    format!("\"{}\"", field.replace('"', "\"\""))
+  } else {
+    field.to_string()
+  }
+}
+
+fn markdown_fence(content: &str) -> String {
+  let mut longest_run = 0;
+  let mut current_run = 0;
+  for character in content.chars() {
+    if character == '`' {
+      current_run += 1;
+      longest_run = longest_run.max(current_run);
+    } else {
+      current_run = 0;
+    }
+  }
+  "`".repeat((longest_run + 1).max(3))
+}
+
+#[derive(Serialize)]
+#[serde(untagged)]
+enum SortedExportValue {
+  Object(BTreeMap<String, SortedExportValue>),
+  Array(Vec<SortedExportValue>),
+  Scalar(Value),
+}
+
+impl From<Value> for SortedExportValue {
+  fn from(value: Value) -> Self {
+    match value {
+      Value::Object(fields) => SortedExportValue::Object(
+        fields
+          .into_iter()
+          .map(|(key, value)| (key, SortedExportValue::from(value)))
+          .collect(),
+      ),
+      Value::Array(items) => {
+        SortedExportValue::Array(items.into_iter().map(SortedExportValue::from).collect())
+      }
+      scalar => SortedExportValue::Scalar(scalar),
+    }
+  }
+}
+
+fn sorted_export_value(payload: &ExportPayload) -> Result<SortedExportValue, AppError> {
+  Ok(SortedExportValue::from(
+    serde_json::to_value(payload).map_err(AppError::from)?,
+  ))
+}
+
+fn serialize_export_json(payload: &ExportPayload) -> Result<String, AppError> {
+  if payload.stable {
+    let mut output = serde_json::to_string_pretty(&sorted_export_value(payload)?)?;
+    output.push('\n');
+    return Ok(output);
+  }
+  serde_json::to_string_pretty(payload).map_err(AppError::from)
+}
+
+fn serialize_export_yaml(payload: &ExportPayload) -> Result<String, AppError> {
+  if payload.stable {
+    return serde_yaml::to_string(&sorted_export_value(payload)?).map_err(AppError::from);
+  }
+  serde_yaml::to_string(payload).map_err(AppError::from)
+}
+
+fn serialize_export_csv(payload: &ExportPayload) -> String {
+  let mut output = String::from(
+    "title,description,content,tags,is_favorite,score_avg,score_count,source_url,model,language,icon,category,author\r\n",
+  );
+  for prompt in &payload.prompts {
+    let fields = [
+      csv_escape(&prompt.title),
+      csv_escape(&prompt.description),
+      csv_escape(&prompt.content),
+      csv_escape(&prompt.tags.join(", ")),
+      prompt.is_favorite.to_string(),
+      prompt.score_avg.to_string(),
+      prompt.score_count.to_string(),
+      csv_escape(&prompt.source_url),
+      csv_escape(&prompt.model),
+      csv_escape(&prompt.language),
+      csv_escape(&prompt.icon),
+      csv_escape(&prompt.category),
+      csv_escape(&prompt.author),
+    ];
+    output.push_str(&fields.join(","));
+    output.push_str("\r\n");
+  }
+  output
+}
+
+fn markdown_table_cell(text: &str) -> String {
+  text.replace('|', "\\|").replace(['\r', '\n'], " ")
+}
+
+fn push_prompt_markdown(
+  output: &mut String,
+  prompt: &ExportPromptItem,
+  heading: &str,
+  include_variables: bool,
+  change_note: Option<&str>,
+) {
+  output.push_str(heading);
+  output.push(' ');
+  if !prompt.icon.is_empty() {
+    output.push_str(&prompt.icon);
+    output.push(' ');
+  }
+  output.push_str(&prompt.title);
+  if prompt.is_favorite {
+    output.push_str(" ⭐");
+  }
+  output.push_str("\n\n");
+
+  if !prompt.description.is_empty() {
+    output.push_str(&format!("> {}\n\n", prompt.description));
+  }
+  if !prompt.tags.is_empty() {
+    output.push_str(&format!("- 标签：{}\n", prompt.tags.join(", ")));
+  }
+  if !prompt.model.is_empty() {
+    output.push_str(&format!("- 模型：{}\n", prompt.model));
+  }
+  if !prompt.author.is_empty() {
+    output.push_str(&format!("- 作者：{}\n", prompt.author));
+  }
+  if !prompt.source_url.is_empty() {
+    output.push_str(&format!("- 来源：{}\n", prompt.source_url));
+  }
+  if prompt.score_count > 0 {
+    output.push_str(&format!(
+      "- 评分：{:.1}（{} 次）\n",
+      prompt.score_avg, prompt.score_count
+    ));
+  }
+  if let Some(change_note) = change_note.filter(|note| !note.trim().is_empty()) {
+    output.push_str(&format!("- 最近修改：{}\n", change_note.trim()));
+  }
+
+  let fence = markdown_fence(&prompt.content);
+  output.push_str(&format!("\n{fence}\n{}\n{fence}\n", prompt.content));
+
+  if include_variables && !prompt.variables_schema.is_empty() {
+    output.push_str("\n### 变量\n\n| 变量 | 名称 | 类型 | 必填 | 默认值 | 说明 |\n| --- | --- | --- | --- | --- | --- |\n");
+    for spec in &prompt.variables_schema {
+      let kind = serde_json::to_value(spec.kind)
+        .ok()
+        .and_then(|value| value.as_str().map(str::to_string))
+        .unwrap_or_default();
+      output.push_str(&format!(
+        "| `{}` | {} | {} | {} | {} | {} |\n",
+        spec.name,
+        markdown_table_cell(&spec.label),
+        kind,
+        if spec.required { "是" } else { "否" },
+        markdown_table_cell(spec.default.as_deref().unwrap_or_default()),
+        markdown_table_cell(&spec.description)
+      ));
+    }
+  }
+
+  if !prompt.example_output.is_empty() {
+    let example_fence = markdown_fence(&prompt.example_output);
+    output.push_str(&format!(
+      "\n### 示例输出\n\n{example_fence}\n{}\n{example_fence}\n",
+      prompt.example_output
+    ));
+  }
+}
+
+fn serialize_export_markdown(payload: &ExportPayload) -> String {
+  let mut output = String::from("# Prompt Library\n");
+  if let Some(exported_at) = &payload.exported_at {
+    output.push_str(&format!("\n导出时间：{exported_at}\n"));
+  }
+  for prompt in &payload.prompts {
+    output.push('\n');
+    push_prompt_markdown(&mut output, prompt, "##", true, None);
+  }
+  output
+}
+
+fn serialize_markdown_document(
+  payload: &ExportPayload,
+  include_toc: bool,
+  include_variables: bool,
+  include_change_note: bool,
+) -> String {
+  let mut output = String::from("# Prompt Library\n");
+  if let Some(exported_at) = &payload.exported_at {
+    output.push_str(&format!("\n导出时间：{exported_at}\n"));
+  }
+  output.push_str(&format!("\n共 {} 条 Prompt\n", payload.prompts.len()));
+
+  if include_toc && !payload.prompts.is_empty() {
+    output.push_str("\n## 目录\n\n");
+    for (index, prompt) in payload.prompts.iter().enumerate() {
+      output.push_str(&format!(
+        "{}. [{}](#prompt-{})\n",
+        index + 1,
+        prompt.title.replace('[', "\\[").replace(']', "\\]"),
+        index + 1
+      ));
+    }
+  }
+
+  for (index, prompt) in payload.prompts.iter().enumerate() {
+    let change_note = if include_change_note {
+      prompt
+        .versions
+        .as_ref()
+        .and_then(|versions| {
+          if payload.stable {
+            versions.last()
+          } else {
+            versions.first()
+          }
+        })
+        .map(|version| version.change_note.as_str())
+    } else {
+      None
+    };
+    output.push_str(&format!("\n<a id=\"prompt-{}\"></a>\n\n", index + 1));
+    push_prompt_markdown(&mut output, prompt, "##", include_variables, change_note);
+  }
+  output
+}
+
+#[tauri::command]
+fn export_prompts_markdown(
+  state: tauri::State<'_, AppState>,
+  options: Option<MarkdownExportOptions>,
+) -> Result<String, AppError> {
+  let MarkdownExportOptions {
+    selection,
+    include_toc,
+    include_variables,
+    include_change_note,
+  } = options.unwrap_or_default();
+  let export_options = ExportOptions {
+    include_versions: include_change_note,
+    ..selection
+  };
+
+  let connection = open_connection(&state.db_path)?;
+  let payload = build_export_payload(&connection, &export_options)?;
+  Ok(serialize_markdown_document(
+    &payload,
+    include_toc,
+    include_variables,
+    include_change_note,
+  ))
+}
+
+#[tauri::command]
+fn export_prompts(
+  state: tauri::State<'_, AppState>,
+  format: String,
+  options: Option<ExportOptions>,
+) -> Result<String, AppError> {
+  let connection = open_connection(&state.db_path)?;
+  export_prompts_as(&connection, &format, &options.unwrap_or_default())
+}
+
+fn export_prompts_as(
+  connection: &Connection,
+  format: &str,
+  options: &ExportOptions,
+) -> Result<String, AppError> {
+  let payload = build_export_payload(connection, options)?;
+
+  match format.trim().to_lowercase().as_str() {
+    "json" => serialize_export_json(&payload),
+    "yaml" | "yml" => serialize_export_yaml(&payload),
+    "csv" => Ok(serialize_export_csv(&payload)),
+    "markdown" | "md" => Ok(serialize_export_markdown(&payload)),
+    _ => Err(AppError::Validation(format!(
+      "不支持的导出格式：{format}（可选 json、markdown、csv、yaml）"
+    ))),
+  }
+}
+
+#[tauri::command]
+fn export_usage_logs_csv(
+  state: tauri::State<'_, AppState>,
+  prompt_id: Option<i64>,
+) -> Result<String, AppError> {
+  let connection = open_connection(&state.db_path)?;
+  export_usage_logs_csv_with(&connection, prompt_id)
+}
+
+fn export_usage_logs_csv_with(
+  connection: &Connection,
+  prompt_id: Option<i64>,
+) -> Result<String, AppError> {
+  if let Some(prompt_id) = prompt_id {
+    if fetch_prompt(connection, prompt_id)?.is_none() {
+      return Err(AppError::NotFound("指定的 Prompt 不存在".to_string()));
+    }
+  }
+
+  let mut statement = connection
+    .prepare(
       "
-      CREATE TABLE IF NOT EXISTS prompts (
-        id INTEGER PRIMARY KEY AUTOINCREMENT,
-        title TEXT NOT NULL,
-        content TEXT NOT NULL,
-        tags TEXT NOT NULL DEFAULT '[]',
-        is_favorite INTEGER NOT NULL DEFAULT 0,
-        score_avg REAL NOT NULL DEFAULT 0,
-        score_count INTEGER NOT NULL DEFAULT 0,
-        created_at TEXT NOT NULL,
-        updated_at TEXT NOT NULL
-      );
+      SELECT usage_logs.prompt_id, prompts.title, usage_logs.input_vars, usage_logs.output_text, usage_logs.rating, usage_logs.used_at
+      FROM usage_logs
+      JOIN prompts ON prompts.id = usage_logs.prompt_id
+      WHERE ?1 IS NULL OR usage_logs.prompt_id = ?1
+      ORDER BY usage_logs.used_at DESC, usage_logs.id DESC
+      ",
+    )
+    .map_err(AppError::from)?;
+  let rows = statement
+    .query_map(params![prompt_id], |row| {
+      Ok((
+        row.get::<_, i64>(0)?,
+        row.get::<_, String>(1)?,
+        row.get::<_, String>(2)?,
+        row.get::<_, String>(3)?,
+        row.get::<_, Option<i64>>(4)?,
+        row.get::<_, String>(5)?,
+      ))
+    })
+    .map_err(AppError::from)?;
 
-      CREATE TABLE IF NOT EXISTS prompt_versions (
-        id INTEGER PRIMARY KEY AUTOINCREMENT,
-        prompt_id INTEGER NOT NULL,
-        content TEXT NOT NULL,
-        change_note TEXT NOT NULL DEFAULT '',
-        created_at TEXT NOT NULL,
-        FOREIGN KEY(prompt_id) REFERENCES prompts(id) ON DELETE CASCADE
+  let mut output = String::from("prompt_id,prompt_title,input_vars,output_text,rating,used_at\r\n");
+  for row in rows {
+    let (prompt_id, prompt_title, input_vars, output_text, rating, used_at) =
+      row.map_err(AppError::from)?;
+    let fields = [
+      prompt_id.to_string(),
+      csv_escape(&prompt_title),
+      csv_escape(&input_vars),
+      csv_escape(&output_text),
+      rating.map(|rating| rating.to_string()).unwrap_or_default(),
+      csv_escape(&used_at),
+    ];
+    output.push_str(&fields.join(","));
+    output.push_str("\r\n");
+  }
+  Ok(output)
+}
+
+fn code_identifier(name: &str) -> String {
+  let mut identifier = name
+    .trim()
+    .chars()
+    .map(|character| {
+      if character.is_alphanumeric() || character == '_' {
+        character
+      } else {
+        '_'
+      }
+    })
+    .collect::<String>();
+  if identifier.is_empty() || identifier.starts_with(|character: char| character.is_ascii_digit()) {
+    identifier.insert(0, '_');
+  }
+  identifier
+}
+
+fn code_function_words(title: &str) -> Vec<String> {
+  title
+    .split(|character: char| !character.is_ascii_alphanumeric())
+    .filter(|word| !word.is_empty())
+    .map(str::to_ascii_lowercase)
+    .collect()
+}
+
+fn template_code_segments(content: &str, variable_names: &[String]) -> Vec<(bool, String)> {
+  let mut segments: Vec<(bool, String)> = Vec::new();
+  let mut literal = String::new();
+  let mut cursor = 0;
+
+  while let Some(character) = content[cursor..].chars().next() {
+    let rest = &content[cursor..];
+    if let Some(escape) = ["\\{\\{", "\\{{"]
+      .into_iter()
+      .find(|escape| rest.starts_with(escape))
+    {
+      literal.push_str("{{");
+      cursor += escape.len();
+      continue;
+    }
+
+    if let Some(after_open) = rest.strip_prefix("{{") {
+      if let Some(end) = after_open.find("}}") {
+        let name = after_open[..end].trim();
+        if variable_names.iter().any(|variable| variable == name) {
+          if !literal.is_empty() {
+            segments.push((false, std::mem::take(&mut literal)));
+          }
+          segments.push((true, code_identifier(name)));
+          cursor += end + 4;
+          continue;
+        }
+      }
+    }
+
+    literal.push(character);
+    cursor += character.len_utf8();
+  }
+
+  if !literal.is_empty() {
+    segments.push((false, literal));
+  }
+  segments
+}
+
+fn prompt_as_python(prompt: &PromptRecord, variable_names: &[String]) -> String {
+  let words = code_function_words(&prompt.title);
+  let function_name = if words.is_empty() {
+    "render_prompt".to_string()
+  } else {
+    code_identifier(&words.join("_"))
+  };
+
+  let parameters = variable_names
+    .iter()
+    .map(|name| {
+      let spec = prompt
+        .variables_schema
+        .iter()
+        .find(|spec| &spec.name == name);
+      let is_number = spec.is_some_and(|spec| spec.kind == VariableKind::Number);
+      let mut parameter = format!(
+        "{}: {}",
+        code_identifier(name),
+        if is_number { "float" } else { "str" }
       );
+      if let Some(default) = spec.and_then(|spec| spec.default.as_deref()) {
+        let literal = if is_number {
+          default.trim().to_string()
+        } else {
+          serde_json::to_string(default).unwrap_or_default()
+        };
+        parameter.push_str(&format!(" = {literal}"));
+      }
+      parameter
+    })
+    .collect::<Vec<_>>();
+  let signature = if parameters.is_empty() {
+    String::new()
+  } else {
+    format!("*, {}", parameters.join(", "))
+  };
 
-      CREATE TABLE IF NOT EXISTS usage_logs (
-        id INTEGER PRIMARY KEY AUTOINCREMENT,
-        prompt_id INTEGER NOT NULL,
-        input_vars TEXT NOT NULL DEFAULT '{}',
-        output_text TEXT NOT NULL,
-        rating INTEGER,
-        used_at TEXT NOT NULL,
-        FOREIGN KEY(prompt_id) REFERENCES prompts(id) ON DELETE CASCADE
+  let mut body = String::new();
+  for (is_variable, text) in template_code_segments(&prompt.content, variable_names) {
+    if is_variable {
+      body.push_str(&format!("{{{text}}}"));
+    } else {
+      body.push_str(
+        &text
+          .replace('\\', "\\\\")
+          .replace('{', "{{")
+          .replace('}', "}}")
+          .replace("\"\"\"", "\\\"\\\"\\\""),
       );
+    }
+  }
+  if body.ends_with('"') {
+    body.insert(body.len() - 1, '\\');
+  }
 
-      CREATE TABLE IF NOT EXISTS app_settings (
-        key TEXT PRIMARY KEY,
-        value TEXT NOT NULL,
-        updated_at TEXT NOT NULL
+  format!(
+    "# {}\ndef {function_name}({signature}) -> str:\n    return f\"\"\"{body}\"\"\"\n",
+    prompt.title.replace(['\r', '\n'], " ")
+  )
+}
+
+fn prompt_as_typescript(prompt: &PromptRecord, variable_names: &[String]) -> String {
+  let words = code_function_words(&prompt.title);
+  let function_name = if words.is_empty() {
+    "renderPrompt".to_string()
+  } else {
+    let camel_case = words
+      .iter()
+      .enumerate()
+      .map(|(index, word)| {
+        if index == 0 {
+          word.clone()
+        } else {
+          let mut characters = word.chars();
+          characters
+            .next()
+            .map(|first| first.to_ascii_uppercase().to_string() + characters.as_str())
+            .unwrap_or_default()
+        }
+      })
+      .collect::<String>();
+    code_identifier(&camel_case)
+  };
+
+  let mut fields = String::new();
+  let mut bindings = Vec::new();
+  for name in variable_names {
+    let identifier = code_identifier(name);
+    let spec = prompt
+      .variables_schema
+      .iter()
+      .find(|spec| &spec.name == name);
+    let is_number = spec.is_some_and(|spec| spec.kind == VariableKind::Number);
+    let default = spec.and_then(|spec| spec.default.as_deref());
+    fields.push_str(&format!(
+      "  {identifier}{}: {};\n",
+      if default.is_some() { "?" } else { "" },
+      if is_number { "number" } else { "string" }
+    ));
+    bindings.push(match default {
+      Some(default) if is_number => format!("{identifier} = {}", default.trim()),
+      Some(default) => format!(
+        "{identifier} = {}",
+        serde_json::to_string(default).unwrap_or_default()
+      ),
+      None => identifier,
+    });
+  }
+
+  let mut body = String::new();
+  for (is_variable, text) in template_code_segments(&prompt.content, variable_names) {
+    if is_variable {
+      body.push_str(&format!("${{{text}}}"));
+    } else {
+      body.push_str(
+        &text
+          .replace('\\', "\\\\")
+          .replace('`', "\\`")
+          .replace("${", "\\${"),
       );
+    }
+  }
 
-      CREATE INDEX IF NOT EXISTS idx_prompts_updated_at ON prompts(updated_at);
-      CREATE INDEX IF NOT EXISTS idx_prompt_versions_prompt_id ON prompt_versions(prompt_id);
-      CREATE INDEX IF NOT EXISTS idx_usage_logs_prompt_id ON usage_logs(prompt_id);
-      ",
-    )
-    .map_err(|error| error.to_string())?;
+  format!(
+    "// {title}\nexport function {function_name}(params: {{\n{fields}}}): string {{\n  const {{ {} }} = params;\n  return `{body}`;\n}}\n",
+    bindings.join(", "),
+    title = prompt.title.replace(['\r', '\n'], " ")
+  )
+}
 
-  connection
-    .execute(
-      "
-      INSERT INTO app_settings (key, value, updated_at)
-      VALUES (?1, ?2, ?3)
-      ON CONFLICT(key) DO NOTHING
-      ",
-      params![
-        GLOBAL_SHORTCUT_SETTING_KEY,
-        DEFAULT_GLOBAL_SHORTCUT,
-        now_iso()
-      ],
-    )
-    .map_err(|error| error.to_string())?;
+#[tauri::command]
+fn export_prompt_as_template(
+  state: tauri::State<'_, AppState>,
+  id: i64,
+  lang: String,
+) -> Result<String, AppError> {
+  let connection = open_connection(&state.db_path)?;
+  let prompt = fetch_prompt(&connection, id)?
+    .ok_or_else(|| AppError::NotFound("指定的 Prompt 不存在".to_string()))?;
+  let variable_names = extract_variable_names(&prompt.content);
 
-  Ok(())
+  match lang.trim().to_lowercase().as_str() {
+    "python" | "py" => Ok(prompt_as_python(&prompt, &variable_names)),
+    "typescript" | "ts" => Ok(prompt_as_typescript(&prompt, &variable_names)),
+    _ => Err(AppError::Validation(format!(
+      "不支持的模板语言：{lang}（可选 python、typescript）"
+    ))),
+  }
 }
 
-fn normalize_shortcut(shortcut: &str) -> Result<String, String> {
-  let normalized = shortcut.trim().to_string();
-  if normalized.is_empty() {
-    return Err("全局快捷键不能为空".to_string());
+fn sanitize_file_stem(title: &str) -> String {
+  let sanitized = title
+    .chars()
+    .map(|character| {
+      if character.is_control() || "/\\:*?\"<>|".contains(character) {
+        '_'
+      } else {
+        character
+      }
+    })
+    .take(MAX_FILE_STEM_CHARS)
+    .collect::<String>();
+  let trimmed = sanitized.trim().trim_matches('.');
+  if trimmed.is_empty() {
+    "prompt".to_string()
+  } else {
+    trimmed.to_string()
   }
-  Ok(normalized)
 }
 
-fn read_global_shortcut_setting(connection: &Connection) -> Result<String, String> {
-  let stored_value = connection
-    .query_row(
-      "SELECT value FROM app_settings WHERE key = ?1 LIMIT 1",
-      params![GLOBAL_SHORTCUT_SETTING_KEY],
-      |row| row.get::<_, String>(0),
+#[tauri::command]
+fn export_prompts_zip(state: tauri::State<'_, AppState>, path: String) -> Result<i64, AppError> {
+  let connection = open_connection(&state.db_path)?;
+  let payload = build_export_payload(&connection, &ExportOptions::default())?;
+  let file =
+    fs::File::create(&path).map_err(|error| AppError::Io(format!("创建导出文件失败: {error}")))?;
+  let zip_error =
+    |error: zip::result::ZipError| AppError::Serialization(format!("写入压缩包失败: {error}"));
+  let write_error = |error: std::io::Error| AppError::Io(format!("写入压缩包失败: {error}"));
+
+  let mut archive = zip::ZipWriter::new(file);
+  let file_options =
+    zip::write::SimpleFileOptions::default().compression_method(zip::CompressionMethod::Deflated);
+  let mut manifest = ExportManifest {
+    exported_at: payload.exported_at.clone(),
+    prompts: Vec::new(),
+  };
+  for prompt in &payload.prompts {
+    let file_name = format!("{}-{}.md", sanitize_file_stem(&prompt.title), prompt.id);
+    let mut markdown = String::new();
+    push_prompt_markdown(&mut markdown, prompt, "#", true, None);
+    archive
+      .start_file(file_name.as_str(), file_options)
+      .map_err(zip_error)?;
+    archive
+      .write_all(markdown.as_bytes())
+      .map_err(write_error)?;
+    manifest.prompts.push(ExportManifestEntry {
+      id: prompt.id,
+      title: prompt.title.clone(),
+      file: file_name,
+    });
+  }
+
+  archive
+    .start_file("manifest.json", file_options)
+    .map_err(zip_error)?;
+  archive
+    .write_all(
+      serde_json::to_string_pretty(&manifest)
+        .map_err(AppError::from)?
+        .as_bytes(),
     )
-    .optional()
-    .map_err(|error| error.to_string())?;
+    .map_err(write_error)?;
+  archive.finish().map_err(zip_error)?;
+  Ok(manifest.prompts.len() as i64)
+}
 
-  let normalized = stored_value
-    .as_deref()
-    .and_then(|value| normalize_shortcut(value).ok())
-    .unwrap_or_else(|| DEFAULT_GLOBAL_SHORTCUT.to_string());
+fn prompt_frontmatter_markdown(
+  prompt: &ExportPromptItem,
+  timestamps: Option<(String, String)>,
+) -> Result<String, AppError> {
+  let (created, updated) = timestamps.unzip();
+  let frontmatter = PromptFrontmatter {
+    title: prompt.title.clone(),
+    tags: prompt.tags.clone(),
+    favorite: prompt.is_favorite,
+    created,
+    updated,
+    score: (prompt.score_count > 0).then_some(prompt.score_avg),
+  };
+  let yaml = serde_yaml::to_string(&frontmatter).map_err(AppError::from)?;
+  Ok(format!("---\n{yaml}---\n\n{}", prompt.content))
+}
+
+#[tauri::command]
+fn export_prompts_to_directory(
+  state: tauri::State<'_, AppState>,
+  dir: String,
+  options: Option<ExportOptions>,
+) -> Result<ExportManifest, AppError> {
+  let target_dir = PathBuf::from(dir.trim());
+  if target_dir.as_os_str().is_empty() {
+    return Err(AppError::Validation("导出目录不能为空".to_string()));
+  }
+  fs::create_dir_all(&target_dir)
+    .map_err(|error| AppError::Io(format!("创建导出目录失败: {error}")))?;
+
+  let options = options.unwrap_or_default();
+  let connection = open_connection(&state.db_path)?;
+  let payload = build_export_payload(&connection, &options)?;
+  let mut manifest = ExportManifest {
+    exported_at: payload.exported_at.clone(),
+    prompts: Vec::new(),
+  };
+  for prompt in &payload.prompts {
+    let timestamps = if options.anonymize {
+      None
+    } else {
+      connection
+        .query_row(
+          "SELECT created_at, updated_at FROM prompts WHERE id = ?1",
+          params![prompt.id],
+          |row| Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?)),
+        )
+        .optional()
+        .map_err(AppError::from)?
+    };
+    let file_name = format!("{}-{}.md", sanitize_file_stem(&prompt.title), prompt.id);
+    let markdown = prompt_frontmatter_markdown(prompt, timestamps)?;
+    fs::write(target_dir.join(&file_name), markdown)
+      .map_err(|error| AppError::Io(format!("写入导出文件 {file_name} 失败: {error}")))?;
+    manifest.prompts.push(ExportManifestEntry {
+      id: prompt.id,
+      title: prompt.title.clone(),
+      file: file_name,
+    });
+  }
+
+  Ok(manifest)
+}
+
+fn write_export_temp_file(
+  temp_path: &Path,
+  path: &Path,
+  payload: &ExportPayload,
+) -> Result<(), AppError> {
+  let file = fs::File::create(temp_path)
+    .map_err(|error| AppError::Io(format!("创建导出文件失败: {error}")))?;
+  let mut writer = BufWriter::new(file);
+  if payload.stable {
+    serde_json::to_writer_pretty(&mut writer, &sorted_export_value(payload)?)
+      .map_err(AppError::from)?;
+    writer
+      .write_all(b"\n")
+      .map_err(|error| AppError::Io(format!("写入导出文件失败: {error}")))?;
+  } else {
+    serde_json::to_writer_pretty(&mut writer, payload).map_err(AppError::from)?;
+  }
+  writer
+    .flush()
+    .map_err(|error| AppError::Io(format!("写入导出文件失败: {error}")))?;
+  fs::rename(temp_path, path).map_err(|error| AppError::Io(format!("替换导出文件失败: {error}")))
+}
+
+fn write_export_file(path: &Path, payload: &ExportPayload) -> Result<u64, AppError> {
+  let file_name = path
+    .file_name()
+    .ok_or_else(|| AppError::Validation("导出路径必须包含文件名".to_string()))?;
+  if let Some(parent) = path
+    .parent()
+    .filter(|parent| !parent.as_os_str().is_empty())
+  {
+    fs::create_dir_all(parent)
+      .map_err(|error| AppError::Io(format!("创建导出目录失败: {error}")))?;
+  }
+
+  let mut temp_name = file_name.to_os_string();
+  temp_name.push(".tmp");
+  let temp_path = path.with_file_name(temp_name);
+  if let Err(error) = write_export_temp_file(&temp_path, path, payload) {
+    let _ = fs::remove_file(&temp_path);
+    return Err(error);
+  }
+
+  fs::metadata(path)
+    .map(|metadata| metadata.len())
+    .map_err(|error| AppError::Io(format!("读取导出文件信息失败: {error}")))
+}
+
+#[tauri::command]
+fn export_prompts_to_file(
+  state: tauri::State<'_, AppState>,
+  path: String,
+  options: Option<ExportOptions>,
+) -> Result<ExportFileSummary, AppError> {
+  let target_path = PathBuf::from(path.trim());
+  if target_path.as_os_str().is_empty() {
+    return Err(AppError::Validation("导出路径不能为空".to_string()));
+  }
+
+  let connection = open_connection(&state.db_path)?;
+  let payload = build_export_payload(&connection, &options.unwrap_or_default())?;
+  let size_bytes = write_export_file(&target_path, &payload)?;
+  Ok(ExportFileSummary {
+    prompt_count: payload.prompts.len() as i64,
+    size_bytes,
+    path: target_path.display().to_string(),
+  })
+}
+
+#[tauri::command]
+fn export_prompts_json(
+  state: tauri::State<'_, AppState>,
+  options: Option<ExportOptions>,
+  since: Option<String>,
+) -> Result<String, AppError> {
+  let mut options = options.unwrap_or_default();
+  if since.is_some() {
+    options.since = since;
+  }
+  export_prompts(state, "json".to_string(), Some(options))
+}
+
+#[tauri::command]
+fn export_prompts_yaml(
+  state: tauri::State<'_, AppState>,
+  options: Option<ExportOptions>,
+) -> Result<String, AppError> {
+  export_prompts(state, "yaml".to_string(), options)
+}
 
-  Ok(normalized)
+#[tauri::command]
+fn export_collection_json(
+  state: tauri::State<'_, AppState>,
+  collection_id: i64,
+  include_versions: Option<bool>,
+) -> Result<String, AppError> {
+  let options = ExportOptions {
+    include_versions: include_versions.unwrap_or(true),
+    collection_id: Some(collection_id),
+    ..ExportOptions::default()
+  };
+  export_prompts(state, "json".to_string(), Some(options))
 }
 
-fn persist_global_shortcut_setting(connection: &Connection, shortcut: &str) -> Result<(), String> {
-  connection
-    .execute(
-      "
-      INSERT INTO app_settings (key, value, updated_at)
-      VALUES (?1, ?2, ?3)
-      ON CONFLICT(key) DO UPDATE SET value = excluded.value, updated_at = excluded.updated_at
-      ",
-      params![GLOBAL_SHORTCUT_SETTING_KEY, shortcut, now_iso()],
-    )
-    .map_err(|error| error.to_string())?;
+fn import_payload_items(payload: ImportPayload) -> ParsedImport {
+  match payload {
+    ImportPayload::Wrapped {
+      prompts,
+      collections,
+      snippets,
+      tombstones,
+      ..
+    } => ParsedImport {
+      items: prompts,
+      collections,
+      snippets,
+      tombstones,
+    },
+    ImportPayload::Flat(prompts) => ParsedImport {
+      items: prompts,
+      ..ParsedImport::default()
+    },
+  }
+}
 
-  Ok(())
+fn import_schema_version(value: &Value) -> Result<u64, AppError> {
+  let Some(fields) = value.as_object() else {
+    return Ok(LEGACY_EXPORT_SCHEMA_VERSION);
+  };
+  check_import_schema_version(fields.get("schemaVersion"), fields.get("appVersion"))
 }
 
-fn apply_global_shortcut<R: tauri::Runtime>(
-  app_handle: &AppHandle<R>,
-  shortcut: &str,
-) -> Result<(), String> {
-  let shortcut_manager = app_handle.global_shortcut();
-  shortcut_manager
-    .unregister_all()
-    .map_err(|error| error.to_string())?;
-  shortcut_manager
-    .register(shortcut)
-    .map_err(|error| error.to_string())?;
+fn check_import_schema_version(
+  schema_version: Option<&Value>,
+  app_version: Option<&Value>,
+) -> Result<u64, AppError> {
+  let schema_version = match schema_version {
+    None | Some(Value::Null) => return Ok(LEGACY_EXPORT_SCHEMA_VERSION),
+    Some(version) => version
+      .as_u64()
+      .filter(|version| *version > 0)
+      .ok_or_else(|| AppError::Validation("schemaVersion 必须是正整数".to_string()))?,
+  };
 
-  Ok(())
+  if schema_version > EXPORT_SCHEMA_VERSION {
+    let exported_by = app_version
+      .and_then(Value::as_str)
+      .map(|app_version| format!("（由 v{app_version} 导出）"))
+      .unwrap_or_default();
+    return Err(AppError::Validation(format!(
+      "导入文件的格式版本为 {schema_version}{exported_by}，当前应用 v{} 最高支持版本 {EXPORT_SCHEMA_VERSION}，请升级应用后再导入",
+      env!("CARGO_PKG_VERSION")
+    )));
+  }
+  Ok(schema_version)
 }
 
-fn row_to_prompt(row: &rusqlite::Row<'_>) -> rusqlite::Result<PromptRecord> {
-  let tags_raw: String = row.get(3)?;
-  Ok(PromptRecord {
-    id: row.get(0)?,
-    title: row.get(1)?,
-    content: row.get(2)?,
-    tags: decode_tags(&tags_raw),
-    is_favorite: row.get::<_, i64>(4)? == 1,
-    score_avg: row.get(5)?,
-    score_count: row.get(6)?,
-    created_at: row.get(7)?,
-    updated_at: row.get(8)?,
-  })
+fn upgrade_import_value(value: Value, schema_version: u64) -> Value {
+  match (schema_version, value) {
+    (LEGACY_EXPORT_SCHEMA_VERSION, Value::Array(prompts)) => {
+      serde_json::json!({ "prompts": prompts })
+    }
+    (_, value) => value,
+  }
 }
 
-fn fetch_prompt(connection: &Connection, prompt_id: i64) -> Result<Option<PromptRecord>, String> {
-  let mut statement = connection
-    .prepare(
-      "
-      SELECT id, title, content, tags, is_favorite, score_avg, score_count, created_at, updated_at
-      FROM prompts
-      WHERE id = ?1
-      LIMIT 1
-      ",
-    )
-    .map_err(|error| error.to_string())?;
+fn import_payload_from_value(value: Value) -> Result<ParsedImport, AppError> {
+  let schema_version = import_schema_version(&value)?;
+  let payload: ImportPayload = serde_json::from_value(upgrade_import_value(value, schema_version))
+    .map_err(|error| {
+      AppError::Serialization(format!(
+        "导入数据格式错误（格式版本 {schema_version}）: {error}"
+      ))
+    })?;
+  Ok(import_payload_items(payload))
+}
 
-  statement
-    .query_row(params![prompt_id], row_to_prompt)
-    .optional()
-    .map_err(|error| error.to_string())
+fn parse_json_import(data: &str) -> Result<ParsedImport, AppError> {
+  let value: Value = serde_json::from_str(data)
+    .map_err(|error| AppError::Serialization(format!("JSON 解析失败: {error}")))?;
+  import_payload_from_value(value)
 }
 
-fn fetch_prompt_versions(
-  connection: &Connection,
-  prompt_id: i64,
-) -> Result<Vec<PromptVersionRecord>, String> {
-  let mut statement = connection
-    .prepare(
-      "
-      SELECT id, prompt_id, content, change_note, created_at
-      FROM prompt_versions
-      WHERE prompt_id = ?1
-      ORDER BY created_at DESC, id DESC
-      ",
-    )
-    .map_err(|error| error.to_string())?;
+fn parse_awesome_import(data: &str) -> Result<ParsedImport, AppError> {
+  let payload: AwesomePayload = serde_json::from_str(data)
+    .map_err(|error| AppError::Serialization(format!("JSON 解析失败: {error}")))?;
+  let prompts = match payload {
+    AwesomePayload::Wrapped { prompts } | AwesomePayload::Flat(prompts) => prompts,
+  };
 
-  let rows = statement
-    .query_map(params![prompt_id], |row| {
-      Ok(PromptVersionRecord {
-        id: row.get(0)?,
-        prompt_id: row.get(1)?,
-        content: row.get(2)?,
-        change_note: row.get(3)?,
-        created_at: row.get(4)?,
+  Ok(ParsedImport {
+    items: prompts
+      .into_iter()
+      .map(|prompt| ImportPromptItem {
+        title: prompt.act,
+        content: prompt.prompt,
+        tags: Some(vec![AWESOME_IMPORT_TAG.to_string()]),
+        ..ImportPromptItem::default()
       })
-    })
-    .map_err(|error| error.to_string())?;
+      .collect(),
+    ..ParsedImport::default()
+  })
+}
 
-  let mut versions = Vec::new();
-  for row in rows {
-    versions.push(row.map_err(|error| error.to_string())?);
+fn parse_json_or_yaml_import(data: &str) -> Result<ParsedImport, AppError> {
+  let content = data.trim_start_matches('\u{feff}');
+  match parse_json_import(content) {
+    Ok(parsed_import) => Ok(parsed_import),
+    Err(json_error) if content.trim_start().starts_with(['{', '[']) => Err(json_error),
+    Err(_) => parse_yaml_import(content),
   }
-  Ok(versions)
 }
 
-fn insert_prompt_version(
-  connection: &Connection,
-  prompt_id: i64,
-  content: &str,
-  change_note: &str,
-  created_at: &str,
-) -> Result<(), String> {
-  connection
-    .execute(
-      "
-      INSERT INTO prompt_versions (prompt_id, content, change_note, created_at)
-      VALUES (?1, ?2, ?3, ?4)
-      ",
-      params![prompt_id, content, change_note, created_at],
-    )
-    .map_err(|error| error.to_string())?;
-  Ok(())
+fn parse_yaml_import(data: &str) -> Result<ParsedImport, AppError> {
+  let value: Value = serde_yaml::from_str(data)
+    .map_err(|error| AppError::Serialization(format!("YAML 解析失败: {error}")))?;
+  import_payload_from_value(value)
 }
 
-#[tauri::command]
-fn get_global_shortcut(state: tauri::State<'_, AppState>) -> Result<String, String> {
-  let connection = open_connection(&state.db_path)?;
-  read_global_shortcut_setting(&connection)
-}
+fn parse_csv_records(data: &str) -> Result<Vec<Vec<String>>, AppError> {
+  let mut records = Vec::new();
+  let mut record = Vec::new();
+  let mut field = String::new();
+  let mut in_quotes = false;
+  let mut characters = data.chars().peekable();
 
-#[tauri::command]
-fn update_global_shortcut(
-  app: tauri::AppHandle,
-  state: tauri::State<'_, AppState>,
-  shortcut: String,
-) -> Result<String, String> {
-  let normalized_shortcut = normalize_shortcut(&shortcut)?;
-  let connection = open_connection(&state.db_path)?;
-  let previous_shortcut = read_global_shortcut_setting(&connection)?;
+  while let Some(character) = characters.next() {
+    if in_quotes {
+      if character == '"' {
+        if characters.peek() == Some(&'"') {
+          field.push('"');
+          characters.next();
+        } else {
+          in_quotes = false;
+        }
+      } else {
+        field.push(character);
+      }
+      continue;
+    }
 
-  if previous_shortcut == normalized_shortcut {
-    return Ok(previous_shortcut);
+    match character {
+      '"' => in_quotes = true,
+      ',' => record.push(std::mem::take(&mut field)),
+      '\r' => {}
+      '\n' => {
+        record.push(std::mem::take(&mut field));
+        records.push(std::mem::take(&mut record));
+      }
+      other => field.push(other),
+    }
   }
 
-  if let Err(error) = apply_global_shortcut(&app, &normalized_shortcut) {
-    let _ = apply_global_shortcut(&app, &previous_shortcut);
-    return Err(format!("全局快捷键注册失败：{error}"));
+  if in_quotes {
+    return Err(AppError::Serialization(
+      "CSV 解析失败：存在未闭合的引号".to_string(),
+    ));
+  }
+  if !field.is_empty() || !record.is_empty() {
+    record.push(field);
+    records.push(record);
   }
 
-  persist_global_shortcut_setting(&connection, &normalized_shortcut)?;
-  log::info!("global shortcut updated: {normalized_shortcut}");
-
-  Ok(normalized_shortcut)
+  records.retain(|record| record.iter().any(|value| !value.trim().is_empty()));
+  Ok(records)
 }
 
-#[tauri::command]
-fn list_prompts(
-  state: tauri::State<'_, AppState>,
-  search: Option<String>,
-  tag: Option<String>,
-  sort_by: Option<String>,
-) -> Result<Vec<PromptRecord>, String> {
-  let connection = open_connection(&state.db_path)?;
-  let mut sql = String::from(
-    "
-    SELECT id, title, content, tags, is_favorite, score_avg, score_count, created_at, updated_at
-    FROM prompts
-    WHERE 1 = 1
-    ",
-  );
-  let mut query_params: Vec<String> = Vec::new();
+fn parse_csv_import(data: &str) -> Result<ParsedImport, AppError> {
+  let mut records = parse_csv_records(data)?.into_iter();
+  let Some(header) = records.next() else {
+    return Ok(ParsedImport::default());
+  };
+  let header = header
+    .iter()
+    .map(|name| name.trim().to_lowercase())
+    .collect::<Vec<_>>();
+  let column = |name: &str| header.iter().position(|candidate| candidate == name);
+  let (Some(title_index), Some(content_index)) = (column("title"), column("content")) else {
+    return Err(AppError::Serialization(
+      "CSV 解析失败：缺少 title 或 content 列".to_string(),
+    ));
+  };
 
-  if let Some(search_term) = search
-    .map(|value| value.trim().to_string())
-    .filter(|value| !value.is_empty())
-  {
-    sql.push_str(" AND (title LIKE ? OR content LIKE ? OR tags LIKE ?)");
-    let pattern = format!("%{search_term}%");
-    query_params.push(pattern.clone());
-    query_params.push(pattern.clone());
-    query_params.push(pattern);
+  let optional_columns = [
+    column("description"),
+    column("tags"),
+    column("is_favorite"),
+    column("score_avg"),
+    column("score_count"),
+    column("source_url"),
+    column("model"),
+    column("language"),
+    column("icon"),
+    column("category"),
+    column("author"),
+  ];
+
+  let mut items = Vec::new();
+  for record in records {
+    let cell = |index: Option<usize>| {
+      index
+        .and_then(|position| record.get(position))
+        .map(|value| value.trim().to_string())
+        .filter(|value| !value.is_empty())
+    };
+    let [description, tags, is_favorite, score_avg, score_count, source_url, model, language, icon, category, author] =
+      optional_columns.map(cell);
+
+    items.push(ImportPromptItem {
+      title: record.get(title_index).cloned().unwrap_or_default(),
+      description,
+      content: record.get(content_index).cloned().unwrap_or_default(),
+      tags: tags.map(|value| value.split(',').map(|tag| tag.to_string()).collect()),
+      is_favorite: is_favorite
+        .map(|value| matches!(value.to_lowercase().as_str(), "true" | "1" | "yes")),
+      score_avg: score_avg.and_then(|value| value.parse::<f64>().ok()),
+      score_count: score_count.and_then(|value| value.parse::<i64>().ok()),
+      source_url,
+      author,
+      model,
+      language,
+      icon,
+      category,
+      ..ImportPromptItem::default()
+    });
   }
 
-  if let Some(tag_filter) = tag
-    .map(|value| value.trim().to_string())
-    .filter(|value| !value.is_empty())
+  Ok(ParsedImport {
+    items,
+    ..ParsedImport::default()
+  })
+}
+
+fn detect_import_format(data: &str) -> Result<&'static str, AppError> {
+  let trimmed = data.trim_start_matches('\u{feff}').trim_start();
+  if trimmed.starts_with('{') || trimmed.starts_with('[') {
+    return Ok("json");
+  }
+
+  let first_line = trimmed.lines().next().unwrap_or_default().trim();
+  let header_columns = first_line
+    .split(',')
+    .map(|name| name.trim().trim_matches('"').to_lowercase())
+    .collect::<Vec<_>>();
+  if header_columns.len() > 1
+    && header_columns.iter().any(|name| name == "title")
+    && header_columns.iter().any(|name| name == "content")
   {
-    sql.push_str(" AND tags LIKE ?");
-    query_params.push(format!("%\"{tag_filter}\"%"));
+    return Ok("csv");
   }
 
-  let sort_clause = match sort_by.as_deref() {
-    Some("score") => "score_avg DESC, updated_at DESC",
-    Some("created") => "created_at DESC",
-    _ => "updated_at DESC",
-  };
-  sql.push_str(" ORDER BY ");
-  sql.push_str(sort_clause);
+  let mut yaml_lines = trimmed.lines().map(str::trim_end).filter(|line| {
+    let line = line.trim_start();
+    !line.is_empty() && !line.starts_with('#') && line != "---"
+  });
+  let starts_with_prompt_list = yaml_lines
+    .clone()
+    .next()
+    .and_then(|line| line.strip_prefix("- "))
+    .and_then(|item| item.split_once(':'))
+    .is_some_and(|(key, _)| matches!(key.trim(), "title" | "content" | "uuid"));
+  let has_prompts_key = yaml_lines.any(|line| line == "prompts:" || line.starts_with("prompts: "));
+  if starts_with_prompt_list || has_prompts_key {
+    return Ok("yaml");
+  }
 
-  let mut statement = connection.prepare(&sql).map_err(|error| error.to_string())?;
-  let rows = statement
-    .query_map(params_from_iter(query_params.iter()), row_to_prompt)
-    .map_err(|error| error.to_string())?;
+  Err(AppError::Validation(
+    "无法识别导入格式：JSON 需以 { 或 [ 开头，YAML 需包含顶层 prompts: 或以 - title: 列表开头，CSV 需包含 title,content 表头"
+      .to_string(),
+  ))
+}
 
-  let mut prompts = Vec::new();
-  for row in rows {
-    prompts.push(row.map_err(|error| error.to_string())?);
+fn normalize_import_timestamp(
+  value: Option<String>,
+  label: &str,
+  warnings: &mut Vec<String>,
+) -> Option<String> {
+  let value = value.filter(|value| !value.trim().is_empty())?;
+  let parsed = DateTime::parse_from_rfc3339(value.trim())
+    .ok()
+    .map(|timestamp| timestamp.with_timezone(&Utc).to_rfc3339());
+  if parsed.is_none() {
+    warnings.push(format!("{label} 无法解析（{value}），已使用默认时间"));
   }
-  Ok(prompts)
+  parsed
 }
 
-#[tauri::command]
-fn list_tags(state: tauri::State<'_, AppState>) -> Result<Vec<TagInfo>, String> {
-  let connection = open_connection(&state.db_path)?;
+fn normalize_uuid(value: Option<String>) -> Option<String> {
+  value
+    .map(|value| value.trim().to_lowercase())
+    .filter(|value| !value.is_empty())
+}
+
+fn find_prompt_by_uuid(connection: &Connection, uuid: &str) -> Result<Option<i64>, AppError> {
+  connection
+    .query_row(
+      "SELECT id FROM prompts WHERE uuid = ?1",
+      params![uuid],
+      |row| row.get(0),
+    )
+    .optional()
+    .map_err(AppError::from)
+}
+
+fn find_existing_prompt(
+  connection: &Connection,
+  title: &str,
+  content: Option<&str>,
+) -> Result<Option<i64>, AppError> {
   let mut statement = connection
-    .prepare("SELECT tags FROM prompts")
-    .map_err(|error| error.to_string())?;
+    .prepare(
+      "SELECT id, content, content_compressed FROM prompts WHERE title = ?1 COLLATE NOCASE ORDER BY id ASC",
+    )
+    .map_err(AppError::from)?;
   let rows = statement
-    .query_map([], |row| row.get::<_, String>(0))
-    .map_err(|error| error.to_string())?;
+    .query_map(params![title], |row| {
+      Ok((row.get::<_, i64>(0)?, read_prompt_content(row, 1, 2)?))
+    })
+    .map_err(AppError::from)?;
 
-  let mut counts: BTreeMap<String, i64> = BTreeMap::new();
+  let expected_hash = content.map(|content| content_hash(content.trim()));
   for row in rows {
-    let tags = decode_tags(&row.map_err(|error| error.to_string())?);
-    for tag_name in tags {
-      *counts.entry(tag_name).or_insert(0) += 1;
+    let (prompt_id, existing_content) = row.map_err(AppError::from)?;
+    match &expected_hash {
+      Some(expected_hash) if *expected_hash != content_hash(existing_content.trim()) => {}
+      _ => return Ok(Some(prompt_id)),
     }
   }
+  Ok(None)
+}
 
-  let mut tag_items: Vec<TagInfo> = counts
-    .into_iter()
-    .map(|(name, count)| TagInfo { name, count })
-    .collect();
-  tag_items.sort_by(|left, right| {
-    right
-      .count
-      .cmp(&left.count)
-      .then_with(|| left.name.cmp(&right.name))
-  });
+fn preview_import_items(
+  connection: &Connection,
+  parsed_import: &ParsedImport,
+  options: &ImportOptions,
+) -> Result<ImportPreview, AppError> {
+  let normalize_content = read_content_normalization(connection)?;
+  let mut planned: Vec<(String, String)> = Vec::new();
+  let mut planned_uuids = HashSet::new();
+  let mut planned_titles = HashSet::new();
+  let mut preview = ImportPreview::default();
 
-  Ok(tag_items)
-}
+  for (item_index, item) in parsed_import.items.iter().enumerate() {
+    let content = if normalize_content {
+      normalize_prompt_content(&item.content)
+    } else {
+      item.content.clone()
+    };
+    let title = item.title.trim().to_string();
+    let label = if title.is_empty() {
+      format!("第 {} 条", item_index + 1)
+    } else {
+      format!("「{title}」")
+    };
 
-#[tauri::command]
-fn get_prompt(state: tauri::State<'_, AppState>, id: i64) -> Result<Option<PromptRecord>, String> {
-  let connection = open_connection(&state.db_path)?;
-  fetch_prompt(&connection, id)
-}
+    let invalid_reason = if title.is_empty() {
+      Some("缺少标题")
+    } else if content.trim().is_empty() {
+      Some("内容为空")
+    } else if content.len() > MAX_IMPORT_CONTENT_BYTES {
+      Some("内容过大")
+    } else {
+      None
+    };
+    let mut conflict = false;
+    let action = if let Some(reason) = invalid_reason {
+      preview.warnings.push(format!("{label}{reason}，将被忽略"));
+      ImportPreviewAction::Invalid
+    } else {
+      let empty_versions = item
+        .versions
+        .as_ref()
+        .map(|versions| {
+          versions
+            .iter()
+            .filter(|version| version.content.trim().is_empty())
+            .count()
+        })
+        .unwrap_or(0);
+      if empty_versions > 0 {
+        preview.warnings.push(format!(
+          "{label}有 {empty_versions} 个历史版本内容为空，将被忽略"
+        ));
+      }
 
-#[tauri::command]
-fn list_prompt_versions(
-  state: tauri::State<'_, AppState>,
-  prompt_id: i64,
-) -> Result<Vec<PromptVersionRecord>, String> {
-  let connection = open_connection(&state.db_path)?;
-  fetch_prompt_versions(&connection, prompt_id)
-}
+      let uuid = normalize_uuid(item.uuid.clone());
+      let matches_uuid = match &uuid {
+        Some(uuid) => {
+          planned_uuids.contains(uuid) || find_prompt_by_uuid(connection, uuid)?.is_some()
+        }
+        None => false,
+      };
+      if let Some(uuid) = uuid {
+        planned_uuids.insert(uuid);
+      }
+      conflict = !matches_uuid
+        && (!planned_titles.insert(title.to_lowercase())
+          || find_existing_prompt(connection, &title, None)?.is_some());
+      let key = (
+        title.to_lowercase(),
+        if options.match_content {
+          content_hash(content.trim())
+        } else {
+          String::new()
+        },
+      );
+      let matches_existing = !matches_uuid
+        && options.strategy != ImportStrategy::Duplicate
+        && (planned.contains(&key)
+          || find_existing_prompt(
+            connection,
+            &title,
+            options.match_content.then_some(content.as_str()),
+          )?
+          .is_some());
+      if !matches_existing {
+        planned.push(key);
+      }
 
-#[tauri::command]
-fn upsert_prompt(
-  state: tauri::State<'_, AppState>,
-  input: SavePromptInput,
-) -> Result<PromptRecord, String> {
-  let SavePromptInput {
-    id,
-    title,
-    content,
-    tags,
-    is_favorite,
-    change_note,
-  } = input;
+      match (options.strategy, matches_existing) {
+        _ if matches_uuid => ImportPreviewAction::Update,
+        (ImportStrategy::Skip, true) => ImportPreviewAction::Skip,
+        (ImportStrategy::Overwrite, true) => ImportPreviewAction::Update,
+        _ => {
+          if conflict {
+            preview
+              .warnings
+              .push(format!("{label}与已有 Prompt 标题重复，将创建同名副本"));
+          }
+          ImportPreviewAction::Create
+        }
+      }
+    };
 
-  let normalized_title = title.trim().to_string();
-  if normalized_title.is_empty() {
-    return Err("标题不能为空".to_string());
+    match action {
+      ImportPreviewAction::Create => preview.created += 1,
+      ImportPreviewAction::Update => preview.updated += 1,
+      ImportPreviewAction::Skip => preview.skipped += 1,
+      ImportPreviewAction::Invalid => preview.invalid += 1,
+    }
+    if conflict {
+      preview.conflicts += 1;
+    }
+    preview.items.push(ImportPreviewItem {
+      title,
+      action,
+      conflict,
+    });
   }
-  if content.trim().is_empty() {
-    return Err("Prompt 内容不能为空".to_string());
+  for tombstone in &parsed_import.tombstones {
+    if let Some(uuid) = normalize_uuid(Some(tombstone.uuid.clone())) {
+      if find_prompt_by_uuid(connection, &uuid)?.is_some() {
+        preview.deleted += 1;
+      }
+    }
   }
 
-  let normalized_tags = normalize_tags(tags);
-  let tags_json = encode_tags(&normalized_tags);
-  let note = change_note.unwrap_or_default().trim().to_string();
-  let timestamp = now_iso();
-  let connection = open_connection(&state.db_path)?;
+  Ok(preview)
+}
+
+fn import_prompt_items(
+  connection: &mut Connection,
+  parsed_import: ParsedImport,
+  options: &ImportOptions,
+  cancel_flag: &AtomicBool,
+  mut on_progress: impl FnMut(ImportProgress),
+) -> Result<ImportResult, AppError> {
+  let ParsedImport {
+    items,
+    collections,
+    snippets,
+    tombstones,
+  } = parsed_import;
+  let total = items.len();
+  let transaction = connection.transaction().map_err(AppError::from)?;
+
+  let mut imported_count = 0_i64;
+  let mut skipped_items = Vec::new();
+  let mut updated_count = 0_i64;
+  let mut imported_ids: HashMap<usize, i64> = HashMap::new();
+  let compression_threshold = read_content_compression_threshold(&transaction)?;
+  let normalize_content = read_content_normalization(&transaction)?;
+  let output_text_limit = read_output_text_limit(&transaction)?;
+  let max_tag_count = read_max_tag_count(&transaction)?;
+  let mut warnings = Vec::new();
+
+  for (item_index, item) in items.into_iter().enumerate() {
+    if cancel_flag.load(Ordering::Relaxed) {
+      log::info!("import cancelled after {item_index}/{total} items, rolling back");
+      transaction.rollback().map_err(AppError::from)?;
+      return Ok(ImportResult {
+        cancelled: true,
+        ..ImportResult::default()
+      });
+    }
+    if item_index % IMPORT_PROGRESS_INTERVAL == 0 {
+      on_progress(ImportProgress {
+        processed: item_index,
+        total,
+      });
+    }
+
+    let ImportPromptItem {
+      uuid,
+      title,
+      description,
+      content,
+      tags,
+      is_favorite,
+      score_avg,
+      score_count,
+      source_url,
+      author,
+      model,
+      language,
+      params: prompt_params,
+      icon,
+      category,
+      meta,
+      example_output,
+      variables_schema,
+      variable_presets,
+      test_cases,
+      usage_logs,
+      created_at,
+      updated_at,
+      versions,
+    } = item;
+
+    let content = if normalize_content {
+      normalize_prompt_content(&content)
+    } else {
+      content
+    };
+    let normalized_title = title.trim().to_string();
+    let skip_reason = if normalized_title.is_empty() {
+      Some("empty_title")
+    } else if content.trim().is_empty() {
+      Some("empty_content")
+    } else if content.len() > MAX_IMPORT_CONTENT_BYTES {
+      Some("oversized")
+    } else {
+      None
+    };
+    if let Some(reason) = skip_reason {
+      skipped_items.push(SkippedItem {
+        index: item_index,
+        title: if normalized_title.is_empty() {
+          "<untitled>".to_string()
+        } else {
+          normalized_title
+        },
+        reason,
+      });
+      continue;
+    }
+    let uuid = normalize_uuid(uuid);
+    let uuid_match = match &uuid {
+      Some(uuid) => find_prompt_by_uuid(&transaction, uuid)?,
+      None => None,
+    };
+    let existing_id = if uuid_match.is_some() {
+      uuid_match
+    } else if options.strategy == ImportStrategy::Duplicate {
+      None
+    } else {
+      find_existing_prompt(
+        &transaction,
+        &normalized_title,
+        options.match_content.then_some(content.as_str()),
+      )?
+    };
+    if let (ImportStrategy::Skip, Some(existing_id), None) =
+      (options.strategy, existing_id, uuid_match)
+    {
+      imported_ids.insert(item_index, existing_id);
+      skipped_items.push(SkippedItem {
+        index: item_index,
+        title: normalized_title,
+        reason: "duplicate_title",
+      });
+      continue;
+    }
+
+    let normalized_description = description
+      .map(|value| {
+        value
+          .trim()
+          .chars()
+          .take(MAX_DESCRIPTION_CHARS)
+          .collect::<String>()
+      })
+      .unwrap_or_default();
+    let normalized_language = language
+      .as_deref()
+      .map(normalize_language)
+      .filter(|value| !value.is_empty())
+      .unwrap_or_else(|| detect_language(&content));
+    let category_name = category
+      .as_deref()
+      .and_then(|value| normalize_category_name(value).ok())
+      .unwrap_or_else(|| UNCATEGORIZED_CATEGORY_NAME.to_string());
+    let category_id = ensure_category(&transaction, &category_name)?;
+    let params_json = encode_params(prompt_params)
+      .ok()
+      .flatten()
+      .unwrap_or_else(|| "{}".to_string());
+    let variables_schema_json = variables_schema
+      .and_then(|schema| normalize_variables_schema(&content, schema).ok())
+      .and_then(|(schema, _)| serde_json::to_string(&schema).ok())
+      .unwrap_or_else(|| "[]".to_string());
+    let mut normalized_tags = normalize_tags(tags.unwrap_or_default());
+    if let Some(warning) = cap_tags(&mut normalized_tags, max_tag_count) {
+      warnings.push(format!("「{normalized_title}」{warning}"));
+    }
+    let tags_json = encode_tags(&normalized_tags);
+    let created_at = normalize_import_timestamp(
+      created_at,
+      &format!("「{normalized_title}」的 createdAt"),
+      &mut warnings,
+    )
+    .unwrap_or_else(now_iso);
+    let updated_at = normalize_import_timestamp(
+      updated_at,
+      &format!("「{normalized_title}」的 updatedAt"),
+      &mut warnings,
+    )
+    .unwrap_or_else(|| created_at.clone());
+    let score_count = score_count.unwrap_or(0).max(0);
+    let score_avg = if score_count == 0 {
+      0.0
+    } else {
+      score_avg.unwrap_or(0.0)
+    };
 
-  if let Some(prompt_id) = id {
-    let previous_content = connection
-      .query_row(
-        "SELECT content FROM prompts WHERE id = ?1",
-        params![prompt_id],
-        |row| row.get::<_, String>(0),
-      )
-      .optional()
-      .map_err(|error| error.to_string())?;
+    if let Some(existing_id) = existing_id {
+      transaction
+        .execute(
+          "UPDATE prompts SET content = ?1, content_compressed = 0, tags = ?2, updated_at = ?3 WHERE id = ?4",
+          params![&content, tags_json, now_iso(), existing_id],
+        )
+        .map_err(AppError::from)?;
+      compress_stored_content(&transaction, existing_id, &content, compression_threshold)?;
+      if uuid_match.is_some() {
+        transaction
+          .execute(
+            "UPDATE prompts SET title = ?1, description = ?2, is_favorite = ?3 WHERE id = ?4",
+            params![
+              normalized_title,
+              normalized_description,
+              if is_favorite.unwrap_or(false) { 1 } else { 0 },
+              existing_id
+            ],
+          )
+          .map_err(AppError::from)?;
+      }
+      for version in versions.unwrap_or_default() {
+        if version.content.trim().is_empty() {
+          continue;
+        }
+        let version_created_at = normalize_import_timestamp(
+          version.created_at,
+          &format!("「{normalized_title}」的版本 createdAt"),
+          &mut warnings,
+        )
+        .unwrap_or_else(now_iso);
+        insert_prompt_version(
+          &transaction,
+          existing_id,
+          &version.content,
+          &version
+            .change_note
+            .unwrap_or_else(|| "imported version".to_string()),
+          &version_created_at,
+        )?;
+      }
+      if latest_version_hash(&transaction, existing_id)?.as_deref()
+        != Some(content_hash(&content).as_str())
+      {
+        insert_prompt_version(&transaction, existing_id, &content, "imported", &now_iso())?;
+      }
 
-    let Some(old_content) = previous_content else {
-      return Err("指定的 Prompt 不存在".to_string());
-    };
+      imported_ids.insert(item_index, existing_id);
+      updated_count += 1;
+      continue;
+    }
 
-    connection
+    transaction
       .execute(
         "
-        UPDATE prompts
-        SET title = ?1, content = ?2, tags = ?3, is_favorite = ?4, updated_at = ?5
-        WHERE id = ?6
+        INSERT INTO prompts (title, content, tags, is_favorite, score_avg, score_count, created_at, updated_at, description, source_url, model, language, params, icon, category_id, example_output, variables_schema, author, uuid)
+        VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14, ?15, ?16, ?17, ?18, ?19)
         ",
         params![
           normalized_title,
           &content,
           tags_json,
-          if is_favorite { 1 } else { 0 },
-          timestamp,
-          prompt_id
+          if is_favorite.unwrap_or(false) { 1 } else { 0 },
+          score_avg,
+          score_count,
+          created_at,
+          updated_at,
+          normalized_description,
+          source_url.unwrap_or_default(),
+          model.as_deref().map(normalize_model).unwrap_or_default(),
+          normalized_language,
+          params_json,
+          icon
+            .as_deref()
+            .and_then(|value| normalize_icon(value).ok())
+            .unwrap_or_default(),
+          category_id,
+          example_output.unwrap_or_default().trim(),
+          variables_schema_json,
+          author
+            .as_deref()
+            .and_then(|value| normalize_author(value).ok())
+            .unwrap_or_default(),
+          uuid.unwrap_or_default()
         ],
       )
-      .map_err(|error| error.to_string())?;
+      .map_err(AppError::from)?;
 
-    if old_content != content || !note.is_empty() {
-      let version_note = if note.is_empty() {
-        "content updated".to_string()
-      } else {
-        note.clone()
+    let prompt_id = transaction.last_insert_rowid();
+    compress_stored_content(&transaction, prompt_id, &content, compression_threshold)?;
+    let meta_entries = meta
+      .unwrap_or_default()
+      .into_iter()
+      .filter_map(|(key, value)| normalize_meta_key(&key).ok().map(|key| (key, value)))
+      .take(MAX_META_KEYS_PER_PROMPT);
+    for (key, value) in meta_entries {
+      upsert_prompt_meta(&transaction, prompt_id, &key, &value)?;
+    }
+    for preset in variable_presets.unwrap_or_default() {
+      let (Ok(name), Value::Object(values)) = (normalize_preset_name(&preset.name), preset.values)
+      else {
+        continue;
+      };
+      upsert_variable_preset(&transaction, prompt_id, &name, &values)?;
+    }
+    for test_case in test_cases.unwrap_or_default() {
+      let (Ok(name), Value::Object(variables)) = (
+        normalize_test_case_name(&test_case.name),
+        test_case.variables,
+      ) else {
+        continue;
       };
-      insert_prompt_version(&connection, prompt_id, &content, &version_note, &timestamp)?;
+      insert_test_case(&transaction, prompt_id, &name, &variables)?;
+    }
+    if let Some(usage_logs) = usage_logs.filter(|usage_logs| !usage_logs.is_empty()) {
+      for usage_log in usage_logs {
+        let input_vars = match usage_log.input_vars {
+          Value::Object(values) => Value::Object(values),
+          _ => Value::Object(Map::new()),
+        };
+        let (output_text, original_length) =
+          truncate_output_text(&usage_log.output_text, output_text_limit);
+        transaction
+          .execute(
+            "
+            INSERT INTO usage_logs (prompt_id, input_vars, output_text, rating, used_at, output_original_length)
+            VALUES (?1, ?2, ?3, ?4, ?5, ?6)
+            ",
+            params![
+              prompt_id,
+              serde_json::to_string(&input_vars).map_err(AppError::from)?,
+              output_text,
+              usage_log.rating.filter(|rating| (1..=5).contains(rating)),
+              usage_log.used_at.unwrap_or_else(now_iso),
+              original_length
+            ],
+          )
+          .map_err(AppError::from)?;
+      }
+      transaction
+        .execute(
+          "UPDATE prompts SET last_used_at = (SELECT MAX(used_at) FROM usage_logs WHERE prompt_id = ?1) WHERE id = ?1",
+          params![prompt_id],
+        )
+        .map_err(AppError::from)?;
+    }
+    adopt_unrecorded_ratings(&transaction, prompt_id)?;
+    recompute_prompt_score(&transaction, prompt_id)?;
+
+    let mut inserted_version = false;
+    if let Some(version_items) = versions {
+      for version in version_items {
+        if version.content.trim().is_empty() {
+          continue;
+        }
+        let version_created_at = normalize_import_timestamp(
+          version.created_at,
+          &format!("「{normalized_title}」的版本 createdAt"),
+          &mut warnings,
+        )
+        .unwrap_or_else(now_iso);
+        insert_prompt_version(
+          &transaction,
+          prompt_id,
+          &version.content,
+          &version
+            .change_note
+            .unwrap_or_else(|| "imported version".to_string()),
+          &version_created_at,
+        )?;
+        inserted_version = true;
+      }
     }
 
-    return fetch_prompt(&connection, prompt_id)?
-      .ok_or_else(|| "读取更新后的 Prompt 失败".to_string());
+    if !inserted_version {
+      insert_prompt_version(&transaction, prompt_id, &content, "imported", &now_iso())?;
+    }
+
+    imported_ids.insert(item_index, prompt_id);
+    imported_count += 1;
   }
+  on_progress(ImportProgress {
+    processed: total,
+    total,
+  });
 
-  connection
-    .execute(
-      "
-      INSERT INTO prompts (title, content, tags, is_favorite, score_avg, score_count, created_at, updated_at)
-      VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)
-      ",
-      params![
-        normalized_title,
-        &content,
-        tags_json,
-        if is_favorite { 1 } else { 0 },
-        0.0_f64,
-        0_i64,
-        timestamp,
-        timestamp
-      ],
-    )
-    .map_err(|error| error.to_string())?;
+  for snippet in snippets {
+    let Ok(snippet_name) = normalize_snippet_name(&snippet.name) else {
+      continue;
+    };
+    let timestamp = now_iso();
+    transaction
+      .execute(
+        "
+        INSERT INTO snippets (name, content, created_at, updated_at)
+        VALUES (?1, ?2, ?3, ?3)
+        ON CONFLICT(name) DO NOTHING
+        ",
+        params![snippet_name, snippet.content, timestamp],
+      )
+      .map_err(AppError::from)?;
+  }
 
-  let prompt_id = connection.last_insert_rowid();
-  let initial_note = if note.is_empty() {
-    "initial version".to_string()
-  } else {
-    note
-  };
-  insert_prompt_version(&connection, prompt_id, &content, &initial_note, &timestamp)?;
+  let mut deleted_count = 0_i64;
+  for tombstone in tombstones {
+    let Some(uuid) = normalize_uuid(Some(tombstone.uuid)) else {
+      continue;
+    };
+    deleted_count += transaction
+      .execute("DELETE FROM prompts WHERE uuid = ?1", params![uuid])
+      .map_err(AppError::from)? as i64;
+  }
 
-  fetch_prompt(&connection, prompt_id)?.ok_or_else(|| "读取新建 Prompt 失败".to_string())
-}
+  let mut imported_collection_ids: HashMap<usize, i64> = HashMap::new();
+  for (collection_index, collection) in collections.into_iter().enumerate() {
+    let Ok(collection_name) = normalize_collection_name(&collection.name) else {
+      continue;
+    };
+    let parent_id = collection
+      .parent_index
+      .filter(|parent_index| *parent_index < collection_index)
+      .and_then(|parent_index| imported_collection_ids.get(&parent_index).copied());
+    let collection_id = ensure_collection(&transaction, &collection_name, parent_id)?;
+    imported_collection_ids.insert(collection_index, collection_id);
+    for prompt_index in collection.prompt_indexes {
+      if let Some(prompt_id) = imported_ids.get(&prompt_index) {
+        add_prompt_to_collection(&transaction, collection_id, *prompt_id)?;
+      }
+    }
+  }
 
-#[tauri::command]
-fn delete_prompt(state: tauri::State<'_, AppState>, id: i64) -> Result<(), String> {
-  let connection = open_connection(&state.db_path)?;
-  connection
-    .execute("DELETE FROM prompts WHERE id = ?1", params![id])
-    .map_err(|error| error.to_string())?;
-  Ok(())
+  transaction.commit().map_err(AppError::from)?;
+  Ok(ImportResult {
+    cancelled: false,
+    imported: imported_count,
+    skipped: skipped_items.len() as i64,
+    updated: updated_count,
+    deleted: deleted_count,
+    skipped_items,
+    skipped_files: Vec::new(),
+    warnings,
+  })
 }
 
 #[tauri::command]
-fn log_prompt_usage(state: tauri::State<'_, AppState>, input: LogUsageInput) -> Result<(), String> {
-  if let Some(score) = input.rating {
-    if !(1..=5).contains(&score) {
-      return Err("评分范围必须在 1 到 5 之间".to_string());
+fn cancel_import(state: tauri::State<'_, AppState>, job_id: Option<u64>) -> Result<(), AppError> {
+  let import_jobs = state
+    .import_jobs
+    .lock()
+    .unwrap_or_else(|poisoned| poisoned.into_inner());
+  match job_id {
+    Some(job_id) => {
+      let cancel_flag = import_jobs
+        .get(&job_id)
+        .ok_or_else(|| AppError::NotFound(format!("导入任务不存在或已结束: {job_id}")))?;
+      cancel_flag.store(true, Ordering::Relaxed);
+    }
+    None => {
+      for cancel_flag in import_jobs.values() {
+        cancel_flag.store(true, Ordering::Relaxed);
+      }
     }
   }
+  Ok(())
+}
 
-  let connection = open_connection(&state.db_path)?;
-  let now = now_iso();
-  let input_vars_json = serde_json::to_string(&input.input_vars).map_err(|error| error.to_string())?;
-
-  connection
-    .execute(
-      "
-      INSERT INTO usage_logs (prompt_id, input_vars, output_text, rating, used_at)
-      VALUES (?1, ?2, ?3, ?4, ?5)
-      ",
-      params![input.prompt_id, input_vars_json, input.output_text, input.rating, now],
-    )
-    .map_err(|error| error.to_string())?;
+fn emit_import_progress(app: &AppHandle, job_id: u64, progress: ImportProgress) {
+  if let Err(error) = app.emit(
+    IMPORT_PROGRESS_EVENT,
+    ImportProgressEvent { job_id, progress },
+  ) {
+    log::warn!("failed to emit import progress: {error}");
+  }
+}
 
-  if let Some(score) = input.rating {
-    let score_state = connection
-      .query_row(
-        "SELECT score_avg, score_count FROM prompts WHERE id = ?1",
-        params![input.prompt_id],
-        |row| Ok((row.get::<_, f64>(0)?, row.get::<_, i64>(1)?)),
-      )
-      .optional()
-      .map_err(|error| error.to_string())?;
+fn register_import_job(state: &AppState) -> (u64, Arc<AtomicBool>) {
+  let job_id = state.next_import_job_id.fetch_add(1, Ordering::Relaxed) + 1;
+  let cancel_flag = Arc::new(AtomicBool::new(false));
+  state
+    .import_jobs
+    .lock()
+    .unwrap_or_else(|poisoned| poisoned.into_inner())
+    .insert(job_id, Arc::clone(&cancel_flag));
+  (job_id, cancel_flag)
+}
 
-    let Some((score_avg, score_count)) = score_state else {
-      return Err("记录使用日志失败：Prompt 不存在".to_string());
-    };
+fn finish_import_job(state: &AppState, job_id: u64) {
+  state
+    .import_jobs
+    .lock()
+    .unwrap_or_else(|poisoned| poisoned.into_inner())
+    .remove(&job_id);
+}
 
-    let next_count = score_count + 1;
-    let next_avg = ((score_avg * score_count as f64) + score as f64) / next_count as f64;
-    connection
-      .execute(
-        "
-        UPDATE prompts
-        SET score_avg = ?1, score_count = ?2, updated_at = ?3
-        WHERE id = ?4
-        ",
-        params![next_avg, next_count, now_iso(), input.prompt_id],
-      )
-      .map_err(|error| error.to_string())?;
-  }
+async fn execute_import_job(
+  app: AppHandle,
+  db_path: PathBuf,
+  job_id: u64,
+  cancel_flag: Arc<AtomicBool>,
+  parsed_import: ParsedImport,
+  options: ImportOptions,
+) -> Result<ImportResult, AppError> {
+  tauri::async_runtime::spawn_blocking(move || {
+    let mut connection = open_connection(&db_path)?;
+    import_prompt_items(
+      &mut connection,
+      parsed_import,
+      &options,
+      &cancel_flag,
+      |progress| emit_import_progress(&app, job_id, progress),
+    )
+  })
+  .await
+  .map_err(|error| AppError::Database(format!("导入任务异常终止: {error}")))?
+}
 
-  Ok(())
+async fn run_import_job(
+  app: AppHandle,
+  state: &AppState,
+  parsed_import: ParsedImport,
+  options: ImportOptions,
+) -> Result<ImportResult, AppError> {
+  let (job_id, cancel_flag) = register_import_job(state);
+  let result = execute_import_job(
+    app,
+    state.db_path.clone(),
+    job_id,
+    cancel_flag,
+    parsed_import,
+    options,
+  )
+  .await;
+  finish_import_job(state, job_id);
+  result
 }
 
 #[tauri::command]
-fn export_prompts_json(state: tauri::State<'_, AppState>) -> Result<String, String> {
-  let connection = open_connection(&state.db_path)?;
-  let mut statement = connection
-    .prepare(
-      "
-      SELECT id, title, content, tags, is_favorite, score_avg, score_count, created_at, updated_at
-      FROM prompts
-      ORDER BY updated_at DESC
-      ",
+async fn start_import(
+  app: AppHandle,
+  state: tauri::State<'_, AppState>,
+  json_data: String,
+  options: Option<ImportOptions>,
+) -> Result<u64, AppError> {
+  let parsed_import = parse_json_or_yaml_import(&json_data)?;
+  let job_state = state.inner().clone();
+  let (job_id, cancel_flag) = register_import_job(&job_state);
+
+  tauri::async_runtime::spawn(async move {
+    let outcome = execute_import_job(
+      app.clone(),
+      job_state.db_path.clone(),
+      job_id,
+      cancel_flag,
+      parsed_import,
+      options.unwrap_or_default(),
     )
-    .map_err(|error| error.to_string())?;
+    .await;
+    finish_import_job(&job_state, job_id);
 
-  let rows = statement
-    .query_map([], row_to_prompt)
-    .map_err(|error| error.to_string())?;
+    let (result, error) = match outcome {
+      Ok(result) => (Some(result), None),
+      Err(error) => (None, Some(error)),
+    };
+    if let Err(error) = app.emit(
+      IMPORT_FINISHED_EVENT,
+      ImportFinishedEvent {
+        job_id,
+        result,
+        error,
+      },
+    ) {
+      log::warn!("failed to emit import finished: {error}");
+    }
+  });
 
-  let mut export_prompts = Vec::new();
-  for row in rows {
-    let prompt = row.map_err(|error| error.to_string())?;
-    let versions = fetch_prompt_versions(&connection, prompt.id)?
-      .into_iter()
-      .map(|version| ExportVersionItem {
-        content: version.content,
-        change_note: version.change_note,
-        created_at: version.created_at,
-      })
-      .collect::<Vec<_>>();
+  Ok(job_id)
+}
 
-    export_prompts.push(ExportPromptItem {
-      title: prompt.title,
-      content: prompt.content,
-      tags: prompt.tags,
-      is_favorite: prompt.is_favorite,
-      score_avg: prompt.score_avg,
-      score_count: prompt.score_count,
-      versions,
-    });
-  }
+#[tauri::command]
+async fn import_prompts_json(
+  app: AppHandle,
+  state: tauri::State<'_, AppState>,
+  json_data: String,
+  options: Option<ImportOptions>,
+) -> Result<ImportResult, AppError> {
+  let parsed_import = parse_json_or_yaml_import(&json_data)?;
+  run_import_job(app, &state, parsed_import, options.unwrap_or_default()).await
+}
 
-  let payload = ExportPayload {
-    exported_at: now_iso(),
-    prompts: export_prompts,
-  };
-  serde_json::to_string_pretty(&payload).map_err(|error| error.to_string())
+#[tauri::command]
+fn preview_import(
+  state: tauri::State<'_, AppState>,
+  json_data: String,
+  options: Option<ImportOptions>,
+) -> Result<ImportPreview, AppError> {
+  let parsed_import = parse_json_or_yaml_import(&json_data)?;
+  let connection = open_connection(&state.db_path)?;
+  preview_import_items(&connection, &parsed_import, &options.unwrap_or_default())
 }
 
 #[tauri::command]
-fn import_prompts_json(
+async fn import_prompts_awesome(
+  app: AppHandle,
   state: tauri::State<'_, AppState>,
   json_data: String,
-) -> Result<ImportResult, String> {
-  let parsed_payload: ImportPayload =
-    serde_json::from_str(&json_data).map_err(|error| format!("JSON 解析失败: {error}"))?;
+  options: Option<ImportOptions>,
+) -> Result<ImportResult, AppError> {
+  let parsed_import = parse_awesome_import(&json_data)?;
+  run_import_job(app, &state, parsed_import, options.unwrap_or_default()).await
+}
 
-  let items = match parsed_payload {
-    ImportPayload::Wrapped { prompts } => prompts,
-    ImportPayload::Flat(prompts) => prompts,
+#[tauri::command]
+async fn import_prompts_auto(
+  app: AppHandle,
+  state: tauri::State<'_, AppState>,
+  data: String,
+  options: Option<ImportOptions>,
+) -> Result<ImportResult, AppError> {
+  let content = data.trim_start_matches('\u{feff}');
+  let parsed_import = match detect_import_format(content)? {
+    "json" => parse_json_import(content)?,
+    "csv" => parse_csv_import(content)?,
+    _ => parse_yaml_import(content)?,
   };
 
-  let mut connection = open_connection(&state.db_path)?;
-  let transaction = connection
-    .transaction()
-    .map_err(|error| error.to_string())?;
-
-  let mut imported_count = 0_i64;
-
-  for item in items {
-    let ImportPromptItem {
-      title,
-      content,
-      tags,
-      is_favorite,
-      score_avg,
-      score_count,
-      versions,
-    } = item;
+  run_import_job(app, &state, parsed_import, options.unwrap_or_default()).await
+}
 
-    let normalized_title = title.trim().to_string();
-    if normalized_title.is_empty() || content.trim().is_empty() {
-      continue;
+fn open_import_file(path: &Path) -> Result<BufReader<fs::File>, AppError> {
+  let file = fs::File::open(path).map_err(|error| {
+    if error.kind() == std::io::ErrorKind::NotFound {
+      AppError::NotFound(format!("导入文件不存在: {}", path.display()))
+    } else {
+      AppError::Io(format!("无法读取导入文件 {}: {error}", path.display()))
     }
+  })?;
+  let mut reader = BufReader::new(file);
+  let has_bom = reader
+    .fill_buf()
+    .map_err(|error| AppError::Io(format!("无法读取导入文件 {}: {error}", path.display())))?
+    .starts_with("\u{feff}".as_bytes());
+  if has_bom {
+    reader.consume(3);
+  }
+  Ok(reader)
+}
 
-    let normalized_tags = normalize_tags(tags.unwrap_or_default());
-    let tags_json = encode_tags(&normalized_tags);
-    let created_at = now_iso();
-    let score_count = score_count.unwrap_or(0).max(0);
-    let score_avg = if score_count == 0 {
-      0.0
+fn parse_json_import_reader(reader: impl Read, path: &Path) -> Result<ParsedImport, AppError> {
+  let payload: ImportPayload = serde_json::from_reader(reader).map_err(|error| {
+    if error.is_io() {
+      AppError::Io(format!("无法读取导入文件 {}: {error}", path.display()))
     } else {
-      score_avg.unwrap_or(0.0)
-    };
+      AppError::Serialization(format!(
+        "JSON 解析失败（第 {} 行，第 {} 列）: {error}",
+        error.line(),
+        error.column()
+      ))
+    }
+  })?;
+  if let ImportPayload::Wrapped {
+    schema_version,
+    app_version,
+    ..
+  } = &payload
+  {
+    check_import_schema_version(schema_version.as_ref(), app_version.as_ref())?;
+  }
+  Ok(import_payload_items(payload))
+}
+
+fn parse_import_file(path: &Path, format: Option<&str>) -> Result<ParsedImport, AppError> {
+  let mut reader = open_import_file(path)?;
+  let extension = path
+    .extension()
+    .and_then(|extension| extension.to_str())
+    .map(str::to_lowercase);
+  let format = match format.map(|format| format.trim().to_lowercase()) {
+    Some(format) if !format.is_empty() => format,
+    _ => match extension.as_deref() {
+      Some("json") => "json".to_string(),
+      Some("csv") => "csv".to_string(),
+      Some("yaml") | Some("yml") => "yaml".to_string(),
+      _ => String::new(),
+    },
+  };
+
+  if format == "json" {
+    return parse_json_import_reader(reader, path);
+  }
+
+  let mut content = String::new();
+  reader
+    .read_to_string(&mut content)
+    .map_err(|error| AppError::Io(format!("无法读取导入文件 {}: {error}", path.display())))?;
+  let format = if format.is_empty() {
+    detect_import_format(&content)?.to_string()
+  } else {
+    format
+  };
+
+  match format.as_str() {
+    "json" => parse_json_import(&content),
+    "csv" => parse_csv_import(&content),
+    "yaml" | "yml" => parse_yaml_import(&content),
+    "awesome" => parse_awesome_import(&content),
+    _ => Err(AppError::Validation(format!(
+      "不支持的导入格式：{format}（可选 json、yaml、csv、awesome）"
+    ))),
+  }
+}
+
+#[tauri::command]
+async fn import_prompts_from_file(
+  app: AppHandle,
+  state: tauri::State<'_, AppState>,
+  path: String,
+  options: Option<ImportFileOptions>,
+) -> Result<ImportResult, AppError> {
+  let path = PathBuf::from(path.trim());
+  if path.as_os_str().is_empty() {
+    return Err(AppError::Validation("导入路径不能为空".to_string()));
+  }
 
-    transaction
-      .execute(
-        "
-        INSERT INTO prompts (title, content, tags, is_favorite, score_avg, score_count, created_at, updated_at)
-        VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)
-        ",
-        params![
-          normalized_title,
-          &content,
-          tags_json,
-          if is_favorite.unwrap_or(false) { 1 } else { 0 },
-          score_avg,
-          score_count,
-          created_at,
-          created_at
-        ],
-      )
-      .map_err(|error| error.to_string())?;
+  let options = options.unwrap_or_default();
+  let parsed_import = parse_import_file(&path, options.format.as_deref())?;
+  run_import_job(app, &state, parsed_import, options.import).await
+}
 
-    let prompt_id = transaction.last_insert_rowid();
-    let mut inserted_version = false;
-    if let Some(version_items) = versions {
-      for version in version_items {
-        if version.content.trim().is_empty() {
-          continue;
-        }
-        transaction
-          .execute(
-            "
-            INSERT INTO prompt_versions (prompt_id, content, change_note, created_at)
-            VALUES (?1, ?2, ?3, ?4)
-            ",
-            params![
-              prompt_id,
-              version.content,
-              version.change_note.unwrap_or_else(|| "imported version".to_string()),
-              version.created_at.unwrap_or_else(now_iso)
-            ],
-          )
-          .map_err(|error| error.to_string())?;
-        inserted_version = true;
+fn collect_markdown_files(
+  dir: &Path,
+  recursive: bool,
+  files: &mut Vec<PathBuf>,
+) -> Result<(), AppError> {
+  let entries = fs::read_dir(dir)
+    .map_err(|error| AppError::Io(format!("读取目录 {} 失败: {error}", dir.display())))?;
+  for entry in entries {
+    let path = entry
+      .map_err(|error| AppError::Io(format!("读取目录 {} 失败: {error}", dir.display())))?
+      .path();
+    if path.is_dir() {
+      if recursive {
+        collect_markdown_files(&path, recursive, files)?;
       }
+    } else if path
+      .extension()
+      .and_then(|extension| extension.to_str())
+      .is_some_and(|extension| extension.eq_ignore_ascii_case("md"))
+    {
+      files.push(path);
     }
+  }
+  Ok(())
+}
 
-    if !inserted_version {
-      transaction
-        .execute(
-          "
-          INSERT INTO prompt_versions (prompt_id, content, change_note, created_at)
-          VALUES (?1, ?2, ?3, ?4)
-          ",
-          params![prompt_id, &content, "imported", now_iso()],
-        )
-        .map_err(|error| error.to_string())?;
+fn parse_markdown_prompt(path: &Path) -> Result<ImportPromptItem, String> {
+  let raw = fs::read_to_string(path).map_err(|error| format!("读取文件失败: {error}"))?;
+  let text = raw.trim_start_matches('\u{feff}').replace("\r\n", "\n");
+  let (frontmatter, body) = match text.strip_prefix("---\n") {
+    Some(rest) => {
+      let (yaml, body) = if let Some(body) = rest.strip_prefix("---\n") {
+        ("", body)
+      } else {
+        rest
+          .split_once("\n---\n")
+          .or_else(|| rest.strip_suffix("\n---").map(|yaml| (yaml, "")))
+          .ok_or_else(|| "Frontmatter 缺少结束标记 ---".to_string())?
+      };
+      let frontmatter = if yaml.trim().is_empty() {
+        PromptFrontmatter::default()
+      } else {
+        serde_yaml::from_str::<PromptFrontmatter>(yaml)
+          .map_err(|error| format!("Frontmatter 解析失败: {error}"))?
+      };
+      (frontmatter, body.strip_prefix('\n').unwrap_or(body))
     }
+    None => (PromptFrontmatter::default(), text.as_str()),
+  };
 
-    imported_count += 1;
+  if body.trim().is_empty() {
+    return Err("正文为空".to_string());
+  }
+  let title = if frontmatter.title.trim().is_empty() {
+    path
+      .file_stem()
+      .map(|stem| stem.to_string_lossy().to_string())
+      .unwrap_or_default()
+  } else {
+    frontmatter.title
+  };
+
+  Ok(ImportPromptItem {
+    title,
+    content: body.to_string(),
+    tags: Some(frontmatter.tags),
+    is_favorite: Some(frontmatter.favorite),
+    created_at: frontmatter.created,
+    updated_at: frontmatter.updated,
+    ..ImportPromptItem::default()
+  })
+}
+
+#[tauri::command]
+async fn import_prompts_from_directory(
+  app: AppHandle,
+  state: tauri::State<'_, AppState>,
+  dir: String,
+  options: Option<DirectoryImportOptions>,
+) -> Result<ImportResult, AppError> {
+  let source_dir = PathBuf::from(dir.trim());
+  if source_dir.as_os_str().is_empty() {
+    return Err(AppError::Validation("导入目录不能为空".to_string()));
+  }
+  if !source_dir.is_dir() {
+    return Err(AppError::NotFound(format!(
+      "导入目录不存在: {}",
+      source_dir.display()
+    )));
+  }
+
+  let options = options.unwrap_or_default();
+  let mut files = Vec::new();
+  collect_markdown_files(&source_dir, options.recursive, &mut files)?;
+  files.sort();
+
+  let mut items = Vec::new();
+  let mut skipped_files = Vec::new();
+  for file in files {
+    match parse_markdown_prompt(&file) {
+      Ok(item) => items.push(item),
+      Err(reason) => skipped_files.push(SkippedImportFile {
+        path: file.display().to_string(),
+        reason,
+      }),
+    }
   }
 
-  transaction.commit().map_err(|error| error.to_string())?;
+  let parsed_import = ParsedImport {
+    items,
+    ..ParsedImport::default()
+  };
+  let result = run_import_job(app, &state, parsed_import, options.import).await?;
   Ok(ImportResult {
-    imported: imported_count,
+    skipped_files,
+    ..result
   })
 }
 
@@ -852,6 +9251,7 @@ pub fn run() {
 
   tauri::Builder::default()
     .plugin(global_shortcut_plugin)
+    .plugin(tauri_plugin_clipboard_manager::init())
     .setup(|app| {
       if cfg!(debug_assertions) {
         app.handle().plugin(
@@ -877,23 +9277,1045 @@ pub fn run() {
         }
       }
 
-      app.manage(AppState { db_path });
+      let backup_db_path = db_path.clone();
+      app.manage(AppState {
+        db_path,
+        import_jobs: Arc::new(Mutex::new(HashMap::new())),
+        next_import_job_id: Arc::new(AtomicU64::new(0)),
+        delete_tokens: Arc::new(Mutex::new(HashMap::new())),
+      });
+
+      tauri::async_runtime::spawn_blocking(move || match run_auto_backup_if_due(&backup_db_path) {
+        Ok(Some(backup_path)) => {
+          log::info!("auto backup written: {}", backup_path.display());
+        }
+        Ok(None) => {}
+        Err(error) => {
+          log::warn!("auto backup failed: {error}");
+        }
+      });
 
       Ok(())
     })
     .invoke_handler(tauri::generate_handler![
       get_global_shortcut,
       update_global_shortcut,
+      get_database_info,
+      app_ready,
       list_prompts,
+      list_recent_prompts,
+      list_top_prompts,
+      list_unused_prompts,
       list_tags,
+      get_tag_details,
+      get_tag_report,
+      list_pinned_tags,
+      pin_tag,
+      unpin_tag,
+      list_models,
+      list_languages,
+      rebuild_search_index,
+      list_categories,
+      create_category,
+      rename_category,
+      delete_category,
+      list_collections,
+      create_collection,
+      rename_collection,
+      move_collection,
+      delete_collection,
+      add_prompts_to_collection,
+      remove_prompts_from_collection,
+      move_prompts_to_collection,
+      move_prompt_to_collection,
+      reorder_collections,
+      reorder_collection_prompts,
+      list_smart_collections,
+      create_smart_collection,
+      update_smart_collection,
+      delete_smart_collection,
+      get_smart_collection_prompts,
       get_prompt,
+      get_prompt_detail,
+      get_last_input_vars,
+      set_prompt_example,
+      render_prompt_markdown_html,
+      render_prompt_template,
+      copy_rendered_prompt,
+      update_usage_log,
+      extract_template_variables,
+      get_prompt_variables,
+      lint_prompt_template,
       list_prompt_versions,
+      reorder_version,
+      list_all_versions,
       upsert_prompt,
-      delete_prompt,
+      split_prompt,
+      set_prompt_icon,
+      get_prompt_meta,
+      set_prompt_meta,
+      delete_prompt_meta,
+      request_delete_prompt,
+      confirm_delete_prompt,
+      add_comment,
+      list_comments,
+      delete_comment,
+      save_variable_preset,
+      list_variable_presets,
+      delete_variable_preset,
+      list_prompt_test_cases,
+      create_prompt_test_case,
+      update_prompt_test_case,
+      delete_prompt_test_case,
+      run_prompt_test_cases,
+      list_snippets,
+      create_snippet,
+      update_snippet,
+      delete_snippet,
+      find_prompts_using_snippet,
       log_prompt_usage,
+      log_usage_batch,
+      rate_prompt,
+      get_usage_log_limit,
+      update_usage_log_limit,
+      get_output_text_limit,
+      update_output_text_limit,
+      get_content_compression_threshold,
+      update_content_compression_threshold,
+      get_auto_backup_config,
+      configure_auto_backup,
+      get_max_tag_count,
+      update_max_tag_count,
+      get_content_normalization,
+      update_content_normalization,
+      get_rating_trend,
+      export_prompts,
       export_prompts_json,
-      import_prompts_json
+      export_prompts_yaml,
+      export_prompts_zip,
+      export_prompts_to_file,
+      export_prompt_as_template,
+      export_usage_logs_csv,
+      export_prompts_markdown,
+      export_prompts_to_directory,
+      export_collection_json,
+      import_prompts_json,
+      import_prompts_awesome,
+      import_prompts_auto,
+      import_prompts_from_file,
+      preview_import,
+      import_prompts_from_directory,
+      start_import,
+      library_fingerprint,
+      stream_prompts,
+      rename_prompt,
+      cancel_import
     ])
     .run(tauri::generate_context!())
     .expect("error while running tauri application");
 }
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn prompt_input(title: &str, content: &str, tags: &[&str]) -> SavePromptInput {
+    SavePromptInput {
+      title: title.to_string(),
+      content: content.to_string(),
+      tags: tags.iter().map(|tag| tag.to_string()).collect(),
+      ..SavePromptInput::default()
+    }
+  }
+
+  fn create_prompt(
+    connection: &Connection,
+    title: &str,
+    content: &str,
+    tags: &[&str],
+  ) -> PromptRecord {
+    save_prompt(connection, prompt_input(title, content, tags))
+      .expect("failed to save prompt")
+      .prompt
+  }
+
+  #[test]
+  fn init_in_memory_applies_every_migration() {
+    let connection = init_in_memory();
+    let user_version: i64 = connection
+      .query_row("PRAGMA user_version", [], |row| row.get(0))
+      .unwrap();
+    assert_eq!(user_version, SCHEMA_MIGRATIONS.len() as i64);
+  }
+
+  #[test]
+  fn save_prompt_creates_prompt_with_initial_version() {
+    let connection = init_in_memory();
+    let prompt = create_prompt(&connection, "Greeting", "Hello {{name}}", &["demo"]);
+    assert_eq!(prompt.title, "Greeting");
+    assert_eq!(prompt.tags, vec!["demo".to_string()]);
+    assert!(!prompt.uuid.is_empty());
+
+    let versions = fetch_prompt_versions(&connection, prompt.id).unwrap();
+    assert_eq!(versions.len(), 1);
+    assert_eq!(versions[0].change_note, "initial version");
+  }
+
+  #[test]
+  fn save_prompt_skips_version_for_unchanged_content() {
+    let connection = init_in_memory();
+    let prompt = create_prompt(&connection, "Greeting", "Hello {{name}}", &[]);
+    let mut update = prompt_input("Greeting v2", "Hello {{name}}", &[]);
+    update.id = Some(prompt.id);
+    let updated = save_prompt(&connection, update).unwrap().prompt;
+
+    assert_eq!(updated.title, "Greeting v2");
+    assert_eq!(
+      fetch_prompt_versions(&connection, prompt.id).unwrap().len(),
+      1
+    );
+  }
+
+  #[test]
+  fn list_prompts_with_applies_filter_input() {
+    let connection = init_in_memory();
+    let tagged = create_prompt(&connection, "Summarize", "Summarize {{text}}", &["work"]);
+    create_prompt(
+      &connection,
+      "Translate",
+      "Translate {{text}}",
+      &["personal"],
+    );
+    upsert_prompt_meta(&connection, tagged.id, "team", "docs").unwrap();
+
+    let input: ListPromptsInput =
+      serde_json::from_value(serde_json::json!({ "tag": "work", "sortBy": "title" })).unwrap();
+    let by_tag = list_prompts_with(&connection, input).unwrap();
+    assert_eq!(by_tag.len(), 1);
+    assert_eq!(by_tag[0].id, tagged.id);
+
+    let input: ListPromptsInput =
+      serde_json::from_value(serde_json::json!({ "metadataFilters": [["Team", "docs"]] })).unwrap();
+    let by_meta = list_prompts_with(&connection, input).unwrap();
+    assert_eq!(
+      by_meta.iter().map(|prompt| prompt.id).collect::<Vec<_>>(),
+      vec![tagged.id]
+    );
+  }
+
+  fn tag_list(tags: &[&str]) -> Vec<String> {
+    tags.iter().map(|tag| tag.to_string()).collect()
+  }
+
+  #[test]
+  fn aggregate_tag_counts_folds_mixed_casing_across_prompts() {
+    let tag_items = aggregate_tag_counts(vec![
+      (tag_list(&["Work"]), true),
+      (tag_list(&["work"]), false),
+      (tag_list(&["Work", "rust"]), false),
+      (tag_list(&["WORK", "work"]), true),
+    ]);
+
+    assert_eq!(tag_items.len(), 2);
+    assert_eq!(tag_items[0].name, "Work");
+    assert_eq!(tag_items[0].count, 4);
+    assert_eq!(tag_items[0].favorite_count, 2);
+    assert_eq!(tag_items[1].name, "rust");
+    assert_eq!(tag_items[1].count, 1);
+  }
+
+  #[test]
+  fn aggregate_tag_counts_breaks_casing_ties_deterministically() {
+    let tag_items = aggregate_tag_counts(vec![
+      (tag_list(&["rust"]), false),
+      (tag_list(&["Rust"]), false),
+    ]);
+
+    assert_eq!(tag_items.len(), 1);
+    assert_eq!(tag_items[0].name, "Rust");
+    assert_eq!(tag_items[0].count, 2);
+  }
+
+  #[test]
+  fn load_tag_infos_merges_casings_stored_in_database() {
+    let connection = init_in_memory();
+    create_prompt(&connection, "One", "first", &["AI", "Draft"]);
+    create_prompt(&connection, "Two", "second", &["ai"]);
+    create_prompt(&connection, "Three", "third", &["ai", "DRAFT"]);
+
+    let tag_items = load_tag_infos(&connection).unwrap();
+    let counts = tag_items
+      .iter()
+      .map(|tag_item| (tag_item.name.as_str(), tag_item.count))
+      .collect::<Vec<_>>();
+    assert_eq!(counts, vec![("ai", 3), ("DRAFT", 2)]);
+  }
+
+  #[test]
+  fn search_index_ignores_updates_to_unindexed_columns() {
+    let connection = init_in_memory();
+    let prompt = create_prompt(&connection, "Indexed", "searchable body", &[]);
+    connection
+      .execute(
+        "UPDATE prompts_fts SET title = 'stale' WHERE rowid = ?1",
+        params![prompt.id],
+      )
+      .unwrap();
+
+    connection
+      .execute(
+        "UPDATE prompts SET score_avg = 4.5, icon = '*', last_used_at = ?1 WHERE id = ?2",
+        params![now_iso(), prompt.id],
+      )
+      .unwrap();
+    let untouched: String = connection
+      .query_row(
+        "SELECT title FROM prompts_fts WHERE rowid = ?1",
+        params![prompt.id],
+        |row| row.get(0),
+      )
+      .unwrap();
+    assert_eq!(untouched, "stale");
+
+    connection
+      .execute(
+        "UPDATE prompts SET title = 'Renamed' WHERE id = ?1",
+        params![prompt.id],
+      )
+      .unwrap();
+    let reindexed: String = connection
+      .query_row(
+        "SELECT title FROM prompts_fts WHERE rowid = ?1",
+        params![prompt.id],
+        |row| row.get(0),
+      )
+      .unwrap();
+    assert_eq!(reindexed, "Renamed");
+  }
+
+  #[test]
+  fn search_matches_content_of_compressed_prompts() {
+    let connection = init_in_memory();
+    write_setting(&connection, CONTENT_COMPRESSION_SETTING_KEY, "32").unwrap();
+    let body = format!(
+      "{} needle-phrase {}",
+      "lorem ipsum ".repeat(20),
+      "dolor ".repeat(20)
+    );
+    let prompt = create_prompt(&connection, "Large", &body, &[]);
+    let compressed: i64 = connection
+      .query_row(
+        "SELECT content_compressed FROM prompts WHERE id = ?1",
+        params![prompt.id],
+        |row| row.get(0),
+      )
+      .unwrap();
+    assert_eq!(compressed, 1);
+
+    let search = |connection: &Connection| {
+      let input: ListPromptsInput =
+        serde_json::from_value(serde_json::json!({ "search": "needle-phrase" })).unwrap();
+      list_prompts_with(connection, input)
+        .unwrap()
+        .into_iter()
+        .map(|prompt| prompt.id)
+        .collect::<Vec<_>>()
+    };
+    assert_eq!(search(&connection), vec![prompt.id]);
+
+    connection
+      .execute(
+        "UPDATE prompts SET title = 'Large renamed' WHERE id = ?1",
+        params![prompt.id],
+      )
+      .unwrap();
+    assert_eq!(search(&connection), vec![prompt.id]);
+
+    rebuild_prompt_search_index(&connection).unwrap();
+    assert_eq!(search(&connection), vec![prompt.id]);
+  }
+
+  #[test]
+  fn save_prompt_atomically_waits_for_held_write_lock() {
+    let nanos = std::time::SystemTime::now()
+      .duration_since(std::time::UNIX_EPOCH)
+      .unwrap()
+      .as_nanos();
+    let db_path = std::env::temp_dir().join(format!(
+      "prompt-library-lock-{}-{nanos}.db",
+      std::process::id()
+    ));
+    let mut setup = open_connection(&db_path).unwrap();
+    initialize_schema(&mut setup).unwrap();
+    drop(setup);
+
+    let holder = open_connection(&db_path).unwrap();
+    holder.execute_batch("BEGIN IMMEDIATE").unwrap();
+    holder
+      .execute(
+        "INSERT INTO app_settings (key, value, updated_at) VALUES ('lock_probe', '1', CURRENT_TIMESTAMP)",
+        [],
+      )
+      .unwrap();
+    let release = std::thread::spawn(move || {
+      std::thread::sleep(Duration::from_millis(300));
+      holder.execute_batch("COMMIT").unwrap();
+    });
+
+    let result = save_prompt_atomically(&db_path, &prompt_input("Locked", "body", &[]));
+    release.join().unwrap();
+    let saved = result.expect("save should succeed once the lock is released");
+
+    let connection = open_connection(&db_path).unwrap();
+    let prompt_count: i64 = connection
+      .query_row("SELECT COUNT(*) FROM prompts", [], |row| row.get(0))
+      .unwrap();
+    assert_eq!(prompt_count, 1);
+    let versions = fetch_prompt_versions(&connection, saved.prompt.id).unwrap();
+    assert_eq!(versions.len(), 1);
+
+    connection
+      .execute_batch(
+        "CREATE TRIGGER reject_versions BEFORE INSERT ON prompt_versions
+         BEGIN SELECT RAISE(ABORT, 'rejected'); END;",
+      )
+      .unwrap();
+    assert!(save_prompt_atomically(&db_path, &prompt_input("Partial", "body", &[])).is_err());
+    let prompt_count: i64 = connection
+      .query_row("SELECT COUNT(*) FROM prompts", [], |row| row.get(0))
+      .unwrap();
+    assert_eq!(prompt_count, 1);
+    drop(connection);
+
+    for suffix in ["", "-wal", "-shm", "-journal"] {
+      let _ = std::fs::remove_file(format!("{}{suffix}", db_path.display()));
+    }
+  }
+
+  #[test]
+  fn delete_token_expires_after_ttl() {
+    let connection = init_in_memory();
+    let prompt = create_prompt(&connection, "Doomed", "body", &[]);
+    let delete_tokens = Mutex::new(HashMap::new());
+    let issued_at = Instant::now();
+    let confirmation =
+      issue_delete_token(&connection, &delete_tokens, prompt.id, issued_at).unwrap();
+
+    let error = consume_delete_token(
+      &delete_tokens,
+      prompt.id,
+      &confirmation.token,
+      issued_at + DELETE_TOKEN_TTL + Duration::from_secs(1),
+    )
+    .unwrap_err();
+    assert!(matches!(error, AppError::Validation(message) if message.contains("已过期")));
+    assert!(
+      consume_delete_token(&delete_tokens, prompt.id, &confirmation.token, issued_at).is_err()
+    );
+  }
+
+  #[test]
+  fn delete_token_rejects_wrong_token() {
+    let connection = init_in_memory();
+    let prompt = create_prompt(&connection, "Doomed", "body", &[]);
+    let delete_tokens = Mutex::new(HashMap::new());
+    let issued_at = Instant::now();
+    let confirmation =
+      issue_delete_token(&connection, &delete_tokens, prompt.id, issued_at).unwrap();
+
+    let error =
+      consume_delete_token(&delete_tokens, prompt.id, "not-the-token", issued_at).unwrap_err();
+    assert!(matches!(error, AppError::Validation(message) if message.contains("无效")));
+    consume_delete_token(&delete_tokens, prompt.id, &confirmation.token, issued_at)
+      .expect("the issued token should still be accepted");
+    assert!(
+      consume_delete_token(&delete_tokens, prompt.id, &confirmation.token, issued_at).is_err()
+    );
+  }
+
+  #[test]
+  fn escaped_braces_round_trip_through_scan_render_and_lint() {
+    let content = "Hi {{name}}! Write \\{{placeholder}} or \\{\\{ topic }} to show literal braces.";
+
+    let scanned = scan_template_variables(content);
+    let names: Vec<&str> = scanned
+      .variables
+      .iter()
+      .map(|variable| variable.name.as_str())
+      .collect();
+    assert_eq!(names, vec!["name"]);
+    assert!(scanned.warnings.is_empty());
+
+    let mut values = Map::new();
+    values.insert("name".to_string(), Value::String("Ada".to_string()));
+    let rendered = render_template(content, &values, false).unwrap();
+    assert_eq!(
+      rendered.text,
+      "Hi Ada! Write {{placeholder}} or {{ topic }} to show literal braces."
+    );
+    assert!(rendered.missing_variables.is_empty());
+
+    let findings = lint_template(content, &[]);
+    assert!(
+      findings.iter().all(|finding| finding.code != "strayBrace"),
+      "unexpected findings: {findings:?}"
+    );
+  }
+
+  #[test]
+  fn lint_still_flags_unmatched_close_after_escaped_open() {
+    let findings = lint_template("\\{{literal}} and then }}", &[]);
+    let stray: Vec<_> = findings
+      .iter()
+      .filter(|finding| finding.code == "strayBrace")
+      .collect();
+    assert_eq!(stray.len(), 1);
+    assert_eq!(stray[0].start, Some(22));
+  }
+
+  #[test]
+  fn trigram_similarity_scores_identical_and_disjoint_strings() {
+    assert_eq!(trigram_similarity("write a haiku", "write a haiku"), 1.0);
+    assert_eq!(trigram_similarity("abc", "xyz"), 0.0);
+    let partial = trigram_similarity("write a haiku", "write a sonnet");
+    assert!(
+      partial > 0.0 && partial < 1.0,
+      "partial overlap scored {partial}"
+    );
+  }
+
+  #[test]
+  fn trigram_similarity_handles_empty_input() {
+    assert_eq!(trigram_similarity("", "summarize this"), 0.0);
+    assert_eq!(trigram_similarity("summarize this", ""), 0.0);
+    assert_eq!(trigram_similarity("", "   "), 1.0);
+  }
+
+  #[test]
+  fn trigram_similarity_folds_case_and_whitespace() {
+    assert_eq!(
+      trigram_similarity("Translate  the\tText", "translate the text\n"),
+      1.0
+    );
+  }
+
+  #[test]
+  fn trigram_similarity_compares_cjk_by_character() {
+    assert_eq!(trigram_similarity("提示词管理", "提示词管理"), 1.0);
+    assert_eq!(trigram_similarity("你好世界", "再见朋友"), 0.0);
+    let partial = trigram_similarity("提示词管理", "提示词管理工具");
+    assert!(
+      partial > 0.5 && partial < 1.0,
+      "partial overlap scored {partial}"
+    );
+  }
+
+  #[test]
+  fn normalize_prompt_content_unifies_mixed_line_endings() {
+    assert_eq!(
+      normalize_prompt_content("first\r\nsecond\rthird\nfourth"),
+      "first\nsecond\nthird\nfourth"
+    );
+    assert_eq!(normalize_prompt_content("a\r\n\r\nb\r"), "a\n\nb\n");
+  }
+
+  #[test]
+  fn normalize_prompt_content_trims_trailing_whitespace_per_line() {
+    assert_eq!(
+      normalize_prompt_content("keep  inner  spaces \t\n\tindent stays\t \r\nlast  "),
+      "keep  inner  spaces\n\tindent stays\nlast"
+    );
+  }
+
+  #[test]
+  fn normalize_prompt_content_preserves_blank_lines() {
+    assert_eq!(
+      normalize_prompt_content("intro\n\n  \n\t\nbody\n\n"),
+      "intro\n\n\n\nbody\n\n"
+    );
+  }
+
+  #[test]
+  fn cap_tags_keeps_tags_at_or_below_limit() {
+    let mut below = tag_list(&["a", "b"]);
+    assert_eq!(cap_tags(&mut below, 3), None);
+    assert_eq!(below, tag_list(&["a", "b"]));
+
+    let mut at_limit = tag_list(&["a", "b", "c"]);
+    assert_eq!(cap_tags(&mut at_limit, 3), None);
+    assert_eq!(at_limit, tag_list(&["a", "b", "c"]));
+  }
+
+  #[test]
+  fn cap_tags_truncates_above_limit_with_warning() {
+    let mut above = tag_list(&["a", "b", "c", "d"]);
+    let warning = cap_tags(&mut above, 3).expect("exceeding the limit should warn");
+    assert_eq!(above, tag_list(&["a", "b", "c"]));
+    assert!(warning.contains('4') && warning.contains('3'), "{warning}");
+  }
+
+  #[test]
+  fn cap_tags_treats_zero_as_unlimited() {
+    let mut tags: Vec<String> = (0..50).map(|index| format!("tag{index}")).collect();
+    assert_eq!(cap_tags(&mut tags, 0), None);
+    assert_eq!(tags.len(), 50);
+  }
+
+  const MULTILINE_CHINESE_CONTENT: &str =
+    "第一行：请总结以下内容。\n第二行：保持简洁，使用「{{tone}}」语气。\n\n  - 要点一\n  - 要点二";
+
+  fn import_into_fresh_library(data: &str) -> Vec<PromptRecord> {
+    let parsed_import = parse_json_or_yaml_import(data).expect("failed to parse import");
+    let mut connection = init_in_memory();
+    import_prompt_items(
+      &mut connection,
+      parsed_import,
+      &ImportOptions::default(),
+      &AtomicBool::new(false),
+      |_| {},
+    )
+    .expect("failed to import prompts");
+    let ids: Vec<i64> = connection
+      .prepare("SELECT id FROM prompts ORDER BY id")
+      .unwrap()
+      .query_map([], |row| row.get(0))
+      .unwrap()
+      .collect::<Result<_, _>>()
+      .unwrap();
+    ids
+      .into_iter()
+      .map(|id| fetch_prompt(&connection, id).unwrap().unwrap())
+      .collect()
+  }
+
+  #[test]
+  fn yaml_export_round_trips_multiline_chinese_content() {
+    let connection = init_in_memory();
+    create_prompt(
+      &connection,
+      "会议纪要",
+      MULTILINE_CHINESE_CONTENT,
+      &["中文", "总结"],
+    );
+    let yaml = export_prompts_as(&connection, "yaml", &ExportOptions::default()).unwrap();
+
+    let imported = import_into_fresh_library(&yaml);
+    assert_eq!(imported.len(), 1);
+    assert_eq!(imported[0].title, "会议纪要");
+    assert_eq!(imported[0].content, MULTILINE_CHINESE_CONTENT);
+    assert_eq!(imported[0].tags, tag_list(&["中文", "总结"]));
+  }
+
+  #[test]
+  fn json_export_converted_to_yaml_imports_identically() {
+    let connection = init_in_memory();
+    create_prompt(
+      &connection,
+      "会议纪要",
+      MULTILINE_CHINESE_CONTENT,
+      &["中文"],
+    );
+    let json = export_prompts_as(&connection, "json", &ExportOptions::default()).unwrap();
+    let yaml = serde_yaml::to_string(&serde_json::from_str::<Value>(&json).unwrap()).unwrap();
+
+    let from_json = import_into_fresh_library(&json);
+    let from_yaml = import_into_fresh_library(&yaml);
+    assert_eq!(from_yaml.len(), 1);
+    assert_eq!(from_yaml[0].title, from_json[0].title);
+    assert_eq!(from_yaml[0].content, MULTILINE_CHINESE_CONTENT);
+    assert_eq!(from_yaml[0].content, from_json[0].content);
+    assert_eq!(from_yaml[0].tags, from_json[0].tags);
+  }
+
+  #[test]
+  fn parse_json_or_yaml_import_reports_yaml_error_for_non_json_input() {
+    let error = parse_json_or_yaml_import("prompts:\n  - title: [未闭合").unwrap_err();
+    assert!(
+      matches!(&error, AppError::Serialization(message) if message.starts_with("YAML")),
+      "{error:?}"
+    );
+  }
+
+  #[test]
+  fn library_fingerprint_ignores_insertion_order_and_tag_order() {
+    let forward = init_in_memory();
+    create_prompt(
+      &forward,
+      "Summarize",
+      "Summarize {{text}}",
+      &["work", "ai", "daily"],
+    );
+    create_prompt(&forward, "Translate", "Translate {{text}}", &["lang", "ai"]);
+    create_prompt(&forward, "Review", "Review {{diff}}", &[]);
+
+    let reversed = init_in_memory();
+    create_prompt(&reversed, "Review", "Review {{diff}}", &[]);
+    create_prompt(
+      &reversed,
+      "Translate",
+      "Translate {{text}}",
+      &["ai", "lang"],
+    );
+    create_prompt(
+      &reversed,
+      "Summarize",
+      "Summarize {{text}}",
+      &["daily", "ai", "work"],
+    );
+
+    assert_eq!(
+      compute_library_fingerprint(&forward).unwrap(),
+      compute_library_fingerprint(&reversed).unwrap()
+    );
+
+    create_prompt(&reversed, "Extra", "Another prompt", &[]);
+    assert_ne!(
+      compute_library_fingerprint(&forward).unwrap(),
+      compute_library_fingerprint(&reversed).unwrap()
+    );
+  }
+
+  #[test]
+  fn parse_json_import_reader_reads_wrapped_and_legacy_payloads() {
+    let path = Path::new("prompts.json");
+    let wrapped = r#"{"schemaVersion": 3, "appVersion": "1.0.0", "prompts": [{"title": "A", "content": "a"}], "snippets": [{"name": "sig", "content": "-- me"}]}"#;
+    let parsed = parse_json_import_reader(wrapped.as_bytes(), path).unwrap();
+    assert_eq!(parsed.items.len(), 1);
+    assert_eq!(parsed.items[0].title, "A");
+    assert_eq!(parsed.snippets.len(), 1);
+
+    let legacy = r#"[{"title": "B", "content": "b"}, {"title": "C", "content": "c"}]"#;
+    let parsed = parse_json_import_reader(legacy.as_bytes(), path).unwrap();
+    let titles: Vec<&str> = parsed
+      .items
+      .iter()
+      .map(|item| item.title.as_str())
+      .collect();
+    assert_eq!(titles, vec!["B", "C"]);
+  }
+
+  #[test]
+  fn parse_json_import_reader_rejects_newer_schema_version() {
+    let payload = format!(
+      r#"{{"schemaVersion": {}, "appVersion": "9.9.9", "prompts": []}}"#,
+      EXPORT_SCHEMA_VERSION + 1
+    );
+    let error =
+      parse_json_import_reader(payload.as_bytes(), Path::new("prompts.json")).unwrap_err();
+    assert!(
+      matches!(&error, AppError::Validation(message) if message.contains("v9.9.9") && message.contains("请升级应用")),
+      "{error:?}"
+    );
+  }
+
+  #[test]
+  fn list_helpers_run_against_a_plain_connection() {
+    let connection = init_in_memory();
+    let first = create_prompt(&connection, "First", "one", &[]);
+    let second = create_prompt(&connection, "Second", "two", &[]);
+    connection
+      .execute(
+        "UPDATE prompts SET updated_at = '2000-01-01T00:00:00Z' WHERE id = ?1",
+        params![first.id],
+      )
+      .unwrap();
+
+    let recent = list_recent_prompts_with(&connection, 10).unwrap();
+    let recent_ids: Vec<i64> = recent.iter().map(|prompt| prompt.id).collect();
+    assert_eq!(recent_ids, vec![second.id, first.id]);
+
+    assert_eq!(list_unused_prompts_with(&connection).unwrap().len(), 2);
+    assert_eq!(list_all_versions_with(&connection, 10, 0).unwrap().total, 2);
+    assert!(list_comments_with(&connection, first.id)
+      .unwrap()
+      .is_empty());
+  }
+
+  fn usage_item(prompt_id: i64, input_vars: Value, rating: Option<i64>) -> LogUsageInput {
+    LogUsageInput {
+      prompt_id,
+      input_vars,
+      output_text: String::new(),
+      rating,
+      check_variables: None,
+      validate: None,
+    }
+  }
+
+  #[test]
+  fn record_usage_batch_rejects_invalid_items_without_logging() {
+    let mut connection = init_in_memory();
+    let spec: VariableSpec =
+      serde_json::from_value(serde_json::json!({ "name": "topic", "required": true })).unwrap();
+    let prompt = save_prompt(
+      &connection,
+      SavePromptInput {
+        variables_schema: Some(vec![spec]),
+        ..prompt_input("Essay", "Write about {{topic}}", &[])
+      },
+    )
+    .unwrap()
+    .prompt;
+
+    let non_object = usage_item(prompt.id, Value::String("topic".to_string()), None);
+    assert!(record_usage_batch(&mut connection, &[non_object]).is_err());
+
+    let mut checked = usage_item(prompt.id, serde_json::json!({ "topic": "rust" }), None);
+    checked.check_variables = Some(true);
+    assert!(record_usage_batch(&mut connection, &[checked]).is_err());
+
+    let mut validated = usage_item(prompt.id, serde_json::json!({}), None);
+    validated.validate = Some(true);
+    let valid = usage_item(prompt.id, serde_json::json!({ "topic": "rust" }), Some(4));
+    let error = record_usage_batch(&mut connection, &[valid.clone(), validated]).unwrap_err();
+    assert!(
+      matches!(&error, AppError::Validation(message) if message.contains("第 2 条")),
+      "{error:?}"
+    );
+
+    let logged: i64 = connection
+      .query_row("SELECT COUNT(*) FROM usage_logs", [], |row| row.get(0))
+      .unwrap();
+    assert_eq!(logged, 0);
+
+    assert_eq!(record_usage_batch(&mut connection, &[valid]).unwrap(), 1);
+    let prompt = fetch_prompt(&connection, prompt.id).unwrap().unwrap();
+    assert_eq!(prompt.score_count, 1);
+  }
+
+  #[test]
+  fn direct_ratings_survive_usage_score_recompute() {
+    let mut connection = init_in_memory();
+    let prompt = create_prompt(&connection, "Rated", "body", &[]);
+    assert!(record_direct_rating(&connection, prompt.id, 5).unwrap());
+    assert!(record_direct_rating(&connection, prompt.id, 3).unwrap());
+    assert!(!record_direct_rating(&connection, prompt.id + 1, 4).unwrap());
+
+    let rated = fetch_prompt(&connection, prompt.id).unwrap().unwrap();
+    assert_eq!(rated.score_count, 2);
+    assert_eq!(rated.score_avg, 4.0);
+    assert_eq!(rated.updated_at, prompt.updated_at);
+
+    let item = usage_item(prompt.id, serde_json::json!({}), Some(1));
+    record_usage_batch(&mut connection, &[item]).unwrap();
+    let recomputed = fetch_prompt(&connection, prompt.id).unwrap().unwrap();
+    assert_eq!(recomputed.score_count, 3);
+    assert_eq!(recomputed.score_avg, 3.0);
+
+    connection.execute("DELETE FROM usage_logs", []).unwrap();
+    recompute_prompt_score(&connection, prompt.id).unwrap();
+    let without_logs = fetch_prompt(&connection, prompt.id).unwrap().unwrap();
+    assert_eq!(without_logs.score_count, 2);
+    assert_eq!(without_logs.score_avg, 4.0);
+  }
+
+  #[test]
+  fn markdown_preview_uses_the_template_renderer() {
+    let connection = init_in_memory();
+    let prompt = create_prompt(
+      &connection,
+      "Preview",
+      "# Hi {{name}}\n\n{{#if extra}}Hidden{{/if}}Write \\{{literal}} as is.",
+      &[],
+    );
+
+    let html = render_prompt_preview_html(
+      &connection,
+      prompt.id,
+      Some(serde_json::json!({ "name": "Ada" })),
+    )
+    .unwrap();
+    assert!(html.contains("Hi Ada"), "{html}");
+    assert!(
+      !html.contains("Hidden") && !html.contains("{{#if"),
+      "{html}"
+    );
+    assert!(html.contains("{{literal}}"), "{html}");
+
+    assert!(render_prompt_preview_html(&connection, prompt.id, Some(Value::Bool(true))).is_err());
+  }
+
+  #[test]
+  fn tag_details_use_the_same_casing_as_tag_counts() {
+    let connection = init_in_memory();
+    create_prompt(&connection, "One", "first", &["AI", "Draft"]);
+    create_prompt(&connection, "Two", "second", &["ai"]);
+    create_prompt(&connection, "Three", "third", &["ai", "DRAFT"]);
+
+    let mut detail_names: Vec<String> = collect_tag_details(&connection)
+      .unwrap()
+      .into_iter()
+      .map(|details| details.name)
+      .collect();
+    detail_names.sort();
+    let mut info_names: Vec<String> = load_tag_infos(&connection)
+      .unwrap()
+      .into_iter()
+      .map(|tag_item| tag_item.name)
+      .collect();
+    info_names.sort();
+    assert_eq!(detail_names, vec!["DRAFT".to_string(), "ai".to_string()]);
+    assert_eq!(detail_names, info_names);
+  }
+
+  #[test]
+  fn database_size_includes_the_wal_file() {
+    let nanos = std::time::SystemTime::now()
+      .duration_since(std::time::UNIX_EPOCH)
+      .unwrap()
+      .as_nanos();
+    let db_path = std::env::temp_dir().join(format!(
+      "prompt-library-size-{}-{nanos}.db",
+      std::process::id()
+    ));
+    let mut connection = open_connection(&db_path).unwrap();
+    connection
+      .query_row("PRAGMA journal_mode = WAL", [], |_| Ok(()))
+      .unwrap();
+    initialize_schema(&mut connection).unwrap();
+    create_prompt(&connection, "Sized", "body", &[]);
+
+    let main_size = fs::metadata(&db_path).unwrap().len();
+    let wal_size = fs::metadata(format!("{}-wal", db_path.display()))
+      .unwrap()
+      .len();
+    assert!(wal_size > 0);
+    assert_eq!(database_size_bytes(&db_path).unwrap(), main_size + wal_size);
+    drop(connection);
+
+    for suffix in ["", "-wal", "-shm"] {
+      let _ = fs::remove_file(format!("{}{suffix}", db_path.display()));
+    }
+    assert!(matches!(
+      database_size_bytes(&db_path),
+      Err(AppError::Io(_))
+    ));
+  }
+
+  #[test]
+  fn sorted_export_value_orders_nested_keys() {
+    let mut inner = Map::new();
+    inner.insert("zeta".to_string(), Value::from(1));
+    inner.insert("alpha".to_string(), Value::from(2));
+    let mut outer = Map::new();
+    outer.insert(
+      "prompts".to_string(),
+      Value::Array(vec![Value::Object(inner)]),
+    );
+    outer.insert("appVersion".to_string(), Value::from("1.0.0"));
+
+    let output = serde_json::to_string(&SortedExportValue::from(Value::Object(outer))).unwrap();
+    assert_eq!(
+      output,
+      r#"{"appVersion":"1.0.0","prompts":[{"alpha":2,"zeta":1}]}"#
+    );
+  }
+
+  #[test]
+  fn stable_exports_are_byte_identical() {
+    let connection = init_in_memory();
+    create_prompt(&connection, "Beta", "second {{topic}}", &["b", "a"]);
+    create_prompt(&connection, "Alpha", "first", &["中文"]);
+    let options = ExportOptions {
+      stable: true,
+      ..ExportOptions::default()
+    };
+
+    for format in ["json", "yaml"] {
+      let first = export_prompts_as(&connection, format, &options).unwrap();
+      let second = export_prompts_as(&connection, format, &options).unwrap();
+      assert_eq!(
+        first.as_bytes(),
+        second.as_bytes(),
+        "{format} export changed"
+      );
+    }
+  }
+
+  #[test]
+  fn detect_import_format_requires_a_prompt_shaped_yaml_document() {
+    assert_eq!(
+      detect_import_format("prompts:\n  - title: A\n    content: a").unwrap(),
+      "yaml"
+    );
+    assert_eq!(
+      detect_import_format("---\n# exported\n- title: A\n  content: a").unwrap(),
+      "yaml"
+    );
+    assert_eq!(
+      detect_import_format("schemaVersion: 3\nappVersion: 1.0.0\nprompts: []").unwrap(),
+      "yaml"
+    );
+    assert_eq!(detect_import_format("title,content\nA,a").unwrap(), "csv");
+
+    for unknown in [
+      "http://example.com/prompts",
+      "Note:foo",
+      "- just a list item",
+    ] {
+      assert!(
+        matches!(detect_import_format(unknown), Err(AppError::Validation(_))),
+        "{unknown} should not be detected"
+      );
+    }
+  }
+
+  #[test]
+  fn detect_import_format_accepts_yaml_exports() {
+    let connection = init_in_memory();
+    create_prompt(&connection, "Exported", "body", &[]);
+    let yaml = export_prompts_as(&connection, "yaml", &ExportOptions::default()).unwrap();
+    assert_eq!(detect_import_format(&yaml).unwrap(), "yaml");
+  }
+
+  #[test]
+  fn imported_version_timestamps_are_normalized_to_utc() {
+    let data = r#"{
+      "schemaVersion": 3,
+      "prompts": [{
+        "title": "Versioned",
+        "content": "v3",
+        "versions": [
+          { "content": "v1", "changeNote": "first", "createdAt": "2024-01-02T03:00:00+08:00" },
+          { "content": "v2", "changeNote": "second", "createdAt": "2024-01-01T20:00:00Z" },
+          { "content": "v3", "changeNote": "third", "createdAt": "not a timestamp" }
+        ]
+      }]
+    }"#;
+    let parsed_import = parse_json_or_yaml_import(data).unwrap();
+    let mut connection = init_in_memory();
+    let result = import_prompt_items(
+      &mut connection,
+      parsed_import,
+      &ImportOptions::default(),
+      &AtomicBool::new(false),
+      |_| {},
+    )
+    .unwrap();
+    assert!(
+      result
+        .warnings
+        .iter()
+        .any(|warning| warning.contains("版本 createdAt") && warning.contains("not a timestamp")),
+      "{:?}",
+      result.warnings
+    );
+
+    let prompt_id: i64 = connection
+      .query_row("SELECT id FROM prompts", [], |row| row.get(0))
+      .unwrap();
+    let versions = fetch_prompt_versions(&connection, prompt_id).unwrap();
+    let imported: Vec<(&str, &str)> = versions
+      .iter()
+      .filter(|version| version.content != "v3")
+      .map(|version| (version.content.as_str(), version.created_at.as_str()))
+      .collect();
+    assert_eq!(
+      imported,
+      vec![
+        ("v2", "2024-01-01T20:00:00+00:00"),
+        ("v1", "2024-01-01T19:00:00+00:00")
+      ]
+    );
+  }
+}
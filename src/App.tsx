@@ -7,6 +7,7 @@ type SortBy = "updated" | "score" | "created";
 
 const DEFAULT_GLOBAL_SHORTCUT = "CommandOrControl+Shift+K";
 const GLOBAL_SHORTCUT_EVENT = "global-shortcut-triggered";
+const IMPORT_PROGRESS_EVENT = "import-progress";
 
 type PromptRecord = {
   id: number;
@@ -18,6 +19,37 @@ type PromptRecord = {
   scoreCount: number;
   createdAt: string;
   updatedAt: string;
+  description: string;
+  sourceUrl: string;
+  author: string;
+  model: string;
+  language: string;
+  params: Record<string, unknown>;
+  icon: string;
+  categoryId: number | null;
+  meta: Record<string, string>;
+  charCount: number;
+  wordCount: number;
+  estimatedTokens: number;
+  variablesSchema: VariableSpec[];
+  lastUsedAt: string | null;
+  collections?: { id: number; name: string }[];
+  uuid: string;
+};
+
+type VariableSpec = {
+  name: string;
+  label: string;
+  description?: string;
+  type: "text" | "multiline" | "number" | "select";
+  default?: string;
+  required: boolean;
+  options?: string[];
+};
+
+type UpsertPromptResult = {
+  prompt: PromptRecord;
+  warnings: string[];
 };
 
 type PromptVersionRecord = {
@@ -26,15 +58,44 @@ type PromptVersionRecord = {
   content: string;
   changeNote: string;
   createdAt: string;
+  charCount: number;
+  wordCount: number;
+  estimatedTokens: number;
 };
 
 type TagInfo = {
   name: string;
   count: number;
+  favoriteCount: number;
+  pinned: boolean;
 };
 
 type ImportResult = {
+  cancelled: boolean;
   imported: number;
+  skipped: number;
+  updated: number;
+  deleted: number;
+  skippedItems?: { index: number; title: string; reason: string }[];
+  skippedFiles?: { path: string; reason: string }[];
+};
+
+type ImportProgress = {
+  jobId: number;
+  processed: number;
+  total: number;
+};
+
+type DeleteConfirmation = {
+  token: string;
+  expiresInSeconds: number;
+};
+
+type AppErrorKind = "notFound" | "validation" | "database" | "busy" | "serialization" | "io";
+
+type AppError = {
+  kind: AppErrorKind;
+  message: string;
 };
 
 type EditorState = {
@@ -106,6 +167,31 @@ function summarizeContent(content: string): string {
   return `${cleaned.slice(0, 90)}...`;
 }
 
+function isAppError(error: unknown): error is AppError {
+  return (
+    typeof error === "object" &&
+    error !== null &&
+    typeof (error as AppError).kind === "string" &&
+    typeof (error as AppError).message === "string"
+  );
+}
+
+function formatError(error: unknown): string {
+  if (!isAppError(error)) {
+    return String(error);
+  }
+  if (error.kind === "database") {
+    return `数据库错误：${error.message}`;
+  }
+  if (error.kind === "busy") {
+    return `数据库繁忙，请稍后重试：${error.message}`;
+  }
+  if (error.kind === "serialization") {
+    return `数据格式错误：${error.message}`;
+  }
+  return error.message;
+}
+
 function formatDate(value: string): string {
   const parsed = new Date(value);
   if (Number.isNaN(parsed.getTime())) {
@@ -171,7 +257,7 @@ function App() {
         setShortcutDraft(shortcut);
       })
       .catch((error) => {
-        setStatusMessage(`读取全局快捷键失败: ${String(error)}`);
+        setStatusMessage(`读取全局快捷键失败: ${formatError(error)}`);
       });
   }, []);
 
@@ -181,9 +267,11 @@ function App() {
         try {
           const [promptItems, tagItems] = await Promise.all([
             invoke<PromptRecord[]>("list_prompts", {
-              search: searchText.trim() || null,
-              tag: selectedTag || null,
-              sortBy,
+              filter: {
+                search: searchText.trim() || null,
+                tag: selectedTag || null,
+                sortBy,
+              },
             }),
             invoke<TagInfo[]>("list_tags"),
           ]);
@@ -197,7 +285,7 @@ function App() {
             setRating(null);
           }
         } catch (error) {
-          setStatusMessage(`列表刷新失败: ${String(error)}`);
+          setStatusMessage(`列表刷新失败: ${formatError(error)}`);
         }
       })();
     }, 120);
@@ -238,7 +326,7 @@ function App() {
         unlisten = fn;
       })
       .catch((error) => {
-        setStatusMessage(`全局快捷键监听失败: ${String(error)}`);
+        setStatusMessage(`全局快捷键监听失败: ${formatError(error)}`);
       });
 
     return () => {
@@ -258,13 +346,14 @@ function App() {
       void (async () => {
         try {
           const result = await invoke<PromptRecord[]>("list_prompts", {
-            search: paletteQuery.trim() || null,
-            tag: null,
-            sortBy: "updated",
+            filter: {
+              search: paletteQuery.trim() || null,
+              sortBy: "updated",
+            },
           });
           setPaletteItems(result.slice(0, 12));
         } catch (error) {
-          setStatusMessage(`快速面板查询失败: ${String(error)}`);
+          setStatusMessage(`快速面板查询失败: ${formatError(error)}`);
         }
       })();
     }, 100);
@@ -297,16 +386,18 @@ function App() {
       setRating(null);
       setStatusMessage(`已加载：${prompt.title}`);
     } catch (error) {
-      setStatusMessage(`读取详情失败: ${String(error)}`);
+      setStatusMessage(`读取详情失败: ${formatError(error)}`);
     }
   };
 
   const refreshListAndTags = async () => {
     const [promptItems, tagItems] = await Promise.all([
       invoke<PromptRecord[]>("list_prompts", {
-        search: searchText.trim() || null,
-        tag: selectedTag || null,
-        sortBy,
+        filter: {
+          search: searchText.trim() || null,
+          tag: selectedTag || null,
+          sortBy,
+        },
       }),
       invoke<TagInfo[]>("list_tags"),
     ]);
@@ -343,12 +434,14 @@ function App() {
         changeNote: editor.changeNote.trim() || null,
       };
 
-      const savedPrompt = await invoke<PromptRecord>("upsert_prompt", { input: payload });
+      const { prompt: savedPrompt, warnings } = await invoke<UpsertPromptResult>("upsert_prompt", {
+        input: payload,
+      });
       await refreshListAndTags();
       await selectPrompt(savedPrompt.id);
-      setStatusMessage("保存成功");
+      setStatusMessage(warnings.length > 0 ? `保存成功（${warnings.join("；")}）` : "保存成功");
     } catch (error) {
-      setStatusMessage(`保存失败: ${String(error)}`);
+      setStatusMessage(`保存失败: ${formatError(error)}`);
     } finally {
       setIsSaving(false);
     }
@@ -358,6 +451,14 @@ function App() {
     if (editor.id === null) {
       return;
     }
+
+    let confirmation: DeleteConfirmation;
+    try {
+      confirmation = await invoke<DeleteConfirmation>("request_delete_prompt", { id: editor.id });
+    } catch (error) {
+      setStatusMessage(`删除失败: ${formatError(error)}`);
+      return;
+    }
     const confirmed = window.confirm("确认删除这个 Prompt 吗？该操作不可恢复。");
     if (!confirmed) {
       return;
@@ -365,7 +466,7 @@ function App() {
 
     setIsSaving(true);
     try {
-      await invoke("delete_prompt", { id: editor.id });
+      await invoke("confirm_delete_prompt", { id: editor.id, token: confirmation.token });
       await refreshListAndTags();
       setEditor(createEmptyEditorState());
       setVersions([]);
@@ -373,7 +474,7 @@ function App() {
       setRating(null);
       setStatusMessage("删除成功");
     } catch (error) {
-      setStatusMessage(`删除失败: ${String(error)}`);
+      setStatusMessage(`删除失败: ${formatError(error)}`);
     } finally {
       setIsSaving(false);
     }
@@ -391,7 +492,7 @@ function App() {
       URL.revokeObjectURL(blobUrl);
       setStatusMessage("已导出 JSON");
     } catch (error) {
-      setStatusMessage(`导出失败: ${String(error)}`);
+      setStatusMessage(`导出失败: ${formatError(error)}`);
     }
   };
 
@@ -401,16 +502,25 @@ function App() {
       return;
     }
 
+    let unlistenProgress: (() => void) | null = null;
     try {
+      unlistenProgress = await listen<ImportProgress>(IMPORT_PROGRESS_EVENT, ({ payload }) => {
+        setStatusMessage(`正在导入 ${payload.processed}/${payload.total}`);
+      });
       const jsonContent = await selectedFile.text();
       const importResult = await invoke<ImportResult>("import_prompts_json", {
         jsonData: jsonContent,
       });
+      if (importResult.cancelled) {
+        setStatusMessage("导入已取消，所有更改均已回滚");
+        return;
+      }
       await refreshListAndTags();
       setStatusMessage(`导入完成，共 ${importResult.imported} 条`);
     } catch (error) {
-      setStatusMessage(`导入失败: ${String(error)}`);
+      setStatusMessage(`导入失败: ${formatError(error)}`);
     } finally {
+      unlistenProgress?.();
       event.target.value = "";
     }
   };
@@ -437,7 +547,7 @@ function App() {
       await refreshListAndTags();
       setStatusMessage("已复制并记录使用日志");
     } catch (error) {
-      setStatusMessage(`复制失败: ${String(error)}`);
+      setStatusMessage(`复制失败: ${formatError(error)}`);
     }
   };
 
@@ -473,7 +583,7 @@ function App() {
       setSettingsOpen(false);
       setStatusMessage(`全局快捷键已更新为 ${formatShortcutForDisplay(savedShortcut)}`);
     } catch (error) {
-      setStatusMessage(`更新全局快捷键失败: ${String(error)}`);
+      setStatusMessage(`更新全局快捷键失败: ${formatError(error)}`);
     } finally {
       setIsUpdatingShortcut(false);
     }